@@ -1,18 +1,29 @@
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use zip::write::SimpleFileOptions;
 use zip::ZipWriter;
 use tracing::info;
 
 use crate::errors::SealedResult;
 
-/// Create a ZIP archive of all sealed artifacts.
+/// Files that stay in the sealed directory but are left out of the archive.
+const SKIPPED_FILES: &[&str] = &["tile_index.json", "ots_upgrade.log"];
+
+/// Intermediate extraction directories (video frames, PDF pages).
+const SKIPPED_DIRS: &[&str] = &["frames", "pages"];
+
+/// Create a ZIP archive of all sealed artifacts, including per-item subdirectories.
 pub fn create_archive(
     output_dir: &Path,
     archive_name: &str,
-) -> SealedResult<std::path::PathBuf> {
+) -> SealedResult<PathBuf> {
     let archive_path = output_dir.join(format!("{}.zip", archive_name));
+
+    let mut entries = Vec::new();
+    collect_entries(output_dir, output_dir, &mut entries)?;
+    entries.sort();
+
     let file = File::create(&archive_path)?;
     let mut zip = ZipWriter::new(file);
 
@@ -20,29 +31,54 @@ pub fn create_archive(
         .compression_method(zip::CompressionMethod::Deflated)
         .unix_permissions(0o644);
 
-    for entry in fs::read_dir(output_dir)? {
+    for (entry_name, path) in entries {
+        let data = fs::read(&path)?;
+
+        zip.start_file(&entry_name, options)?;
+        zip.write_all(&data)?;
+
+        info!("Added to archive: {}", entry_name);
+    }
+
+    zip.finish()?;
+    info!("Archive created: {}", archive_path.display());
+
+    Ok(archive_path)
+}
+
+/// Recursively gather archivable files as (relative `/`-separated name, path) pairs.
+fn collect_entries(
+    root: &Path,
+    dir: &Path,
+    entries: &mut Vec<(String, PathBuf)>,
+) -> SealedResult<()> {
+    for entry in fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
 
-        if path.is_dir() || path.extension().map_or(false, |ext| ext == "zip") {
+        if path.is_dir() {
+            if !SKIPPED_DIRS.contains(&file_name.as_str()) {
+                collect_entries(root, &path, entries)?;
+            }
             continue;
         }
 
-        let file_name = path.file_name().unwrap().to_string_lossy().to_string();
-        if file_name == "tile_index.json" || file_name == "ots_upgrade.log" {
+        if path.extension().is_some_and(|ext| ext == "zip")
+            || SKIPPED_FILES.contains(&file_name.as_str())
+        {
             continue;
         }
 
-        let data = fs::read(&path)?;
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let entry_name = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
 
-        zip.start_file(&file_name, options)?;
-        zip.write_all(&data)?;
-
-        info!("Added to archive: {}", file_name);
+        entries.push((entry_name, path));
     }
 
-    zip.finish()?;
-    info!("Archive created: {}", archive_path.display());
-
-    Ok(archive_path)
+    Ok(())
 }
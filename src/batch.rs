@@ -0,0 +1,267 @@
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+
+use crate::canonical::to_canonical_json;
+use crate::errors::{SealedError, SealedResult};
+use crate::signing::SignedEnvelope;
+use crate::verification::SealedRecord;
+
+/// File name of the top-level manifest written for directory seals.
+pub const BATCH_MANIFEST_FILE: &str = "batch.json";
+
+/// File name of the signed envelope over `batch.json`.
+pub const SIGNED_BATCH_FILE: &str = "signed_batch.json";
+
+/// Domain-separation prefixes (RFC 6962 style) so a leaf can never pass as a node.
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// One sealed image within a directory seal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItem {
+    /// Subdirectory (relative to the sealed root) holding this item's artifacts.
+    pub dir: String,
+    /// File name of the source image that was sealed.
+    pub source: String,
+    /// SHA-256 of the source image's decoded pixels.
+    pub sha256: String,
+    /// Merkle leaf hash over the item's record in RFC 8785 form (hex), so
+    /// re-indenting `hashes.json` does not break its inclusion proof.
+    #[serde(default)]
+    pub leaf: String,
+}
+
+impl BatchItem {
+    /// Build an item, deriving its Merkle leaf from its hash record.
+    pub fn new<T: Serialize + ?Sized>(dir: &str, source: &str, sha256: &str, record: &T) -> SealedResult<Self> {
+        Ok(Self {
+            dir: dir.to_string(),
+            source: source.to_string(),
+            sha256: sha256.to_string(),
+            leaf: item_leaf(record)?,
+        })
+    }
+}
+
+/// Merkle leaf (hex) for an item's hash record.
+fn item_leaf<T: Serialize + ?Sized>(record: &T) -> SealedResult<String> {
+    Ok(hex::encode(leaf_hash(to_canonical_json(record)?.as_bytes())))
+}
+
+/// Which side of the running hash a proof sibling sits on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProofSide {
+    Left,
+    Right,
+}
+
+/// One step of a Merkle inclusion proof.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub side: ProofSide,
+    pub hash: String,
+}
+
+/// Inclusion proof tying one batch item to the batch Merkle root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub index: usize,
+    pub leaf: String,
+    pub steps: Vec<ProofStep>,
+    pub merkle_root: String,
+}
+
+/// Manifest mapping each subdirectory of a directory seal back to its source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchManifest {
+    pub items: Vec<BatchItem>,
+    /// Merkle root over every item's leaf, in manifest order (hex).
+    #[serde(default)]
+    pub merkle_root: String,
+    #[serde(default)]
+    pub sealed_at: String,
+    #[serde(default)]
+    pub sealed_version: String,
+}
+
+impl BatchManifest {
+    /// Empty manifest stamped with the current time and crate version.
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            merkle_root: String::new(),
+            sealed_at: chrono::Utc::now().to_rfc3339(),
+            sealed_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// Recompute `merkle_root` from the items' leaves.
+    pub fn compute_root(&mut self) -> SealedResult<String> {
+        let leaves = self.leaves()?;
+        self.merkle_root = hex::encode(merkle_root(&leaves));
+        Ok(self.merkle_root.clone())
+    }
+
+    /// Inclusion proof for the item at `index`.
+    pub fn inclusion_proof(&self, index: usize) -> SealedResult<InclusionProof> {
+        let leaves = self.leaves()?;
+        if index >= leaves.len() {
+            return Err(SealedError::InvalidInput(format!(
+                "Batch item {} out of range ({} items)",
+                index,
+                leaves.len()
+            )));
+        }
+        Ok(InclusionProof {
+            index,
+            leaf: hex::encode(leaves[index]),
+            steps: proof_steps(&leaves, index),
+            merkle_root: hex::encode(merkle_root(&leaves)),
+        })
+    }
+
+    /// Write the manifest as `batch.json` in the sealed directory. Returns the JSON written.
+    pub fn save(&self, sealed_dir: &Path) -> SealedResult<String> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(sealed_dir.join(BATCH_MANIFEST_FILE), &json)?;
+        Ok(json)
+    }
+
+    /// Load `batch.json` from a sealed directory.
+    pub fn load(sealed_dir: &Path) -> SealedResult<Self> {
+        let path = sealed_dir.join(BATCH_MANIFEST_FILE);
+        if !path.exists() {
+            return Err(SealedError::FileNotFound(path.display().to_string()));
+        }
+        let json = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    fn leaves(&self) -> SealedResult<Vec<[u8; 32]>> {
+        self.items.iter().map(|item| decode_hash(&item.leaf)).collect()
+    }
+}
+
+impl Default for BatchManifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Merkle leaf hash: SHA-256(0x00 || data).
+pub fn leaf_hash(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// Interior node hash: SHA-256(0x01 || left || right).
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Merkle root over leaf hashes. An unpaired node is promoted unchanged, never duplicated.
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return Sha256::digest([]).into();
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Hash one tree level into the next.
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+fn proof_steps(leaves: &[[u8; 32]], mut index: usize) -> Vec<ProofStep> {
+    let mut steps = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            steps.push(ProofStep {
+                side: if sibling < index { ProofSide::Left } else { ProofSide::Right },
+                hash: hex::encode(level[sibling]),
+            });
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    steps
+}
+
+/// Check that `proof.leaf` folds up to `proof.merkle_root`.
+pub fn verify_inclusion(proof: &InclusionProof) -> SealedResult<bool> {
+    let mut acc = decode_hash(&proof.leaf)?;
+    for step in &proof.steps {
+        let sibling = decode_hash(&step.hash)?;
+        acc = match step.side {
+            ProofSide::Left => node_hash(&sibling, &acc),
+            ProofSide::Right => node_hash(&acc, &sibling),
+        };
+    }
+    Ok(hex::encode(acc) == proof.merkle_root)
+}
+
+/// The key that signed the directory seal holding `item_dir`, once
+/// `signed_batch.json` verifies and the item's current `hashes.json` is
+/// proven to be a leaf under the signed Merkle root. `None` when no
+/// enclosing directory seal is signed.
+pub fn item_signer(item_dir: &Path) -> SealedResult<Option<String>> {
+    let Some(batch_dir) = item_dir.ancestors().skip(1).find(|d| d.join(BATCH_MANIFEST_FILE).exists()) else {
+        return Ok(None);
+    };
+    let signed_path = batch_dir.join(SIGNED_BATCH_FILE);
+    if !signed_path.exists() {
+        return Ok(None);
+    }
+    let envelope: SignedEnvelope = serde_json::from_str(&std::fs::read_to_string(&signed_path)?)?;
+    envelope.verify()?;
+    let manifest: BatchManifest = serde_json::from_str(&envelope.payload)?;
+
+    let relative = item_dir.strip_prefix(batch_dir).expect("ancestor");
+    let index = manifest
+        .items
+        .iter()
+        .position(|item| Path::new(&item.dir) == relative)
+        .ok_or_else(|| SealedError::VerificationFailed(format!(
+            "{} is not an item of the signed batch",
+            relative.display()
+        )))?;
+    let proof = manifest.inclusion_proof(index)?;
+    let record: SealedRecord = serde_json::from_str(&std::fs::read_to_string(item_dir.join("hashes.json"))?)?;
+    let leaf = item_leaf(&record)?;
+    if proof.leaf != leaf || proof.merkle_root != manifest.merkle_root || !verify_inclusion(&proof)? {
+        return Err(SealedError::VerificationFailed(format!(
+            "hashes.json of {} is not the one the batch signed",
+            relative.display()
+        )));
+    }
+    Ok(Some(envelope.public_key))
+}
+
+fn decode_hash(hex_str: &str) -> SealedResult<[u8; 32]> {
+    let bytes = hex::decode(hex_str)
+        .map_err(|e| SealedError::InvalidInput(format!("Invalid hash hex '{}': {}", hex_str, e)))?;
+    bytes
+        .try_into()
+        .map_err(|_| SealedError::InvalidInput(format!("Hash must be 32 bytes: {}", hex_str)))
+}
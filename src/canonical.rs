@@ -0,0 +1,168 @@
+use std::fmt;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+use crate::errors::{SealedError, SealedResult};
+
+/// `SignedEnvelope::canonicalization` value for RFC 8785 payloads.
+pub const JCS: &str = "jcs";
+
+/// Serialize `value` in RFC 8785 (JSON Canonicalization Scheme) form.
+pub fn to_canonical_json<T: Serialize + ?Sized>(value: &T) -> SealedResult<String> {
+    canonicalize(&serde_json::to_value(value)?)
+}
+
+/// Re-serialize a JSON document in RFC 8785 form, whatever its layout.
+/// Duplicate member names are refused rather than resolved.
+pub fn canonicalize_str(json: &str) -> SealedResult<String> {
+    let StrictValue(value) = serde_json::from_str(json)?;
+    canonicalize(&value)
+}
+
+/// RFC 8785 form of a parsed value: no whitespace, object members sorted by
+/// UTF-16 code units, ECMAScript number formatting.
+pub fn canonicalize(value: &Value) -> SealedResult<String> {
+    let mut out = String::new();
+    write_value(value, &mut out)?;
+    Ok(out)
+}
+
+fn write_value(value: &Value, out: &mut String) -> SealedResult<()> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        // Every number is an IEEE-754 double, so integers past 2^53 round.
+        Value::Number(n) => match n.as_f64() {
+            Some(f) => out.push_str(&format_number(f)?),
+            None => return Err(SealedError::InvalidInput(format!("Unrepresentable JSON number {}", n))),
+        },
+        // serde_json escapes exactly as RFC 8785 requires: `"`, `\` and
+        // control characters only, with lowercase \u00xx.
+        Value::String(s) => out.push_str(&serde_json::to_string(s)?),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out)?;
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut members: Vec<(&String, &Value)> = map.iter().collect();
+            members.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+            out.push('{');
+            for (i, (key, item)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::to_string(key)?);
+                out.push(':');
+                write_value(item, out)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+/// ECMAScript `Number.prototype.toString` for a finite double.
+fn format_number(f: f64) -> SealedResult<String> {
+    if !f.is_finite() {
+        return Err(SealedError::InvalidInput(format!("{} has no JSON form", f)));
+    }
+    if f == 0.0 {
+        return Ok("0".to_string());
+    }
+    // Rust's `{:e}` gives the shortest round-tripping digits, e.g. "-1.25e-7".
+    let sci = format!("{:e}", f.abs());
+    let (mantissa, exp) = sci.split_once('e').expect("exponent form");
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    // Decimal point position: value = 0.digits × 10^n.
+    let n = exp.parse::<i32>().expect("integer exponent") + 1;
+
+    let body = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat((-n) as usize), digits)
+    } else {
+        let sign = if n - 1 < 0 { '-' } else { '+' };
+        let fraction = if k > 1 { format!(".{}", &digits[1..]) } else { String::new() };
+        format!("{}{}e{}{}", &digits[..1], fraction, sign, (n - 1).abs())
+    };
+    Ok(if f < 0.0 { format!("-{}", body) } else { body })
+}
+
+/// A parsed JSON value whose objects had no repeated member names.
+/// `serde_json` keeps the last duplicate, so two readers of the same bytes
+/// could disagree on what was signed.
+struct StrictValue(Value);
+
+impl<'de> Deserialize<'de> for StrictValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(StrictVisitor).map(StrictValue)
+    }
+}
+
+struct StrictVisitor;
+
+impl<'de> Visitor<'de> for StrictVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, i: i64) -> Result<Value, E> {
+        Ok(Value::from(i))
+    }
+
+    fn visit_u64<E>(self, u: u64) -> Result<Value, E> {
+        Ok(Value::from(u))
+    }
+
+    fn visit_f64<E: de::Error>(self, f: f64) -> Result<Value, E> {
+        Number::from_f64(f).map(Value::Number).ok_or_else(|| E::custom(format!("{} has no JSON form", f)))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+        Ok(Value::String(s.to_string()))
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<Value, E> {
+        Ok(Value::String(s))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(StrictValue(item)) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Value, A::Error> {
+        let mut map = Map::new();
+        while let Some(key) = access.next_key::<String>()? {
+            if map.contains_key(&key) {
+                return Err(de::Error::custom(format!("duplicate member name {:?}", key)));
+            }
+            let StrictValue(item) = access.next_value()?;
+            map.insert(key, item);
+        }
+        Ok(Value::Object(map))
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashSet;
+use std::path::Path;
+use sha2::{Sha256, Digest};
+
+use crate::cid::{read_varint, write_varint, Block, Cid};
+use crate::errors::{SealedError, SealedResult};
+
+/// CBOR tag for an IPLD link in DAG-CBOR.
+const CBOR_TAG_CID: [u8; 2] = [0xd8, 0x2a];
+
+/// Serialize a CARv1 archive: a DAG-CBOR header naming `root`, then each
+/// distinct block as `varint(len) || cid || data`.
+pub fn encode_car(root: &Cid, blocks: &[Block]) -> Vec<u8> {
+    let header = encode_header(root);
+    let mut out = Vec::new();
+    write_varint(&mut out, header.len() as u64);
+    out.extend_from_slice(&header);
+
+    let mut seen = HashSet::new();
+    for block in blocks {
+        if !seen.insert(block.cid) {
+            continue;
+        }
+        let cid = block.cid.to_bytes();
+        write_varint(&mut out, (cid.len() + block.data.len()) as u64);
+        out.extend_from_slice(&cid);
+        out.extend_from_slice(&block.data);
+    }
+    out
+}
+
+/// Write a CARv1 file.
+pub fn write_car(path: &Path, root: &Cid, blocks: &[Block]) -> SealedResult<()> {
+    std::fs::write(path, encode_car(root, blocks))?;
+    Ok(())
+}
+
+/// Parse a CARv1 archive, checking every block against its CID.
+/// Returns the single root and the blocks in file order.
+pub fn decode_car(bytes: &[u8]) -> SealedResult<(Cid, Vec<Block>)> {
+    let mut pos = 0;
+    let header_len = read_varint(bytes, &mut pos)? as usize;
+    let header = pos
+        .checked_add(header_len)
+        .and_then(|end| bytes.get(pos..end))
+        .ok_or_else(|| SealedError::InvalidInput("Truncated CAR header".to_string()))?;
+    let root = decode_header(header)?;
+    pos += header_len;
+
+    let mut blocks = Vec::new();
+    while pos < bytes.len() {
+        let len = read_varint(bytes, &mut pos)? as usize;
+        let section = pos
+            .checked_add(len)
+            .and_then(|end| bytes.get(pos..end))
+            .ok_or_else(|| SealedError::InvalidInput("Truncated CAR block".to_string()))?;
+        pos += len;
+
+        // A sha2-256 CID is 34 bytes (v0) or 36 bytes (v1 with one-byte codec).
+        let cid_len = if section.first() == Some(&0x12) { 34 } else { 36 };
+        if section.len() < cid_len {
+            return Err(SealedError::InvalidInput("CAR block shorter than its CID".to_string()));
+        }
+        let cid = Cid::from_bytes(&section[..cid_len])?;
+        let data = section[cid_len..].to_vec();
+        if Sha256::digest(&data).as_slice() != cid.digest {
+            return Err(SealedError::VerificationFailed(format!("CAR block {} does not match its CID", cid)));
+        }
+        blocks.push(Block { cid, data });
+    }
+
+    Ok((root, blocks))
+}
+
+/// DAG-CBOR `{"roots": [root], "version": 1}` (keys in length-first order).
+fn encode_header(root: &Cid) -> Vec<u8> {
+    let cid = root.to_bytes();
+    let mut out = vec![0xa2];
+    out.push(0x65);
+    out.extend_from_slice(b"roots");
+    out.push(0x81);
+    out.extend_from_slice(&CBOR_TAG_CID);
+    // Byte string holding the identity multibase prefix plus the binary CID.
+    out.push(0x58);
+    out.push((cid.len() + 1) as u8);
+    out.push(0x00);
+    out.extend_from_slice(&cid);
+    out.push(0x67);
+    out.extend_from_slice(b"version");
+    out.push(0x01);
+    out
+}
+
+/// Accepts exactly the header layout `encode_header` produces.
+fn decode_header(header: &[u8]) -> SealedResult<Cid> {
+    let malformed = || SealedError::UnsupportedFormat("Only single-root CARv1 headers are supported".to_string());
+
+    let prefix = [&[0xa2, 0x65][..], b"roots", &[0x81], &CBOR_TAG_CID, &[0x58]].concat();
+    let rest = header.strip_prefix(prefix.as_slice()).ok_or_else(malformed)?;
+    let (&len, rest) = rest.split_first().ok_or_else(malformed)?;
+    let len = len as usize;
+    if rest.len() < len || len < 2 || rest[0] != 0x00 {
+        return Err(malformed());
+    }
+    let root = Cid::from_bytes(&rest[1..len])?;
+
+    let version = [&[0x67][..], b"version", &[0x01]].concat();
+    if rest[len..] != version[..] {
+        return Err(malformed());
+    }
+    Ok(root)
+}
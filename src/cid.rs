@@ -0,0 +1,562 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use sha2::{Sha256, Digest};
+
+use crate::errors::{SealedError, SealedResult};
+
+/// Kubo's default fixed-size chunker (`size-262144`).
+pub const CHUNK_SIZE: usize = 262_144;
+
+/// Maximum links per node in Kubo's balanced DAG layout.
+const MAX_LINKS: usize = 174;
+
+/// Largest file reassembled from a fetched DAG.
+pub const MAX_FILE_SIZE: u64 = 1 << 30;
+
+/// Deepest link chain followed in a fetched DAG; Kubo's layouts stay far below.
+const MAX_DAG_DEPTH: usize = 32;
+
+/// Multicodec codes.
+pub const CODEC_RAW: u64 = 0x55;
+pub const CODEC_DAG_PB: u64 = 0x70;
+
+/// Multihash code for sha2-256 and its digest length.
+const MH_SHA2_256: u8 = 0x12;
+const MH_SHA2_256_LEN: u8 = 0x20;
+
+/// UnixFS `Data.DataType` values.
+const UNIXFS_DIRECTORY: u64 = 1;
+const UNIXFS_FILE: u64 = 2;
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// CID flavour to import with, matching `ipfs add` defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CidVersion {
+    /// CIDv0: base58btc, dag-pb leaves.
+    #[default]
+    V0,
+    /// CIDv1: base32, raw leaves.
+    V1,
+}
+
+/// A sha2-256 content identifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cid {
+    pub version: CidVersion,
+    pub codec: u64,
+    pub digest: [u8; 32],
+}
+
+impl Cid {
+    fn new(version: CidVersion, codec: u64, block: &[u8]) -> Self {
+        Self {
+            version,
+            codec,
+            digest: Sha256::digest(block).into(),
+        }
+    }
+
+    /// Binary form, as stored in dag-pb links and CAR files.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(36);
+        if self.version == CidVersion::V1 {
+            write_varint(&mut out, 1);
+            write_varint(&mut out, self.codec);
+        }
+        out.push(MH_SHA2_256);
+        out.push(MH_SHA2_256_LEN);
+        out.extend_from_slice(&self.digest);
+        out
+    }
+
+    /// Parse the binary form.
+    pub fn from_bytes(bytes: &[u8]) -> SealedResult<Self> {
+        let (version, codec, mh) = if bytes.len() == 34 && bytes[0] == MH_SHA2_256 {
+            (CidVersion::V0, CODEC_DAG_PB, bytes)
+        } else {
+            let mut pos = 0;
+            let version = read_varint(bytes, &mut pos)?;
+            if version != 1 {
+                return Err(SealedError::InvalidInput(format!("Unsupported CID version {}", version)));
+            }
+            let codec = read_varint(bytes, &mut pos)?;
+            (CidVersion::V1, codec, &bytes[pos..])
+        };
+        match mh {
+            [MH_SHA2_256, MH_SHA2_256_LEN, digest @ ..] if digest.len() == 32 => Ok(Self {
+                version,
+                codec,
+                digest: digest.try_into().expect("length checked"),
+            }),
+            _ => Err(SealedError::InvalidInput("CID is not a sha2-256 multihash".to_string())),
+        }
+    }
+
+    /// Same content address regardless of version/encoding (CIDv0 == dag-pb CIDv1).
+    pub fn same_content(&self, other: &Cid) -> bool {
+        self.codec == other.codec && self.digest == other.digest
+    }
+}
+
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.version {
+            CidVersion::V0 => f.write_str(&base58_encode(&self.to_bytes())),
+            CidVersion::V1 => write!(f, "b{}", base32_encode(&self.to_bytes())),
+        }
+    }
+}
+
+impl FromStr for Cid {
+    type Err = SealedError;
+
+    fn from_str(s: &str) -> SealedResult<Self> {
+        let bytes = if s.len() == 46 && s.starts_with("Qm") {
+            base58_decode(s)?
+        } else if let Some(rest) = s.strip_prefix('b') {
+            base32_decode(rest)?
+        } else {
+            return Err(SealedError::InvalidInput(format!(
+                "Unsupported CID encoding (expected Qm... or b...): {}",
+                s
+            )));
+        };
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// One encoded IPLD block.
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub cid: Cid,
+    pub data: Vec<u8>,
+}
+
+/// A dag-pb link being assembled into a parent node.
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub cid: Cid,
+    pub name: String,
+    /// Cumulative size of the linked subtree's blocks.
+    pub tsize: u64,
+}
+
+/// A node in the DAG under construction, with what its parent needs to link it.
+struct Node {
+    cid: Cid,
+    /// File bytes covered by this subtree.
+    file_size: u64,
+    tsize: u64,
+}
+
+/// Chunk `data` into a balanced UnixFS file DAG the way `ipfs add` does.
+/// Blocks are returned children-first; the last one is the root.
+pub fn import_file(data: &[u8], version: CidVersion) -> Vec<Block> {
+    let mut blocks = Vec::new();
+
+    let chunks: Vec<&[u8]> = if data.is_empty() {
+        vec![data]
+    } else {
+        data.chunks(CHUNK_SIZE).collect()
+    };
+    let mut level: Vec<Node> = chunks
+        .into_iter()
+        .map(|chunk| {
+            let block = match version {
+                CidVersion::V0 => {
+                    let node = encode_pb_node(&[], &encode_unixfs(UNIXFS_FILE, Some(chunk), chunk.len() as u64, &[]));
+                    Block { cid: Cid::new(version, CODEC_DAG_PB, &node), data: node }
+                }
+                CidVersion::V1 => Block {
+                    cid: Cid::new(version, CODEC_RAW, chunk),
+                    data: chunk.to_vec(),
+                },
+            };
+            let node = Node { cid: block.cid, file_size: chunk.len() as u64, tsize: block.data.len() as u64 };
+            blocks.push(block);
+            node
+        })
+        .collect();
+
+    while level.len() > 1 {
+        level = level
+            .chunks(MAX_LINKS)
+            .map(|children| {
+                let links: Vec<Link> = children
+                    .iter()
+                    .map(|c| Link { cid: c.cid, name: String::new(), tsize: c.tsize })
+                    .collect();
+                let sizes: Vec<u64> = children.iter().map(|c| c.file_size).collect();
+                let file_size = sizes.iter().sum();
+                let node = encode_pb_node(&links, &encode_unixfs(UNIXFS_FILE, None, file_size, &sizes));
+                let cid = Cid::new(version, CODEC_DAG_PB, &node);
+                let tsize = node.len() as u64 + children.iter().map(|c| c.tsize).sum::<u64>();
+                blocks.push(Block { cid, data: node });
+                Node { cid, file_size, tsize }
+            })
+            .collect();
+    }
+
+    blocks
+}
+
+/// Build a UnixFS directory node over `links` (sorted by name, as IPFS requires).
+pub fn directory_block(mut links: Vec<Link>, version: CidVersion) -> Block {
+    links.sort_by(|a, b| a.name.as_bytes().cmp(b.name.as_bytes()));
+    let node = encode_pb_node(&links, &encode_unixfs(UNIXFS_DIRECTORY, None, 0, &[]));
+    Block { cid: Cid::new(version, CODEC_DAG_PB, &node), data: node }
+}
+
+/// CID that `ipfs add` would report for `data`.
+pub fn cid_for_bytes(data: &[u8], version: CidVersion) -> Cid {
+    import_file(data, version).last().expect("import yields a root").cid
+}
+
+/// CID that `ipfs add` would report for the file at `path`.
+pub fn cid_for_file(path: &Path, version: CidVersion) -> SealedResult<Cid> {
+    if !path.exists() {
+        return Err(SealedError::FileNotFound(path.display().to_string()));
+    }
+    Ok(cid_for_bytes(&std::fs::read(path)?, version))
+}
+
+/// Whether `data` hashes to `expected` under that CID's own version.
+pub fn verify_cid(data: &[u8], expected: &str) -> SealedResult<bool> {
+    let expected: Cid = expected.parse()?;
+    Ok(cid_for_bytes(data, expected.version).same_content(&expected))
+}
+
+/// Blocks of a fetched DAG, indexed by digest so CIDv0 and dag-pb CIDv1
+/// links resolve alike. Blocks must already be checked against their CIDs.
+#[derive(Debug, Default)]
+pub struct BlockStore {
+    blocks: HashMap<[u8; 32], Block>,
+}
+
+impl BlockStore {
+    pub fn new(blocks: Vec<Block>) -> Self {
+        Self { blocks: blocks.into_iter().map(|b| (b.cid.digest, b)).collect() }
+    }
+
+    pub fn get(&self, cid: &Cid) -> SealedResult<&Block> {
+        self.blocks
+            .get(&cid.digest)
+            .filter(|b| b.cid.codec == cid.codec)
+            .ok_or_else(|| SealedError::InvalidInput(format!("Block {} missing from DAG", cid)))
+    }
+
+    /// Reassemble the UnixFS file rooted at `root`.
+    ///
+    /// The DAG is untrusted: it is sized before anything is copied, so a
+    /// chain deeper than `MAX_DAG_DEPTH` or a file over `MAX_FILE_SIZE` fails
+    /// without expanding it, and a block reached again is copied, not re-read.
+    pub fn read_file(&self, root: &Cid) -> SealedResult<Vec<u8>> {
+        let size = self.file_size(root, 0, &mut HashMap::new())?;
+        if size > MAX_FILE_SIZE {
+            return Err(SealedError::InvalidInput(format!(
+                "{} expands to more than {} bytes", root, MAX_FILE_SIZE
+            )));
+        }
+        let mut out = Vec::with_capacity(size as usize);
+        self.append_file(root, &mut out, &mut HashMap::new())?;
+        Ok(out)
+    }
+
+    /// Inline bytes and child links of a UnixFS file block.
+    fn file_node(&self, cid: &Cid) -> SealedResult<(Cow<'_, [u8]>, Vec<Link>)> {
+        let block = self.get(cid)?;
+        if cid.codec == CODEC_RAW {
+            return Ok((Cow::Borrowed(&block.data), Vec::new()));
+        }
+        let (links, data) = decode_pb_node(&block.data)?;
+        let (kind, inline) = decode_unixfs(&data)?;
+        if kind != UNIXFS_FILE && kind != 0 {
+            return Err(SealedError::UnsupportedFormat(format!("{} is not a UnixFS file", cid)));
+        }
+        Ok((Cow::Owned(inline), links))
+    }
+
+    /// Bytes the file at `cid` expands to, saturating. Importers only link a
+    /// block twice when its content repeats, so an empty one linked again is refused.
+    fn file_size(&self, cid: &Cid, depth: usize, sizes: &mut HashMap<[u8; 32], u64>) -> SealedResult<u64> {
+        if let Some(&size) = sizes.get(&cid.digest) {
+            if size == 0 {
+                return Err(SealedError::InvalidInput(format!("Empty block {} is linked more than once", cid)));
+            }
+            return Ok(size);
+        }
+        if depth > MAX_DAG_DEPTH {
+            return Err(SealedError::InvalidInput(format!("DAG is deeper than {} levels", MAX_DAG_DEPTH)));
+        }
+        let (inline, links) = self.file_node(cid)?;
+        let mut size = inline.len() as u64;
+        for link in &links {
+            size = size.saturating_add(self.file_size(&link.cid, depth + 1, sizes)?);
+        }
+        sizes.insert(cid.digest, size);
+        Ok(size)
+    }
+
+    /// Append the file at `cid`, copying blocks already written from `out`.
+    fn append_file(
+        &self,
+        cid: &Cid,
+        out: &mut Vec<u8>,
+        written: &mut HashMap<[u8; 32], std::ops::Range<usize>>,
+    ) -> SealedResult<()> {
+        if let Some(range) = written.get(&cid.digest) {
+            out.extend_from_within(range.clone());
+            return Ok(());
+        }
+        let start = out.len();
+        let (inline, links) = self.file_node(cid)?;
+        out.extend_from_slice(&inline);
+        for link in &links {
+            self.append_file(&link.cid, out, written)?;
+        }
+        written.insert(cid.digest, start..out.len());
+        Ok(())
+    }
+
+    /// Entries of the UnixFS directory at `root`, or `None` if it is a file.
+    pub fn read_directory(&self, root: &Cid) -> SealedResult<Option<Vec<Link>>> {
+        directory_links(root, &self.get(root)?.data)
+    }
+}
+
+/// Links of a UnixFS directory block, or `None` if the block is a file.
+pub fn directory_links(cid: &Cid, block: &[u8]) -> SealedResult<Option<Vec<Link>>> {
+    if cid.codec != CODEC_DAG_PB {
+        return Ok(None);
+    }
+    let (links, data) = decode_pb_node(block)?;
+    Ok((decode_unixfs(&data)?.0 == UNIXFS_DIRECTORY).then_some(links))
+}
+
+/// Parse a dag-pb `PBNode` into its links and `Data` bytes.
+fn decode_pb_node(bytes: &[u8]) -> SealedResult<(Vec<Link>, Vec<u8>)> {
+    let mut links = Vec::new();
+    let mut data = Vec::new();
+    for (field, value) in read_fields(bytes)? {
+        match (field, value) {
+            (2, Field::Bytes(raw)) => {
+                let mut cid = None;
+                let mut name = String::new();
+                let mut tsize = 0;
+                for (field, value) in read_fields(raw)? {
+                    match (field, value) {
+                        (1, Field::Bytes(b)) => cid = Some(Cid::from_bytes(b)?),
+                        (2, Field::Bytes(b)) => name = String::from_utf8_lossy(b).to_string(),
+                        (3, Field::Varint(v)) => tsize = v,
+                        _ => {}
+                    }
+                }
+                let cid = cid.ok_or_else(|| SealedError::InvalidInput("dag-pb link without a CID".to_string()))?;
+                links.push(Link { cid, name, tsize });
+            }
+            (1, Field::Bytes(raw)) => data = raw.to_vec(),
+            _ => {}
+        }
+    }
+    Ok((links, data))
+}
+
+/// UnixFS `Data.Type` and inline `Data.Data`.
+fn decode_unixfs(bytes: &[u8]) -> SealedResult<(u64, Vec<u8>)> {
+    let mut kind = 0;
+    let mut data = Vec::new();
+    for (field, value) in read_fields(bytes)? {
+        match (field, value) {
+            (1, Field::Varint(v)) => kind = v,
+            (2, Field::Bytes(b)) => data = b.to_vec(),
+            _ => {}
+        }
+    }
+    Ok((kind, data))
+}
+
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+/// Split a protobuf message into fields; only wire types 0 and 2 occur here.
+fn read_fields(bytes: &[u8]) -> SealedResult<Vec<(u64, Field<'_>)>> {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let key = read_varint(bytes, &mut pos)?;
+        let value = match key & 7 {
+            0 => Field::Varint(read_varint(bytes, &mut pos)?),
+            2 => {
+                let len = read_varint(bytes, &mut pos)? as usize;
+                let value = pos
+                    .checked_add(len)
+                    .and_then(|end| bytes.get(pos..end))
+                    .ok_or_else(|| SealedError::InvalidInput("Truncated protobuf field".to_string()))?;
+                pos += len;
+                Field::Bytes(value)
+            }
+            other => {
+                return Err(SealedError::InvalidInput(format!("Unexpected protobuf wire type {}", other)));
+            }
+        };
+        fields.push((key >> 3, value));
+    }
+    Ok(fields)
+}
+
+/// dag-pb `PBNode`: links (field 2) are serialized before data (field 1).
+fn encode_pb_node(links: &[Link], data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for link in links {
+        let mut pb_link = Vec::new();
+        write_bytes_field(&mut pb_link, 1, &link.cid.to_bytes());
+        write_bytes_field(&mut pb_link, 2, link.name.as_bytes());
+        write_varint_field(&mut pb_link, 3, link.tsize);
+        write_bytes_field(&mut out, 2, &pb_link);
+    }
+    write_bytes_field(&mut out, 1, data);
+    out
+}
+
+/// UnixFS `Data` message.
+fn encode_unixfs(kind: u64, data: Option<&[u8]>, file_size: u64, block_sizes: &[u64]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_varint_field(&mut out, 1, kind);
+    if let Some(data) = data.filter(|d| !d.is_empty()) {
+        write_bytes_field(&mut out, 2, data);
+    }
+    if kind == UNIXFS_FILE {
+        write_varint_field(&mut out, 3, file_size);
+    }
+    for size in block_sizes {
+        write_varint_field(&mut out, 4, *size);
+    }
+    out
+}
+
+fn write_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    write_varint(out, field << 3);
+    write_varint(out, value);
+}
+
+fn write_bytes_field(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_varint(out, (field << 3) | 2);
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Unsigned LEB128, as used by protobuf, multiformats and CAR.
+pub fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Read an unsigned LEB128 varint at `pos`, advancing it.
+pub fn read_varint(bytes: &[u8], pos: &mut usize) -> SealedResult<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes
+            .get(*pos)
+            .ok_or_else(|| SealedError::InvalidInput("Truncated varint".to_string()))?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(SealedError::InvalidInput("Varint too long".to_string()))
+}
+
+fn base58_encode(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in &bytes[zeros..] {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let mut out = String::with_capacity(zeros + digits.len());
+    out.extend(std::iter::repeat_n('1', zeros));
+    out.extend(digits.iter().rev().map(|&d| BASE58_ALPHABET[d as usize] as char));
+    out
+}
+
+fn base58_decode(s: &str) -> SealedResult<Vec<u8>> {
+    let zeros = s.bytes().take_while(|&c| c == b'1').count();
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in s.bytes().skip(zeros) {
+        let mut carry = BASE58_ALPHABET
+            .iter()
+            .position(|&a| a == c)
+            .ok_or_else(|| SealedError::InvalidInput(format!("Invalid base58 character '{}'", c as char)))?
+            as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut out = vec![0u8; zeros];
+    out.extend(bytes.iter().rev());
+    Ok(out)
+}
+
+/// RFC 4648 base32, lowercase, unpadded (multibase `b`).
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+fn base32_decode(s: &str) -> SealedResult<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in s.bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a == c.to_ascii_lowercase())
+            .ok_or_else(|| SealedError::InvalidInput(format!("Invalid base32 character '{}'", c as char)))?
+            as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
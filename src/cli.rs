@@ -1,6 +1,12 @@
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
+use crate::ipfs::IpfsProvider;
+use crate::key_formats::KeyFormat;
+use crate::passphrase::PassphraseSource;
+use crate::signing::KdfAlgorithm;
+use crate::registry::PriorSealPolicy;
+
 #[derive(Parser, Debug)]
 #[command(name = "sealed-ch", version, about, long_about = None)]
 pub struct Cli {
@@ -9,6 +15,58 @@ pub struct Cli {
 
     #[arg(short, long, global = true)]
     pub verbose: bool,
+
+    /// Config file to use instead of the nearest project sealed.toml.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Named profile from sealed.toml (goes before the subcommand).
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Print results as text or, for scripts, as JSON on stdout.
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    pub format: OutputFormat,
+
+    /// Ignore sealed.toml files.
+    #[arg(long, global = true, conflicts_with = "config")]
+    pub no_config: bool,
+}
+
+/// How commands print their results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Process exit codes. `verify` reports its outcome through them so scripts
+/// need not parse the verdict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ExitStatus {
+    /// Success; for `verify`, an exact match of the original or share.
+    Success = 0,
+    /// The command failed.
+    Error = 1,
+    /// Bad arguments (clap's own exit code).
+    Usage = 2,
+    /// `verify`: perceptually similar to the sealed content.
+    PerceptualMatch = 3,
+    /// `verify`: a cropped region of the sealed original.
+    CropMatch = 4,
+    /// `verify`: no match.
+    NoMatch = 5,
+    /// `verify`: the sealed record's signature, CID or artifact check failed.
+    /// `check`: an artifact is missing or tampered.
+    SignatureFailure = 6,
+}
+
+impl ExitStatus {
+    pub fn code(self) -> u8 {
+        self as u8
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -21,29 +79,85 @@ pub enum Commands {
         #[arg(short, long)]
         output: Option<PathBuf>,
 
-        #[arg(long, default_value = "20")]
-        edge_width: u32,
+        /// Edge width in pixels [default: 20].
+        #[arg(long)]
+        edge_width: Option<u32>,
 
         #[arg(short, long)]
         key: Option<PathBuf>,
 
-        #[arg(long)]
+        /// Where an encrypted key's passphrase comes from: prompt, env:VAR, fd:N, file:PATH or stdin.
+        #[arg(long, value_name = "SOURCE")]
+        passphrase: Option<PassphraseSource>,
+
+        #[arg(long, overrides_with = "no_ipfs")]
         ipfs: bool,
 
-        #[arg(long, default_value = "http://127.0.0.1:5001")]
-        ipfs_url: String,
+        /// Don't pin, even if sealed.toml sets `ipfs = true`.
+        #[arg(long, overrides_with = "ipfs")]
+        no_ipfs: bool,
 
+        /// Kubo API URL (defaults to the default profile, else http://127.0.0.1:5001).
         #[arg(long)]
+        ipfs_url: Option<String>,
+
+        /// Pinata API key (legacy; prefer --ipfs-profile).
+        #[arg(long, conflicts_with = "ipfs_url")]
         ipfs_key: Option<String>,
 
-        #[arg(long, default_value = "5")]
-        frame_interval: u64,
+        /// Named profile from ~/.sealed/ipfs_profiles.json.
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["ipfs_url", "ipfs_key"])]
+        ipfs_profile: Option<String>,
+
+        /// Pin with CIDv1 and raw leaves instead of CIDv0.
+        #[arg(long, overrides_with = "no_cid_v1")]
+        cid_v1: bool,
+
+        /// Pin with CIDv0, even if sealed.toml sets `cid_v1 = true`.
+        #[arg(long, overrides_with = "cid_v1")]
+        no_cid_v1: bool,
 
+        /// Pin the whole sealed directory under one root CID (local node only).
+        #[arg(long, requires = "ipfs", overrides_with = "no_ipfs_dir")]
+        ipfs_dir: bool,
+
+        /// Pin files one by one, even if sealed.toml sets `ipfs_dir = true`.
+        #[arg(long, overrides_with = "ipfs_dir")]
+        no_ipfs_dir: bool,
+
+        /// Seconds between sampled video frames [default: 5].
         #[arg(long)]
-        sample_frames: Option<usize>,
+        frame_interval: Option<u64>,
 
         #[arg(long)]
+        sample_frames: Option<usize>,
+
+        #[arg(long, overrides_with = "no_timestamp")]
         timestamp: bool,
+
+        /// Don't submit to OpenTimestamps, even if sealed.toml sets `timestamp = true`.
+        #[arg(long, overrides_with = "timestamp")]
+        no_timestamp: bool,
+
+        /// RFC 3161 Time-Stamp Authority URL to request a token from.
+        #[arg(long, value_name = "URL")]
+        tsa: Option<String>,
+
+        /// What to do if the input was sealed before [default: warn].
+        #[arg(long, value_enum)]
+        on_prior_seal: Option<PriorSealPolicy>,
+
+        /// Registry to check and record the seal in (defaults to ~/.sealed/registry.json).
+        #[arg(long)]
+        registry: Option<PathBuf>,
+
+        /// Folder of earlier seals to check as well as the registry.
+        #[arg(long, value_name = "DIR")]
+        prior_seals: Vec<PathBuf>,
+
+        /// Don't add this seal to the registry.
+        #[arg(long)]
+        no_register: bool,
     },
 
     /// Verify a suspect image against a sealed record.
@@ -51,11 +165,52 @@ pub enum Commands {
         #[arg(value_name = "SUSPECT")]
         suspect: PathBuf,
 
-        #[arg(value_name = "SEALED_DIR")]
-        sealed_dir: PathBuf,
+        #[arg(value_name = "SEALED_DIR", required_unless_present = "cid")]
+        sealed_dir: Option<PathBuf>,
 
         #[arg(short, long)]
         public_key: Option<PathBuf>,
+
+        /// Fetch the sealed record from IPFS by CID (of hashes.json or a directory pin).
+        #[arg(long, conflicts_with = "sealed_dir")]
+        cid: Option<String>,
+
+        /// CID of a separately pinned signed_record.json.
+        #[arg(long, requires = "cid")]
+        signed_cid: Option<String>,
+
+        /// Trustless gateway to fetch from (defaults to https://ipfs.io/ipfs).
+        #[arg(long, requires = "cid")]
+        gateway: Option<String>,
+
+        /// Fetch through a Kubo node's API instead of a gateway.
+        #[arg(long, requires = "cid", conflicts_with = "gateway")]
+        ipfs_url: Option<String>,
+
+        /// Also write the JSON result to this file. Nothing is written otherwise.
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+
+        /// Trust store of known publisher keys (defaults to ~/.sealed/trust.json).
+        #[arg(long, value_name = "FILE")]
+        trust_store: Option<PathBuf>,
+
+        /// Require this many trusted signers, counting co-signers (the M of "M of N").
+        #[arg(long, value_name = "M")]
+        require_signers: Option<usize>,
+
+        /// Only count these trust store names or key IDs (the N); alone, all must sign.
+        #[arg(long, value_name = "NAME", value_delimiter = ',')]
+        signers: Vec<String>,
+
+        /// Trust anchor certificate (PEM or DER) for the sealed directory's
+        /// RFC 3161 token; a verified token dates the seal for key revocations.
+        #[arg(long, value_name = "CERT")]
+        tsa_anchor: Option<PathBuf>,
+
+        /// Bitcoin block header confirming the sealed directory's OpenTimestamps proof.
+        #[arg(long, value_name = "FILE")]
+        block_header: Option<PathBuf>,
     },
 
     /// Generate an Ed25519 signing keypair.
@@ -67,35 +222,115 @@ pub enum Commands {
         password: bool,
     },
 
+    /// Key IDs, rotation and revocation statements.
+    Key {
+        #[command(subcommand)]
+        command: KeyCommands,
+    },
+
     /// Start the demo web server.
     Serve {
-        #[arg(short, long, default_value = "8000")]
-        port: u16,
+        /// [default: 8000]
+        #[arg(short, long)]
+        port: Option<u16>,
 
-        #[arg(long, default_value = "static")]
-        static_dir: PathBuf,
+        /// [default: static]
+        #[arg(long)]
+        static_dir: Option<PathBuf>,
 
-        #[arg(long, default_value = "uploads")]
-        uploads_dir: PathBuf,
+        /// [default: uploads]
+        #[arg(long)]
+        uploads_dir: Option<PathBuf>,
 
         #[arg(short, long)]
         key: Option<PathBuf>,
+
+        /// Where an encrypted key's passphrase comes from: prompt, env:VAR, fd:N, file:PATH or stdin.
+        #[arg(long, value_name = "SOURCE")]
+        passphrase: Option<PassphraseSource>,
+
+        /// Queue file drained by the background OTS upgrade worker.
+        #[arg(long)]
+        ots_queue: Option<PathBuf>,
+
+        /// Don't run the OTS upgrade worker.
+        #[arg(long)]
+        no_ots_worker: bool,
+    },
+
+    /// Check that a sealed directory's artifacts still match its signed record.
+    Check {
+        /// Sealed directory; for a directory seal, every item is checked.
+        #[arg(value_name = "SEALED_DIR")]
+        sealed_dir: PathBuf,
+    },
+
+    /// Add your signature to an already signed sealed record.
+    Cosign {
+        /// Sealed directory, or a directory seal (signs signed_batch.json).
+        #[arg(value_name = "SEALED_DIR")]
+        sealed_dir: PathBuf,
+
+        /// Secret key to co-sign with.
+        #[arg(short, long)]
+        key: PathBuf,
+    },
+
+    /// Verify an OpenTimestamps proof or RFC 3161 token offline against the sealed SHA-256.
+    VerifyProof {
+        /// Sealed directory (uses its timestamp.ots/.tsr), a .ots file, or a .tsr file.
+        #[arg(value_name = "PROOF")]
+        proof: PathBuf,
+
+        /// Sealed SHA-256 to replay from (defaults to timestamp_record.json).
+        #[arg(long)]
+        hash: Option<String>,
+
+        /// Bitcoin block header for the attested height (80 raw bytes or hex).
+        #[arg(long)]
+        block_header: Option<PathBuf>,
+
+        /// Trust anchor certificate (PEM or DER) for checking a .tsr token.
+        #[arg(long)]
+        tsa_anchor: Option<PathBuf>,
     },
 
-    /// Poll for OTS Bitcoin confirmation (internal, spawned automatically).
-    #[command(hide = true)]
+    /// Upgrade pending OpenTimestamps proofs from the persistent queue.
     OtsUpgrade {
+        /// Resume the whole queue, waiting out retries until nothing is pending.
         #[arg(long)]
-        hash: String,
+        all: bool,
+
+        /// Try every queued proof once, ignoring retry times, then exit.
+        #[arg(long, conflicts_with = "all")]
+        once: bool,
 
+        /// Sealed directory to add to the queue before upgrading.
         #[arg(long)]
-        output_dir: PathBuf,
+        output_dir: Option<PathBuf>,
 
+        /// Sealed SHA-256 for --output-dir (defaults to its timestamp_record.json).
+        #[arg(long, requires = "output_dir")]
+        hash: Option<String>,
+
+        /// Queue file (defaults to ~/.sealed/ots_queue.json).
         #[arg(long)]
+        queue: Option<PathBuf>,
+
+        /// Kubo node to re-pin --output-dir to once confirmed.
+        #[arg(long, requires = "output_dir")]
         ipfs_url: Option<String>,
 
+        /// IPFS profile to re-pin --output-dir with once confirmed.
+        #[arg(long, value_name = "NAME", requires = "output_dir", conflicts_with = "ipfs_url")]
+        ipfs_profile: Option<String>,
+    },
+
+    /// List timestamps still pending in the OTS upgrade queue.
+    Status {
+        /// Queue file (defaults to ~/.sealed/ots_queue.json).
         #[arg(long)]
-        ipfs_key: Option<String>,
+        queue: Option<PathBuf>,
     },
 
     /// Pin a sealed record to IPFS.
@@ -103,10 +338,473 @@ pub enum Commands {
         #[arg(value_name = "SEALED_DIR")]
         sealed_dir: PathBuf,
 
-        #[arg(long, default_value = "http://127.0.0.1:5001")]
-        ipfs_url: String,
+        /// Kubo API URL (defaults to the default profile, else http://127.0.0.1:5001).
+        #[arg(long)]
+        ipfs_url: Option<String>,
+
+        /// Pinata API key (legacy; prefer --ipfs-profile).
+        #[arg(long, conflicts_with = "ipfs_url")]
+        ipfs_key: Option<String>,
+
+        /// Named profile from ~/.sealed/ipfs_profiles.json.
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["ipfs_url", "ipfs_key"])]
+        ipfs_profile: Option<String>,
+
+        /// Pin with CIDv1 and raw leaves instead of CIDv0.
+        #[arg(long, overrides_with = "no_cid_v1")]
+        cid_v1: bool,
+
+        /// Pin with CIDv0, even if sealed.toml sets `cid_v1 = true`.
+        #[arg(long, overrides_with = "cid_v1")]
+        no_cid_v1: bool,
+
+        /// Upload the whole directory with wrap-with-directory under one root CID.
+        #[arg(long, conflicts_with = "car")]
+        dir: bool,
+
+        /// Export the whole directory as a CARv1 file instead of uploading.
+        #[arg(long, value_name = "PATH")]
+        car: Option<PathBuf>,
+    },
+
+    /// Index sealed works and look up similar images.
+    Registry {
+        /// Registry file (defaults to ~/.sealed/registry.json).
+        #[arg(long, global = true)]
+        registry: Option<PathBuf>,
+
+        #[command(subcommand)]
+        command: RegistryCommands,
+    },
+
+    /// Manage the trust store of known publisher keys.
+    Trust {
+        /// Trust store file (defaults to ~/.sealed/trust.json).
+        #[arg(long, global = true, value_name = "FILE")]
+        store: Option<PathBuf>,
+
+        #[command(subcommand)]
+        command: TrustCommands,
+    },
+
+    /// Check every image in a folder against every sealed record under a root.
+    Scan {
+        #[arg(value_name = "SUSPECTS_DIR")]
+        suspects_dir: PathBuf,
+
+        /// Sealed directory, directory seal, or folder of them.
+        #[arg(value_name = "SEALED_ROOT")]
+        sealed_root: PathBuf,
+
+        /// Report file; `.csv` writes CSV, anything else JSON (defaults to scan_report.json).
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Weakest confidence to report: exact, high, medium or low.
+        #[arg(long, default_value = "low")]
+        min_confidence: String,
+
+        /// Look for crops against every record with a tile index, not just the shortlist.
+        #[arg(long)]
+        exhaustive_tiles: bool,
+
+        /// Worker threads (defaults to all cores).
+        #[arg(short, long, default_value = "0")]
+        jobs: usize,
+
+        /// Only count signatures by this public key as valid.
+        #[arg(short, long)]
+        public_key: Option<PathBuf>,
+    },
+
+    /// Seal new or changed media files dropped into a folder.
+    Watch {
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+
+        /// Folder to write sealed output under [default: sealed].
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// State file of files already sealed (defaults to DIR/.sealed-watch.json).
+        #[arg(long)]
+        state: Option<PathBuf>,
+
+        /// Seconds between polls [default: 2].
+        #[arg(long)]
+        interval: Option<u64>,
+
+        /// Seconds a file's size must stay the same before it is sealed [default: 5].
+        #[arg(long)]
+        settle: Option<u64>,
+
+        /// Seal what is in the folder now, then exit.
+        #[arg(long)]
+        once: bool,
 
+        /// Edge width in pixels [default: 20].
         #[arg(long)]
+        edge_width: Option<u32>,
+
+        #[arg(short, long)]
+        key: Option<PathBuf>,
+
+        /// Where an encrypted key's passphrase comes from: prompt, env:VAR, fd:N, file:PATH or stdin.
+        #[arg(long, value_name = "SOURCE")]
+        passphrase: Option<PassphraseSource>,
+
+        #[arg(long, overrides_with = "no_ipfs")]
+        ipfs: bool,
+
+        /// Don't pin, even if sealed.toml sets `ipfs = true`.
+        #[arg(long, overrides_with = "ipfs")]
+        no_ipfs: bool,
+
+        /// Kubo API URL (defaults to the default profile, else http://127.0.0.1:5001).
+        #[arg(long)]
+        ipfs_url: Option<String>,
+
+        /// Pinata API key (legacy; prefer --ipfs-profile).
+        #[arg(long, conflicts_with = "ipfs_url")]
         ipfs_key: Option<String>,
+
+        /// Named profile from ~/.sealed/ipfs_profiles.json.
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["ipfs_url", "ipfs_key"])]
+        ipfs_profile: Option<String>,
+
+        /// Pin with CIDv1 and raw leaves instead of CIDv0.
+        #[arg(long, overrides_with = "no_cid_v1")]
+        cid_v1: bool,
+
+        /// Pin with CIDv0, even if sealed.toml sets `cid_v1 = true`.
+        #[arg(long, overrides_with = "cid_v1")]
+        no_cid_v1: bool,
+
+        /// Pin the whole sealed directory under one root CID.
+        #[arg(long, requires = "ipfs", overrides_with = "no_ipfs_dir")]
+        ipfs_dir: bool,
+
+        /// Pin files one by one, even if sealed.toml sets `ipfs_dir = true`.
+        #[arg(long, overrides_with = "ipfs_dir")]
+        no_ipfs_dir: bool,
+
+        /// Seconds between sampled video frames [default: 5].
+        #[arg(long)]
+        frame_interval: Option<u64>,
+
+        #[arg(long)]
+        sample_frames: Option<usize>,
+
+        #[arg(long, overrides_with = "no_timestamp")]
+        timestamp: bool,
+
+        /// Don't submit to OpenTimestamps, even if sealed.toml sets `timestamp = true`.
+        #[arg(long, overrides_with = "timestamp")]
+        no_timestamp: bool,
+
+        /// RFC 3161 Time-Stamp Authority URL to request a token from.
+        #[arg(long, value_name = "URL")]
+        tsa: Option<String>,
+
+        /// What to do if a file was sealed before [default: warn].
+        #[arg(long, value_enum)]
+        on_prior_seal: Option<PriorSealPolicy>,
+
+        /// Registry to check and record seals in (defaults to ~/.sealed/registry.json).
+        #[arg(long)]
+        registry: Option<PathBuf>,
+
+        /// Don't add seals to the registry.
+        #[arg(long)]
+        no_register: bool,
+    },
+
+    /// Manage pins and pinning profiles.
+    Ipfs {
+        /// Profile file (defaults to ~/.sealed/ipfs_profiles.json).
+        #[arg(long, global = true)]
+        profiles: Option<PathBuf>,
+
+        #[command(subcommand)]
+        command: IpfsCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RegistryCommands {
+    /// Index sealed directories (single seals, directory seals, or folders of them).
+    Add {
+        #[arg(value_name = "SEALED_DIR", required = true)]
+        paths: Vec<PathBuf>,
+    },
+
+    /// Find sealed works that look like an image.
+    Search {
+        #[arg(value_name = "IMAGE")]
+        image: PathBuf,
+
+        /// Weakest confidence to report: exact, high, medium or low.
+        #[arg(long, default_value = "medium")]
+        min_confidence: String,
+
+        #[arg(long, default_value = "10")]
+        limit: usize,
+    },
+
+    /// List indexed works.
+    List,
+
+    /// Remove a work by id (or id prefix) or sealed directory.
+    Remove {
+        #[arg(value_name = "ID_OR_DIR")]
+        target: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TrustCommands {
+    /// Trust a publisher key under a name.
+    Add {
+        #[arg(value_name = "NAME")]
+        name: String,
+
+        /// Public key: a sealed.pub file, or base64 as in signed_record.json.
+        #[arg(value_name = "KEY")]
+        key: String,
+
+        /// Description, e.g. the publisher's full name.
+        #[arg(long)]
+        label: Option<String>,
+
+        /// Only trust records sealed from this time (RFC 3339 or YYYY-MM-DD).
+        #[arg(long)]
+        not_before: Option<String>,
+
+        /// Only trust records sealed up to this time (RFC 3339 or YYYY-MM-DD).
+        #[arg(long)]
+        not_after: Option<String>,
+    },
+
+    /// List trusted keys.
+    List,
+
+    /// Revoke a key by name, base64 public key or key ID.
+    Revoke {
+        #[arg(value_name = "NAME_OR_KEY")]
+        target: String,
+
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Keep trusting records sealed before this time (RFC 3339 or YYYY-MM-DD).
+        #[arg(long)]
+        effective: Option<String>,
+    },
+
+    /// Apply a signed rotation or revocation statement.
+    Import {
+        #[arg(value_name = "STATEMENT")]
+        statement: PathBuf,
+
+        /// Name for a key the statement adds.
+        #[arg(long)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeyCommands {
+    /// Print the key ID of a public key.
+    Id {
+        /// A sealed.pub file, or base64 as in signed_record.json.
+        #[arg(value_name = "KEY")]
+        key: String,
+    },
+
+    /// Sign a statement handing over from a secret key to a new public key.
+    Rotate {
+        /// Secret key being retired.
+        #[arg(short, long)]
+        key: PathBuf,
+
+        /// The new public key: a sealed.pub file, or base64.
+        #[arg(value_name = "NEW_KEY")]
+        new_key: String,
+
+        /// When the new key takes over (RFC 3339 or YYYY-MM-DD) [default: now].
+        #[arg(long)]
+        effective: Option<String>,
+
+        /// [default: key_rotation.json]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Sign a statement revoking a secret key.
+    Revoke {
+        #[arg(short, long)]
+        key: PathBuf,
+
+        /// Records sealed from this time on are untrusted (RFC 3339 or YYYY-MM-DD) [default: now].
+        #[arg(long)]
+        effective: Option<String>,
+
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// [default: key_revocation.json]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Turn a PKCS#8, OpenSSH or JWK secret key into sealed.key + sealed.pub.
+    Import {
+        #[arg(value_name = "FILE")]
+        input: PathBuf,
+
+        #[arg(short, long, default_value = ".")]
+        output: PathBuf,
+
+        /// Encrypt the imported secret key with a password.
+        #[arg(short, long)]
+        password: bool,
+
+        /// Key to take from a JWK set.
+        #[arg(long)]
+        kid: Option<String>,
+    },
+
+    /// Write a key out as PKCS#8, OpenSSH, JWK or raw bytes.
+    Export {
+        /// Secret key, in any format `key import` reads.
+        #[arg(short, long)]
+        key: PathBuf,
+
+        #[arg(long, value_enum)]
+        to: KeyFormat,
+
+        /// Export only the public key.
+        #[arg(long)]
+        public: bool,
+
+        /// [default: stdout; required for raw]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Comment for OpenSSH keys [default: sealed-<key ID>].
+        #[arg(long)]
+        comment: Option<String>,
+    },
+
+    /// Show a key file's format, key ID and public key.
+    Show {
+        /// Secret or public key in any supported format (raw files ending .pub are public).
+        #[arg(value_name = "FILE")]
+        key: PathBuf,
+
+        /// Key to take from a JWK set.
+        #[arg(long)]
+        kid: Option<String>,
+    },
+
+    /// Re-encrypt a password-protected sealed.key with a new password and KDF parameters.
+    Passwd {
+        #[arg(value_name = "FILE")]
+        key: PathBuf,
+
+        /// Argon2 memory cost in KiB [default: 19456].
+        #[arg(long)]
+        memory: Option<u32>,
+
+        /// Argon2 iterations [default: 2].
+        #[arg(long)]
+        iterations: Option<u32>,
+
+        /// Argon2 lanes [default: 1].
+        #[arg(long)]
+        parallelism: Option<u32>,
+
+        #[arg(long, value_enum, default_value_t = KdfAlgorithm::Argon2id)]
+        algorithm: KdfAlgorithm,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IpfsCommands {
+    /// List pins held by a provider.
+    List {
+        /// Profile to use (defaults to the default profile, else the local node).
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Only pins of this CID.
+        #[arg(long)]
+        cid: Option<String>,
+    },
+
+    /// Show the status of a CID or Pinning Service request id.
+    Status {
+        #[arg(value_name = "CID_OR_REQUEST_ID")]
+        target: String,
+
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Remove the pin for a CID or Pinning Service request id.
+    Unpin {
+        #[arg(value_name = "CID_OR_REQUEST_ID")]
+        target: String,
+
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Add, list or remove named profiles.
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileCommands {
+    /// Add or replace a profile.
+    Add {
+        name: String,
+
+        #[arg(long, value_enum)]
+        provider: IpfsProvider,
+
+        /// API base URL (Kubo RPC, Pinata API, or Pinning Service endpoint).
+        #[arg(long)]
+        api_url: Option<String>,
+
+        /// Environment variable holding the access token.
+        #[arg(long, conflicts_with = "token")]
+        token_env: Option<String>,
+
+        /// Access token, stored in the profile file.
+        #[arg(long)]
+        token: Option<String>,
+
+        #[arg(long)]
+        gateway_url: Option<String>,
+
+        /// Kubo node that provides content to a Pinning Service.
+        #[arg(long)]
+        source_node: Option<String>,
+
+        /// Use this profile when none is named.
+        #[arg(long)]
+        default: bool,
+    },
+
+    /// List profiles (tokens are not shown).
+    List,
+
+    /// Remove a profile.
+    Remove {
+        name: String,
     },
 }
@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use tracing::warn;
+
+use crate::errors::{SealedError, SealedResult};
+use crate::passphrase::PassphraseSource;
+use crate::pinning::IpfsProfile;
+use crate::registry::PriorSealPolicy;
+
+/// Config file name, both per project and in the sealed home directory.
+pub const CONFIG_FILE: &str = "sealed.toml";
+
+/// `~/.sealed/sealed.toml` (or under `$SEALED_HOME`).
+pub fn user_config_path() -> PathBuf {
+    crate::paths::sealed_home().join(CONFIG_FILE)
+}
+
+/// The nearest `sealed.toml` in `start` or one of its parents.
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    start.ancestors().map(|dir| dir.join(CONFIG_FILE)).find(|p| p.is_file())
+}
+
+/// An on/off option: `--x` or `--no-x`, whichever came last, else the setting.
+pub fn switch(on: bool, off: bool, setting: Option<bool>) -> bool {
+    on || (!off && setting == Some(true))
+}
+
+/// Defaults for `seal`, `verify`, `serve` and `watch`. Every field is
+/// optional; command-line flags override whatever is set here.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    pub edge_width: Option<u32>,
+    /// Signing key for `seal`, `watch` and `serve`.
+    pub key: Option<PathBuf>,
+    /// Where the signing key's passphrase comes from (`env:VAR`, `file:PATH`, ...).
+    pub key_passphrase: Option<PassphraseSource>,
+    /// Folder sealed output goes under.
+    pub output: Option<PathBuf>,
+    pub frame_interval: Option<u64>,
+    pub sample_frames: Option<usize>,
+
+    /// Pin seals to IPFS.
+    pub ipfs: Option<bool>,
+    /// Named profile from `ipfs_profiles.json`.
+    pub ipfs_profile: Option<String>,
+    /// Kubo API URL.
+    pub ipfs_url: Option<String>,
+    /// Pinning provider given inline instead of by profile name.
+    pub ipfs_service: Option<IpfsProfile>,
+    pub cid_v1: Option<bool>,
+    pub ipfs_dir: Option<bool>,
+
+    /// Submit seals to OpenTimestamps.
+    pub timestamp: Option<bool>,
+    /// RFC 3161 Time-Stamp Authority URL.
+    pub tsa: Option<String>,
+
+    pub on_prior_seal: Option<PriorSealPolicy>,
+    pub registry: Option<PathBuf>,
+
+    /// Trusted public key for `verify`.
+    pub public_key: Option<PathBuf>,
+    /// Trust store of publisher keys for `verify`.
+    pub trust_store: Option<PathBuf>,
+    /// Trusted signers `verify` requires, counting co-signers.
+    pub require_signers: Option<usize>,
+    /// Trust store names or key IDs that count toward `require_signers`.
+    pub signers: Option<Vec<String>>,
+
+    pub port: Option<u16>,
+    pub static_dir: Option<PathBuf>,
+    pub uploads_dir: Option<PathBuf>,
+
+    /// `watch` poll interval in seconds.
+    pub interval: Option<u64>,
+    /// `watch` settle time in seconds.
+    pub settle: Option<u64>,
+}
+
+impl Settings {
+    /// Fields set in `over` win; the rest come from `self`.
+    pub fn overlay(self, over: Settings) -> Settings {
+        Settings {
+            edge_width: over.edge_width.or(self.edge_width),
+            key: over.key.or(self.key),
+            key_passphrase: over.key_passphrase.or(self.key_passphrase),
+            output: over.output.or(self.output),
+            frame_interval: over.frame_interval.or(self.frame_interval),
+            sample_frames: over.sample_frames.or(self.sample_frames),
+            ipfs: over.ipfs.or(self.ipfs),
+            ipfs_profile: over.ipfs_profile.or(self.ipfs_profile),
+            ipfs_url: over.ipfs_url.or(self.ipfs_url),
+            ipfs_service: over.ipfs_service.or(self.ipfs_service),
+            cid_v1: over.cid_v1.or(self.cid_v1),
+            ipfs_dir: over.ipfs_dir.or(self.ipfs_dir),
+            timestamp: over.timestamp.or(self.timestamp),
+            tsa: over.tsa.or(self.tsa),
+            on_prior_seal: over.on_prior_seal.or(self.on_prior_seal),
+            registry: over.registry.or(self.registry),
+            public_key: over.public_key.or(self.public_key),
+            trust_store: over.trust_store.or(self.trust_store),
+            require_signers: over.require_signers.or(self.require_signers),
+            signers: over.signers.or(self.signers),
+            port: over.port.or(self.port),
+            static_dir: over.static_dir.or(self.static_dir),
+            uploads_dir: over.uploads_dir.or(self.uploads_dir),
+            interval: over.interval.or(self.interval),
+            settle: over.settle.or(self.settle),
+        }
+    }
+
+    /// Clear the fields that decide whom `verify` trusts; true if any was set.
+    fn drop_trust_anchors(&mut self) -> bool {
+        let had = self.public_key.is_some()
+            || self.trust_store.is_some()
+            || self.require_signers.is_some()
+            || self.signers.is_some();
+        self.public_key = None;
+        self.trust_store = None;
+        self.require_signers = None;
+        self.signers = None;
+        had
+    }
+
+    /// Make relative paths relative to `base` rather than the working directory.
+    fn rebase(&mut self, base: &Path) {
+        if let Some(source) = &mut self.key_passphrase {
+            source.rebase(base);
+        }
+        for path in [
+            &mut self.key,
+            &mut self.output,
+            &mut self.registry,
+            &mut self.public_key,
+            &mut self.trust_store,
+            &mut self.static_dir,
+            &mut self.uploads_dir,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if path.is_relative() {
+                *path = base.join(&*path);
+            }
+        }
+    }
+}
+
+/// A `sealed.toml`: top-level defaults plus named profiles layered on them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigFile {
+    /// Profile used when none is named on the command line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub defaults: Settings,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Settings>,
+}
+
+impl ConfigFile {
+    /// Parse one file; relative paths in it are taken from its directory.
+    pub fn load(path: &Path) -> SealedResult<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let mut file: ConfigFile = toml::from_str(&text)
+            .map_err(|e| SealedError::InvalidInput(format!("{}: {}", path.display(), e)))?;
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        file.defaults.rebase(base);
+        for settings in file.profiles.values_mut() {
+            settings.rebase(base);
+        }
+        Ok(file)
+    }
+
+    /// Clear `verify` trust settings everywhere in the file; true if any was set.
+    /// A project file found by searching may come with the folder being
+    /// checked, so it must not choose the keys that folder is checked against.
+    pub fn drop_trust_anchors(&mut self) -> bool {
+        let mut had = self.defaults.drop_trust_anchors();
+        for settings in self.profiles.values_mut() {
+            had |= settings.drop_trust_anchors();
+        }
+        had
+    }
+
+    /// Layer `over` on top of `self`, field by field and profile by profile.
+    pub fn overlay(mut self, over: ConfigFile) -> ConfigFile {
+        for (name, settings) in over.profiles {
+            let merged = match self.profiles.remove(&name) {
+                Some(base) => base.overlay(settings),
+                None => settings,
+            };
+            self.profiles.insert(name, merged);
+        }
+        ConfigFile {
+            default_profile: over.default_profile.or(self.default_profile),
+            defaults: self.defaults.overlay(over.defaults),
+            profiles: self.profiles,
+        }
+    }
+
+    /// The user file, then the nearest project file over it. `explicit`
+    /// replaces the project file search; only the user file or an explicit
+    /// one may set `verify` trust anchors.
+    pub fn discover(explicit: Option<&Path>) -> SealedResult<Self> {
+        let mut config = ConfigFile::default();
+        let user = user_config_path();
+        if user.is_file() {
+            config = config.overlay(ConfigFile::load(&user)?);
+        }
+        let project = match explicit {
+            Some(path) if !path.is_file() => return Err(SealedError::FileNotFound(path.display().to_string())),
+            Some(path) => Some(path.to_path_buf()),
+            None => std::env::current_dir().ok().and_then(|dir| find_project_config(&dir)),
+        };
+        if let Some(project) = project.filter(|p| *p != user) {
+            let mut file = ConfigFile::load(&project)?;
+            if explicit.is_none() && file.drop_trust_anchors() {
+                warn!(
+                    "Ignoring public_key, trust_store, require_signers and signers in {}; set them in {} or pass --config",
+                    project.display(),
+                    user.display()
+                );
+            }
+            config = config.overlay(file);
+        }
+        Ok(config)
+    }
+
+    /// Defaults with `profile` (or the default profile) applied.
+    pub fn settings(&self, profile: Option<&str>) -> SealedResult<Settings> {
+        let Some(name) = profile.or(self.default_profile.as_deref()) else {
+            return Ok(self.defaults.clone());
+        };
+        let selected = self.profiles.get(name).ok_or_else(|| {
+            SealedError::InvalidInput(format!(
+                "No profile '{}' in {} (have: {})",
+                name,
+                CONFIG_FILE,
+                self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            ))
+        })?;
+        Ok(self.defaults.clone().overlay(selected.clone()))
+    }
+}
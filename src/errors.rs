@@ -18,6 +18,9 @@ pub enum SealedError {
     #[error("Signature error: {0}")]
     Signature(#[from] ed25519_dalek::SignatureError),
 
+    #[error("ASN.1 decoding error: {0}")]
+    Der(#[from] der::Error),
+
     #[error("HTTP request error: {0}")]
     Http(#[from] reqwest::Error),
 
@@ -39,6 +42,9 @@ pub enum SealedError {
     #[error("Key error: {0}")]
     KeyError(String),
 
+    #[error("Already sealed: {0}")]
+    PriorSeal(String),
+
     #[error("File not found: {0}")]
     FileNotFound(String),
 
@@ -182,8 +182,38 @@ impl std::fmt::Display for SimilarityConfidence {
     }
 }
 
+impl SimilarityConfidence {
+    /// Ordering strength: `Exact` highest, `None` lowest.
+    pub fn rank(&self) -> u8 {
+        match self {
+            Self::Exact => 4,
+            Self::High => 3,
+            Self::Medium => 2,
+            Self::Low => 1,
+            Self::None => 0,
+        }
+    }
+}
+
+impl std::str::FromStr for SimilarityConfidence {
+    type Err = crate::errors::SealedError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "exact" => Ok(Self::Exact),
+            "high" => Ok(Self::High),
+            "medium" => Ok(Self::Medium),
+            "low" => Ok(Self::Low),
+            "none" => Ok(Self::None),
+            _ => Err(crate::errors::SealedError::InvalidInput(format!(
+                "Unknown confidence '{}' (expected exact, high, medium, low or none)", s
+            ))),
+        }
+    }
+}
+
 /// Compare two hash records.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SimilarityReport {
     pub exact_match: bool,
     pub sha256_match: bool,
@@ -253,9 +253,9 @@ pub fn save_artifacts(
 /// Open an image with content-based format detection. Falls back to ffmpeg.
 pub fn open_image_by_content(path: &Path) -> SealedResult<DynamicImage> {
     let reader = ImageReader::open(path)
-        .map_err(|e| SealedError::Io(e))?
+        .map_err(SealedError::Io)?
         .with_guessed_format()
-        .map_err(|e| SealedError::Io(e))?;
+        .map_err(SealedError::Io)?;
 
     match reader.decode() {
         Ok(img) => Ok(img),
@@ -295,7 +295,7 @@ fn convert_with_ffmpeg(path: &Path) -> SealedResult<DynamicImage> {
         });
     }
 
-    let img = image::open(&temp_png).map_err(|e| SealedError::Image(e))?;
+    let img = image::open(&temp_png).map_err(SealedError::Image)?;
 
     let _ = std::fs::remove_file(&temp_png);
 
@@ -0,0 +1,233 @@
+use std::path::Path;
+use image::RgbaImage;
+use serde::{Serialize, Deserialize};
+use tracing::info;
+
+use crate::errors::{SealedError, SealedResult};
+use crate::hashing::{compute_hash_record, HashRecord};
+use crate::image_processing::open_image_by_content;
+use crate::signing::SignedEnvelope;
+use crate::verification::SealedRecord;
+
+/// What was found for one artifact file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum ArtifactStatus {
+    Intact,
+    Missing,
+    /// Present but does not decode as an image.
+    Unreadable { error: String },
+    /// Decodes, but these hash fields differ from the record.
+    Tampered { fields: Vec<String> },
+}
+
+/// One artifact file and its status.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactCheck {
+    pub file: String,
+    #[serde(flatten)]
+    pub status: ArtifactStatus,
+}
+
+/// Whether a sealed directory's artifacts still match its hash record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    /// The record artifacts were checked against: the signed payload in
+    /// `signed_record.json` when there is one, otherwise `hashes.json`.
+    pub checked_against: String,
+    /// Fields of `hashes.json` that differ from the signed payload.
+    pub record_mismatches: Vec<String>,
+    pub artifacts: Vec<ArtifactCheck>,
+    /// Whether frame.png and cropped.png recombine into original.png. `None`
+    /// when one of the three could not be read.
+    pub recombines: Option<bool>,
+}
+
+impl IntegrityReport {
+    pub fn is_intact(&self) -> bool {
+        self.record_mismatches.is_empty()
+            && self.artifacts.iter().all(|a| a.status == ArtifactStatus::Intact)
+            && self.recombines == Some(true)
+    }
+
+    /// One line per problem found, empty when intact.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .record_mismatches
+            .iter()
+            .map(|field| format!("hashes.json {} differs from the signed record", field))
+            .collect();
+        for a in &self.artifacts {
+            match &a.status {
+                ArtifactStatus::Intact => {}
+                ArtifactStatus::Missing => problems.push(format!("{} is missing", a.file)),
+                ArtifactStatus::Unreadable { error } => problems.push(format!("{} cannot be decoded: {}", a.file, error)),
+                ArtifactStatus::Tampered { fields } => {
+                    problems.push(format!("{} does not match its recorded {}", a.file, fields.join(", ")))
+                }
+            }
+        }
+        match self.recombines {
+            Some(true) => {}
+            Some(false) => problems.push("frame.png and cropped.png do not recombine into original.png".to_string()),
+            None => problems.push("frame.png and cropped.png could not be recombined".to_string()),
+        }
+        problems
+    }
+}
+
+/// Re-decode every artifact in `sealed_dir`, recompute its hashes and compare
+/// them with the signed record (or `hashes.json` when unsigned). Also checks
+/// that `hashes.json` agrees with the signed payload and that frame plus
+/// cropped recombine into the original.
+pub fn check_artifacts(sealed_dir: &Path) -> SealedResult<IntegrityReport> {
+    let record_path = sealed_dir.join("hashes.json");
+    let stored: Option<SealedRecord> = match std::fs::read_to_string(&record_path) {
+        Ok(json) => Some(serde_json::from_str(&json)?),
+        Err(_) => None,
+    };
+    let signed: Option<SealedRecord> = match std::fs::read_to_string(sealed_dir.join("signed_record.json")) {
+        Ok(json) => {
+            let envelope: SignedEnvelope = serde_json::from_str(&json)?;
+            Some(serde_json::from_str(&envelope.payload).map_err(|e| {
+                SealedError::VerificationFailed(format!("signed_record.json payload is not a hash record: {}", e))
+            })?)
+        }
+        Err(_) => None,
+    };
+
+    let mut artifacts = Vec::new();
+    let record_mismatches = match (&stored, &signed) {
+        (Some(stored), Some(signed)) => record_differences(stored, signed),
+        _ => Vec::new(),
+    };
+    if stored.is_none() && signed.is_some() {
+        artifacts.push(ArtifactCheck { file: "hashes.json".to_string(), status: ArtifactStatus::Missing });
+    }
+    let (record, checked_against) = match (signed, stored) {
+        (Some(signed), _) => (signed, "signed_record.json"),
+        (None, Some(stored)) => (stored, "hashes.json"),
+        (None, None) => {
+            return Err(SealedError::FileNotFound(format!(
+                "hashes.json not found in {}",
+                sealed_dir.display()
+            )))
+        }
+    };
+
+    let mut decoded = |file: &str, expected: &HashRecord| -> Option<RgbaImage> {
+        let path = sealed_dir.join(file);
+        let (status, image) = if !path.exists() {
+            (ArtifactStatus::Missing, None)
+        } else {
+            match open_image_by_content(&path) {
+                Err(e) => (ArtifactStatus::Unreadable { error: e.to_string() }, None),
+                Ok(img) => {
+                    let status = match compute_hash_record(&img) {
+                        Ok(actual) => {
+                            let fields = hash_differences(expected, &actual);
+                            if fields.is_empty() {
+                                ArtifactStatus::Intact
+                            } else {
+                                ArtifactStatus::Tampered { fields }
+                            }
+                        }
+                        Err(e) => ArtifactStatus::Unreadable { error: e.to_string() },
+                    };
+                    (status, Some(img.to_rgba8()))
+                }
+            }
+        };
+        if status != ArtifactStatus::Intact {
+            info!("Artifact {}: {:?}", file, status);
+        }
+        artifacts.push(ArtifactCheck { file: file.to_string(), status });
+        image
+    };
+
+    let original = decoded("original.png", &record.original);
+    let frame = decoded("frame.png", &record.frame);
+    let cropped = decoded("cropped.png", &record.cropped);
+    // Records from before share.png existed carry no share hashes.
+    if let Some(share) = &record.share {
+        decoded("share.png", share);
+    }
+    decoded("recombined.png", &record.recombined);
+
+    let recombines = match (&original, &frame, &cropped) {
+        (Some(original), Some(frame), Some(cropped)) => Some(recombine(frame, cropped).as_ref() == Some(original)),
+        _ => None,
+    };
+
+    Ok(IntegrityReport {
+        checked_against: checked_against.to_string(),
+        record_mismatches,
+        artifacts,
+        recombines,
+    })
+}
+
+/// Frame and cropped each hold what the other leaves transparent black, so
+/// the original is their union. `None` if they differ in size or overlap.
+fn recombine(frame: &RgbaImage, cropped: &RgbaImage) -> Option<RgbaImage> {
+    if frame.dimensions() != cropped.dimensions() {
+        return None;
+    }
+    let mut out = frame.clone();
+    for (o, c) in out.pixels_mut().zip(cropped.pixels()) {
+        if c.0 == [0; 4] {
+            continue;
+        }
+        if o.0 != [0; 4] {
+            return None;
+        }
+        *o = *c;
+    }
+    Some(out)
+}
+
+fn hash_differences(expected: &HashRecord, actual: &HashRecord) -> Vec<String> {
+    [
+        ("sha256", expected.sha256 != actual.sha256),
+        ("blake3", expected.blake3 != actual.blake3),
+        ("ahash", expected.ahash != actual.ahash),
+        ("dhash", expected.dhash != actual.dhash),
+        // Records from before pHash was added leave it empty.
+        ("phash", !expected.phash.is_empty() && expected.phash != actual.phash),
+        ("width", expected.width != actual.width),
+        ("height", expected.height != actual.height),
+    ]
+    .into_iter()
+    .filter(|(_, differs)| *differs)
+    .map(|(field, _)| field.to_string())
+    .collect()
+}
+
+/// `artifact.field` names where `stored` differs from `signed`.
+fn record_differences(stored: &SealedRecord, signed: &SealedRecord) -> Vec<String> {
+    let mut fields = Vec::new();
+    let pairs = [
+        ("original", Some(&stored.original), Some(&signed.original)),
+        ("frame", Some(&stored.frame), Some(&signed.frame)),
+        ("cropped", Some(&stored.cropped), Some(&signed.cropped)),
+        ("recombined", Some(&stored.recombined), Some(&signed.recombined)),
+        ("share", stored.share.as_ref(), signed.share.as_ref()),
+    ];
+    for (name, a, b) in pairs {
+        match (a, b) {
+            (Some(a), Some(b)) => fields.extend(hash_differences(b, a).into_iter().map(|f| format!("{}.{}", name, f))),
+            (None, None) => {}
+            _ => fields.push(name.to_string()),
+        }
+    }
+    if serde_json::to_value(&stored.tile_index).ok() != serde_json::to_value(&signed.tile_index).ok() {
+        fields.push("tile_index".to_string());
+    }
+    if stored.sealed_at != signed.sealed_at {
+        fields.push("sealed_at".to_string());
+    }
+    if stored.sealed_version != signed.sealed_version {
+        fields.push("sealed_version".to_string());
+    }
+    fields
+}
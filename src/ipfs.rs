@@ -2,31 +2,94 @@ use serde::{Serialize, Deserialize};
 use std::path::Path;
 use tracing::info;
 
+use crate::cid::{cid_for_bytes, directory_block, import_file, Block, Cid, CidVersion, Link, CHUNK_SIZE};
 use crate::errors::{SealedError, SealedResult};
 
 /// IPFS pin response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IpfsRecord {
+    /// CID computed locally and confirmed by the pinning service.
     pub cid: String,
     pub gateway_url: String,
     pub service: String,
     pub pinned_at: String,
+    /// Name of the pinned file within the sealed directory.
+    #[serde(default)]
+    pub file: String,
+    /// Per-file CIDs when the whole sealed directory was pinned under `cid`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<IpfsFileEntry>,
+    /// Pinning Service request id, needed to check on or remove the pin.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// One file inside a directory pin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpfsFileEntry {
+    /// Path relative to the sealed directory, `/`-separated.
+    pub path: String,
+    pub cid: String,
+}
+
+/// Files written after a directory pin, so never part of the pinned tree.
+const DIR_PIN_SKIPPED: &[&str] = &[
+    "ipfs_record.json",
+    "ipfs_signed_record.json",
+    "ipfs_ots_record.json",
+    "ots_upgrade.log",
+];
+
+/// Which API a pin request speaks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum IpfsProvider {
+    /// Kubo RPC API (`/api/v0`) on a node you run.
+    #[default]
+    Kubo,
+    /// Pinata's upload API.
+    Pinata,
+    /// Vendor-neutral IPFS Pinning Service API (`/pins`).
+    PinningService,
+}
+
+impl IpfsProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IpfsProvider::Kubo => "kubo",
+            IpfsProvider::Pinata => "pinata",
+            IpfsProvider::PinningService => "pinning-service",
+        }
+    }
 }
 
 /// IPFS pinning config.
 #[derive(Debug, Clone)]
 pub struct IpfsConfig {
+    pub provider: IpfsProvider,
     pub api_url: String,
+    /// Bearer token for Pinata or a Pinning Service.
     pub api_key: Option<String>,
     pub gateway_url: String,
+    /// CIDv1 also switches the import to raw leaves.
+    pub cid_version: CidVersion,
+    /// Kubo node that uploads the content before a Pinning Service is asked
+    /// to pin it by CID.
+    pub source_node: Option<String>,
+    /// How long to poll a Pinning Service request before leaving it queued.
+    pub poll_timeout_secs: u64,
 }
 
 impl Default for IpfsConfig {
     fn default() -> Self {
         Self {
+            provider: IpfsProvider::Kubo,
             api_url: "http://127.0.0.1:5001".to_string(),
             api_key: None,
             gateway_url: "https://ipfs.io/ipfs".to_string(),
+            cid_version: CidVersion::V0,
+            source_node: None,
+            poll_timeout_secs: 60,
         }
     }
 }
@@ -35,9 +98,11 @@ impl IpfsConfig {
     /// Pinata pinning service.
     pub fn pinata(api_key: &str) -> Self {
         Self {
+            provider: IpfsProvider::Pinata,
             api_url: "https://api.pinata.cloud".to_string(),
             api_key: Some(api_key.to_string()),
             gateway_url: "https://gateway.pinata.cloud/ipfs".to_string(),
+            ..Self::default()
         }
     }
 
@@ -45,6 +110,56 @@ impl IpfsConfig {
     pub fn local() -> Self {
         Self::default()
     }
+
+    /// A Pinning Service API endpoint, e.g. `https://api.example.com/psa`.
+    pub fn pinning_service(endpoint: &str, token: &str) -> Self {
+        Self {
+            provider: IpfsProvider::PinningService,
+            api_url: endpoint.trim_end_matches('/').to_string(),
+            api_key: Some(token.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// A Kubo node at `api_url`.
+    pub fn kubo(api_url: &str) -> Self {
+        Self {
+            api_url: api_url.trim_end_matches('/').to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Settings for a provider named outright, as the web UI sends them.
+    /// Kubo takes no key and the hosted services need one; nothing is inferred.
+    pub fn for_provider(provider: IpfsProvider, api_url: &str, api_key: Option<&str>) -> SealedResult<Self> {
+        match (provider, api_key) {
+            (IpfsProvider::Kubo, None) => Ok(Self::kubo(api_url)),
+            (IpfsProvider::Kubo, Some(_)) => Err(SealedError::IpfsError(
+                "A Kubo node takes no API key; name the provider the key is for".to_string(),
+            )),
+            (_, None) => Err(SealedError::IpfsError(format!("{} needs an API key", provider.as_str()))),
+            (IpfsProvider::Pinata, Some(key)) => Ok(Self {
+                api_url: api_url.trim_end_matches('/').to_string(),
+                ..Self::pinata(key)
+            }),
+            (IpfsProvider::PinningService, Some(key)) => Ok(Self::pinning_service(api_url, key)),
+        }
+    }
+}
+
+/// Fail unless the CID a service returned is the one computed locally.
+fn check_remote_cid(expected: &Cid, returned: &str, service: &str) -> SealedResult<()> {
+    let matches = returned
+        .parse::<Cid>()
+        .map(|cid| cid.same_content(expected))
+        .unwrap_or(false);
+    if !matches {
+        return Err(SealedError::IpfsError(format!(
+            "{} returned CID {} but the content hashes to {}",
+            service, returned, expected
+        )));
+    }
+    Ok(())
 }
 
 /// Pin a file via a local IPFS node.
@@ -60,13 +175,22 @@ pub fn pin_to_local_ipfs(file_path: &Path, config: &IpfsConfig) -> SealedResult<
         .to_string_lossy()
         .to_string();
 
-    let url = format!("{}/api/v0/add", config.api_url);
+    let expected = cid_for_bytes(&file_data, config.cid_version);
+    let url = format!(
+        "{}/api/v0/add?cid-version={}&chunker=size-{}",
+        config.api_url,
+        match config.cid_version {
+            CidVersion::V0 => 0,
+            CidVersion::V1 => 1,
+        },
+        CHUNK_SIZE
+    );
 
-    info!("Pinning {} to IPFS at {}", file_name, config.api_url);
+    info!("Pinning {} to IPFS at {} (expected CID {})", file_name, config.api_url, expected);
 
     let form = reqwest::blocking::multipart::Form::new()
         .part("file", reqwest::blocking::multipart::Part::bytes(file_data)
-            .file_name(file_name));
+            .file_name(file_name.clone()));
 
     let client = reqwest::blocking::Client::new();
     let response = client
@@ -94,6 +218,7 @@ pub fn pin_to_local_ipfs(file_path: &Path, config: &IpfsConfig) -> SealedResult<
         .as_str()
         .ok_or_else(|| SealedError::IpfsError("No CID in IPFS response".to_string()))?
         .to_string();
+    check_remote_cid(&expected, &cid, "IPFS node")?;
 
     let gateway = format!("{}/{}", config.gateway_url, cid);
     let pinned_at = chrono::Utc::now().to_rfc3339();
@@ -105,6 +230,9 @@ pub fn pin_to_local_ipfs(file_path: &Path, config: &IpfsConfig) -> SealedResult<
         gateway_url: gateway,
         service: "local-ipfs".to_string(),
         pinned_at,
+        file: file_name,
+        files: Vec::new(),
+        request_id: None,
     })
 }
 
@@ -125,13 +253,21 @@ pub fn pin_to_pinata(file_path: &Path, config: &IpfsConfig) -> SealedResult<Ipfs
         .to_string_lossy()
         .to_string();
 
+    let expected = cid_for_bytes(&file_data, config.cid_version);
     let url = format!("{}/pinning/pinFileToIPFS", config.api_url);
 
-    info!("Pinning {} to Pinata", file_name);
+    info!("Pinning {} to Pinata (expected CID {})", file_name, expected);
 
+    let options = serde_json::json!({
+        "cidVersion": match config.cid_version {
+            CidVersion::V0 => 0,
+            CidVersion::V1 => 1,
+        }
+    });
     let form = reqwest::blocking::multipart::Form::new()
         .part("file", reqwest::blocking::multipart::Part::bytes(file_data)
-            .file_name(file_name));
+            .file_name(file_name.clone()))
+        .text("pinataOptions", options.to_string());
 
     let client = reqwest::blocking::Client::new();
     let response = client
@@ -157,6 +293,7 @@ pub fn pin_to_pinata(file_path: &Path, config: &IpfsConfig) -> SealedResult<Ipfs
         .as_str()
         .ok_or_else(|| SealedError::IpfsError("No CID in Pinata response".to_string()))?
         .to_string();
+    check_remote_cid(&expected, &cid, "Pinata")?;
 
     let gateway = format!("{}/{}", config.gateway_url, cid);
     let pinned_at = chrono::Utc::now().to_rfc3339();
@@ -168,14 +305,234 @@ pub fn pin_to_pinata(file_path: &Path, config: &IpfsConfig) -> SealedResult<Ipfs
         gateway_url: gateway,
         service: "pinata".to_string(),
         pinned_at,
+        file: file_name,
+        files: Vec::new(),
+        request_id: None,
     })
 }
 
-/// Pin a file to IPFS (auto-detects local vs Pinata).
+/// A sealed directory imported as one UnixFS tree.
+#[derive(Debug, Clone)]
+pub struct DirImport {
+    pub root: Cid,
+    pub blocks: Vec<Block>,
+    pub files: Vec<IpfsFileEntry>,
+    /// Relative directory paths, parents before children.
+    pub dirs: Vec<String>,
+}
+
+/// Import a sealed directory the way `ipfs add -r --wrap-with-directory` would.
+/// Hidden files and the IPFS records themselves are left out.
+pub fn import_sealed_dir(dir: &Path, version: CidVersion) -> SealedResult<DirImport> {
+    if !dir.is_dir() {
+        return Err(SealedError::FileNotFound(dir.display().to_string()));
+    }
+    let mut import = DirImport {
+        root: cid_for_bytes(&[], version),
+        blocks: Vec::new(),
+        files: Vec::new(),
+        dirs: Vec::new(),
+    };
+    let (root, _) = import_dir_level(dir, "", version, &mut import)?;
+    import.root = root;
+    Ok(import)
+}
+
+/// Import one directory level; returns its CID and cumulative size.
+fn import_dir_level(
+    dir: &Path,
+    prefix: &str,
+    version: CidVersion,
+    import: &mut DirImport,
+) -> SealedResult<(Cid, u64)> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+
+    let mut links = Vec::new();
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || (prefix.is_empty() && DIR_PIN_SKIPPED.contains(&name.as_str())) {
+            continue;
+        }
+        let rel = if prefix.is_empty() { name.clone() } else { format!("{}/{}", prefix, name) };
+        let path = entry.path();
+
+        let (cid, tsize) = if path.is_dir() {
+            import.dirs.push(rel.clone());
+            import_dir_level(&path, &rel, version, import)?
+        } else {
+            let blocks = import_file(&std::fs::read(&path)?, version);
+            let cid = blocks.last().expect("import yields a root").cid;
+            let tsize = blocks.iter().map(|b| b.data.len() as u64).sum();
+            import.blocks.extend(blocks);
+            import.files.push(IpfsFileEntry { path: rel, cid: cid.to_string() });
+            (cid, tsize)
+        };
+        links.push(Link { cid, name, tsize });
+    }
+
+    let child_size: u64 = links.iter().map(|l| l.tsize).sum();
+    let block = directory_block(links, version);
+    let result = (block.cid, block.data.len() as u64 + child_size);
+    import.blocks.push(block);
+    Ok(result)
+}
+
+/// Export a sealed directory as a CARv1 file with a single root CID.
+pub fn export_car(dir: &Path, car_path: &Path, version: CidVersion) -> SealedResult<IpfsRecord> {
+    let import = import_sealed_dir(dir, version)?;
+    crate::car::write_car(car_path, &import.root, &import.blocks)?;
+    info!("Wrote CAR {} (root {})", car_path.display(), import.root);
+
+    Ok(IpfsRecord {
+        cid: import.root.to_string(),
+        gateway_url: format!("{}/{}", IpfsConfig::default().gateway_url, import.root),
+        service: "car".to_string(),
+        pinned_at: chrono::Utc::now().to_rfc3339(),
+        file: String::new(),
+        files: import.files,
+        request_id: None,
+    })
+}
+
+/// Upload a whole sealed directory to a local node via `/api/v0/add` with
+/// `wrap-with-directory`, failing if the node's root CID differs from ours.
+/// Pinning Services are asked to pin the locally computed root instead.
+pub fn pin_directory(dir: &Path, config: &IpfsConfig) -> SealedResult<IpfsRecord> {
+    match config.provider {
+        IpfsProvider::Kubo => {}
+        IpfsProvider::PinningService => return crate::pinning::pin_directory_to_service(dir, config),
+        IpfsProvider::Pinata => {
+            return Err(SealedError::IpfsError(
+                "Directory pinning needs a local IPFS node or a Pinning Service; export a CAR for Pinata".to_string(),
+            ));
+        }
+    }
+    let import = import_sealed_dir(dir, config.cid_version)?;
+
+    let mut form = reqwest::blocking::multipart::Form::new();
+    for rel in &import.dirs {
+        let part = reqwest::blocking::multipart::Part::bytes(Vec::new())
+            .file_name(escape_path(rel))
+            .mime_str("application/x-directory")?;
+        form = form.part("file", part);
+    }
+    for file in &import.files {
+        let data = std::fs::read(dir.join(&file.path))?;
+        let part = reqwest::blocking::multipart::Part::bytes(data)
+            .file_name(escape_path(&file.path))
+            .mime_str("application/octet-stream")?;
+        form = form.part("file", part);
+    }
+
+    let url = format!(
+        "{}/api/v0/add?wrap-with-directory=true&cid-version={}&chunker=size-{}",
+        config.api_url,
+        match config.cid_version {
+            CidVersion::V0 => 0,
+            CidVersion::V1 => 1,
+        },
+        CHUNK_SIZE
+    );
+    info!("Pinning directory {} to IPFS (expected root {})", dir.display(), import.root);
+
+    let response = reqwest::blocking::Client::new()
+        .post(&url)
+        .multipart(form)
+        .send()
+        .map_err(|e| SealedError::IpfsError(format!(
+            "Failed to connect to IPFS node at {}: {}. Is your IPFS daemon running?",
+            config.api_url, e
+        )))?;
+    if !response.status().is_success() {
+        return Err(SealedError::IpfsError(format!(
+            "IPFS API returned status {}: {}",
+            response.status(),
+            response.text().unwrap_or_default()
+        )));
+    }
+
+    // One JSON object per added entry; the wrapping directory has an empty name.
+    let body = response.text()?;
+    let root = body
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|entry| entry["Name"].as_str() == Some(""))
+        .and_then(|entry| entry["Hash"].as_str().map(|h| h.to_string()))
+        .ok_or_else(|| SealedError::IpfsError("No wrapping directory in IPFS response".to_string()))?;
+    check_remote_cid(&import.root, &root, "IPFS node")?;
+
+    info!("Pinned directory to IPFS: root CID={}", root);
+
+    Ok(IpfsRecord {
+        gateway_url: format!("{}/{}", config.gateway_url, root),
+        cid: root,
+        service: "local-ipfs".to_string(),
+        pinned_at: chrono::Utc::now().to_rfc3339(),
+        file: String::new(),
+        files: import.files,
+        request_id: None,
+    })
+}
+
+/// Percent-encode a multipart file name the way Kubo's client does.
+fn escape_path(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Pin a file with the configured provider.
 pub fn pin_to_ipfs(file_path: &Path, config: &IpfsConfig) -> SealedResult<IpfsRecord> {
-    if config.api_key.is_some() {
-        pin_to_pinata(file_path, config)
-    } else {
-        pin_to_local_ipfs(file_path, config)
+    match config.provider {
+        IpfsProvider::Kubo => pin_to_local_ipfs(file_path, config),
+        IpfsProvider::Pinata => pin_to_pinata(file_path, config),
+        IpfsProvider::PinningService => crate::pinning::pin_to_service(file_path, config),
+    }
+}
+
+/// Result of checking a sealed file against its recorded CID.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CidCheck {
+    pub file: String,
+    pub recorded_cid: String,
+    pub computed_cid: String,
+    pub matches: bool,
+}
+
+/// Recompute the CID of the file named in `ipfs_record.json` and compare.
+/// Returns `None` if the directory was never pinned.
+pub fn check_recorded_cid(sealed_dir: &Path) -> SealedResult<Option<CidCheck>> {
+    let record_path = sealed_dir.join("ipfs_record.json");
+    if !record_path.exists() {
+        return Ok(None);
+    }
+    let record: IpfsRecord = serde_json::from_str(&std::fs::read_to_string(&record_path)?)?;
+
+    // Directory pins: check the record file's own entry. Records written
+    // before `file` existed always pinned hashes.json.
+    let (file, recorded_cid) = match record.files.iter().find(|f| {
+        f.path == "hashes.json" || f.path == crate::batch::BATCH_MANIFEST_FILE
+    }) {
+        Some(entry) => (entry.path.clone(), entry.cid.clone()),
+        None if record.file.is_empty() => ("hashes.json".to_string(), record.cid),
+        None => (record.file, record.cid),
+    };
+
+    let recorded: Cid = recorded_cid.parse()?;
+    let path = sealed_dir.join(&file);
+    if !path.exists() {
+        return Err(SealedError::FileNotFound(path.display().to_string()));
     }
+    let computed = cid_for_bytes(&std::fs::read(&path)?, recorded.version);
+
+    Ok(Some(CidCheck {
+        file,
+        recorded_cid,
+        computed_cid: computed.to_string(),
+        matches: computed.same_content(&recorded),
+    }))
 }
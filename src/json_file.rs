@@ -0,0 +1,76 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::errors::SealedResult;
+
+/// Load a JSON state file; a missing file is the empty default.
+pub fn load_or_default<T: DeserializeOwned + Default>(path: &Path) -> SealedResult<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// Write `value` as pretty JSON with `write_atomic`.
+pub fn save_pretty<T: Serialize>(path: &Path, value: &T) -> SealedResult<()> {
+    write_atomic(path, serde_json::to_string_pretty(value)?.as_bytes())
+}
+
+/// `save_pretty` for files holding secrets, via `write_atomic_private`.
+pub fn save_pretty_private<T: Serialize>(path: &Path, value: &T) -> SealedResult<()> {
+    write_atomic_private(path, serde_json::to_string_pretty(value)?.as_bytes())
+}
+
+/// Replace `path` with `contents` via a temp file and a rename, so a crash
+/// leaves the old file or the new one, never half of either. Creates the
+/// parent directory if needed.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> SealedResult<()> {
+    replace(path, contents, false)
+}
+
+/// `write_atomic` for secrets: the file is readable by the owner only
+/// (0600 on Unix) from the moment it is created.
+pub fn write_atomic_private(path: &Path, contents: &[u8]) -> SealedResult<()> {
+    replace(path, contents, true)
+}
+
+fn replace(path: &Path, contents: &[u8], private: bool) -> SealedResult<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = temp_path(path);
+    let written = create(&tmp, private).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|()| std::fs::rename(&tmp, path)) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Create the temp file afresh, so a leftover one cannot lend it looser
+/// permissions.
+fn create(tmp: &Path, private: bool) -> std::io::Result<File> {
+    let _ = std::fs::remove_file(tmp);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    options.open(tmp)
+}
+
+/// `<name>.tmp` next to `path`.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
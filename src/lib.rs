@@ -1,13 +1,33 @@
 pub mod errors;
+pub mod paths;
+pub mod json_file;
+pub mod config;
 pub mod hashing;
+pub mod canonical;
 pub mod signing;
+pub mod key_formats;
+pub mod passphrase;
+pub mod trust;
+pub mod statements;
 pub mod image_processing;
 pub mod video;
 pub mod pdf;
 pub mod archive;
+pub mod batch;
+pub mod registry;
+pub mod scan;
+pub mod watch;
+pub mod cid;
+pub mod car;
 pub mod ipfs;
+pub mod pinning;
+pub mod retrieve;
 pub mod verification;
+pub mod integrity;
 pub mod cli;
 pub mod timestamp;
+pub mod upgrade_queue;
+pub mod ots;
+pub mod tsa;
 pub mod tile_hashing;
 pub mod web_server;
@@ -1,36 +1,97 @@
 use std::path::{Path, PathBuf};
 use std::io::Write;
+use std::process::ExitCode;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use clap::Parser;
-use tracing::{info, error};
+use serde::Serialize;
+use tracing::{info, error, warn};
 use tracing_subscriber::EnvFilter;
 use uuid::Uuid;
 
-use sealed::cli::{Cli, Commands};
+use sealed::cli::{Cli, Commands, ExitStatus, IpfsCommands, KeyCommands, OutputFormat, ProfileCommands, RegistryCommands, TrustCommands};
+use sealed::config::{switch, ConfigFile, Settings};
 use sealed::errors::SealedError;
 use sealed::image_processing::{seal_image, save_artifacts, open_image_by_content, SealConfig};
-use sealed::signing::SealedKeyPair;
-use sealed::verification::{verify_image, SealedRecord};
+use sealed::passphrase::PassphraseSource;
+use sealed::signing::{encrypted_key_format, is_encrypted_key, key_id_from_base64, KdfParams, SealedKeyPair, SignedEnvelope};
+use sealed::key_formats::{
+    export_public_key, export_secret_key, openssh_public_line, parse_public_key, parse_secret_key, write_secret_file, KeyFormat,
+};
+use sealed::statements::{read_statement, revocation_statement, rotation_statement, KeyStatement};
+use sealed::integrity::{check_artifacts, IntegrityReport};
+use sealed::json_file::save_pretty;
+use sealed::trust::{default_trust_store_path, normalize_time, read_public_key, KeyTrust, TrustStore, TrustedKey};
+use sealed::verification::{proven_sealing_time, verify_image_with, SealedRecord, SignatureStatus, Signer, SignerPolicy, TimeAnchors, VerificationResult, Verdict};
 use sealed::archive::create_archive;
-use sealed::ipfs::{pin_to_ipfs, IpfsConfig};
+use sealed::registry::{
+    collect_entries, default_registry_path, enforce_prior_seal_policy, PriorSeal, PriorSealPolicy, Registry,
+};
+use sealed::hashing::{compute_hash_record, HashRecord, SimilarityConfidence};
+use sealed::batch::{BatchManifest, BatchItem, BATCH_MANIFEST_FILE, SIGNED_BATCH_FILE};
+use sealed::ipfs::{check_recorded_cid, CidCheck, export_car, pin_directory, pin_to_ipfs, IpfsConfig, IpfsProvider};
+use sealed::retrieve::{retrieve_sealed_record, FetchSource, RetrievedRecord};
+use sealed::scan::{scan_suspects, ScanConfig};
+use sealed::watch::{FolderWatcher, WATCH_STATE_FILE};
+use sealed::pinning::{
+    default_profiles_path, list_pins, pin_status, resolve_config, unpin, IpfsProfile, IpfsProfiles, PinInfo,
+};
+use sealed::cid::CidVersion;
 use sealed::video::process_video;
 use sealed::pdf::process_pdf;
-use sealed::timestamp::{timestamp_hash, spawn_upgrade_listener, run_upgrade_loop};
+use sealed::timestamp::{
+    timestamp_hash, timestamp_hash_tsa, CalendarConfig, TimestampRecord, TsaConfig, TSR_FILE,
+};
+use sealed::upgrade_queue::{
+    default_queue_path, enqueue, process_queue, run_queue_worker, spawn_queue_worker,
+    QueueEntry, QueueLock, UpgradeQueue,
+};
+use sealed::ots::{verify_proof, BlockHeader, DetachedTimestampFile};
+use sealed::tsa::{load_trust_anchor, verify_token};
 use sealed::tile_hashing::generate_tile_index;
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    let format = cli.format;
 
     let filter = if cli.verbose {
         EnvFilter::new("sealed=debug,info")
     } else {
         EnvFilter::new("sealed=info,warn")
     };
-    tracing_subscriber::fmt()
+    let logs = tracing_subscriber::fmt()
         .with_env_filter(filter)
-        .with_target(false)
-        .init();
+        .with_target(false);
+    // Keep stdout for the JSON document.
+    match format {
+        OutputFormat::Text => logs.init(),
+        OutputFormat::Json => logs.with_writer(std::io::stderr).init(),
+    }
+
+    match run(cli) {
+        Ok(status) => ExitCode::from(status.code()),
+        Err(e) => {
+            match format {
+                OutputFormat::Text => eprintln!("Error: {:?}", e),
+                OutputFormat::Json => println!("{}", serde_json::json!({ "error": format!("{:#}", e) })),
+            }
+            ExitCode::from(ExitStatus::Error.code())
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<ExitStatus> {
+    let format = cli.format;
+    let uses_config = matches!(
+        cli.command,
+        Commands::Seal { .. } | Commands::Verify { .. } | Commands::Serve { .. } | Commands::Watch { .. } | Commands::IpfsPin { .. }
+    );
+    let settings = if cli.no_config || !uses_config {
+        Settings::default()
+    } else {
+        ConfigFile::discover(cli.config.as_deref())?.settings(cli.profile.as_deref())?
+    };
 
     match cli.command {
         Commands::Seal {
@@ -38,30 +99,108 @@ fn main() -> Result<()> {
             output,
             edge_width,
             key,
+            passphrase,
             ipfs,
+            no_ipfs,
             ipfs_url,
             ipfs_key,
+            ipfs_profile,
+            cid_v1,
+            no_cid_v1,
+            ipfs_dir,
+            no_ipfs_dir,
             frame_interval,
             sample_frames,
             timestamp,
+            no_timestamp,
+            tsa,
+            on_prior_seal,
+            registry,
+            prior_seals,
+            no_register,
         } => {
-            cmd_seal(
-                &input, output.as_deref(), edge_width, key.as_deref(),
-                ipfs, &ipfs_url, ipfs_key, frame_interval, sample_frames,
-                timestamp,
+            let cid_version = if switch(cid_v1, no_cid_v1, settings.cid_v1) { CidVersion::V1 } else { CidVersion::V0 };
+            let ipfs_profile = ipfs_profile.or_else(|| settings.ipfs_profile.clone());
+            let ipfs_config = if switch(ipfs, no_ipfs, settings.ipfs) {
+                Some(ipfs_config_from_flags(ipfs_profile.as_deref(), ipfs_url.as_deref(), ipfs_key, cid_version, &settings)?)
+            } else {
+                None
+            };
+            let output = output.or_else(|| settings.output.as_deref().map(|root| sealed_output_dir(root, &input)));
+            // Load the signing key once so an encrypted key prompts a single time per seal.
+            let keypair = load_signing_key_from(
+                key.or(settings.key.clone()).as_deref(),
+                &passphrase.or(settings.key_passphrase.clone()).unwrap_or_default(),
             )?;
+            let summary = cmd_seal(
+                &input, output.as_deref(), edge_width.or(settings.edge_width).unwrap_or(DEFAULT_EDGE_WIDTH),
+                keypair.as_ref(), ipfs_config, ipfs_profile.as_deref(), switch(ipfs_dir, no_ipfs_dir, settings.ipfs_dir),
+                frame_interval.or(settings.frame_interval).unwrap_or(DEFAULT_FRAME_INTERVAL),
+                sample_frames.or(settings.sample_frames),
+                switch(timestamp, no_timestamp, settings.timestamp), tsa.or(settings.tsa.clone()).as_deref(),
+                &PriorSealOptions {
+                    policy: on_prior_seal.or(settings.on_prior_seal).unwrap_or_default(),
+                    registry: registry.or(settings.registry.clone()).unwrap_or_else(default_registry_path),
+                    prior_dirs: prior_seals,
+                    register: !no_register,
+                },
+            )?;
+            print_seal_summary(summary.as_ref(), format)?;
         }
 
         Commands::Verify {
             suspect,
             sealed_dir,
             public_key,
+            cid,
+            signed_cid,
+            gateway,
+            ipfs_url,
+            report,
+            trust_store,
+            require_signers,
+            signers,
+            tsa_anchor,
+            block_header,
         } => {
-            cmd_verify(&suspect, &sealed_dir, public_key.as_deref())?;
+            let trust = load_trust_store(trust_store.or(settings.trust_store.clone()).as_deref())?;
+            let anchors = TimeAnchors {
+                tsa_anchor: tsa_anchor
+                    .map(|path| load_trust_anchor(&path).with_context(|| format!("Failed to load trust anchor {}", path.display())))
+                    .transpose()?,
+                block_header: block_header.as_deref().map(BlockHeader::load).transpose()?,
+            };
+            let signers = if signers.is_empty() { settings.signers.clone().unwrap_or_default() } else { signers };
+            let policy = match require_signers.or(settings.require_signers) {
+                Some(required) => Some(SignerPolicy::new(required, signers)?),
+                None if !signers.is_empty() => Some(SignerPolicy::new(signers.len(), signers)?),
+                None => None,
+            };
+            match (sealed_dir, cid, public_key.or(settings.public_key.clone())) {
+                (Some(sealed_dir), _, public_key) => {
+                    return cmd_verify(
+                        &suspect, &sealed_dir, public_key.as_deref(), &trust, &anchors, policy.as_ref(), report.as_deref(), None, format,
+                    );
+                }
+                (None, Some(cid), public_key) => {
+                    let source = match ipfs_url {
+                        Some(url) => FetchSource::Node(url),
+                        None => FetchSource::Gateway(gateway.unwrap_or_else(|| IpfsConfig::default().gateway_url)),
+                    };
+                    return cmd_verify_cid(
+                        &suspect, &cid, signed_cid.as_deref(), &source, public_key.as_deref(), &trust, &anchors, policy.as_ref(), report.as_deref(), format,
+                    );
+                }
+                (None, None, _) => unreachable!("clap requires SEALED_DIR or --cid"),
+            }
         }
 
         Commands::Keygen { output, password } => {
-            cmd_keygen(&output, password)?;
+            cmd_keygen(&output, password, format)?;
+        }
+
+        Commands::Key { command } => {
+            cmd_key(command, format)?;
         }
 
         Commands::Serve {
@@ -69,43 +208,212 @@ fn main() -> Result<()> {
             static_dir,
             uploads_dir,
             key,
+            passphrase,
+            ots_queue,
+            no_ots_worker,
         } => {
+            let ots_queue = if no_ots_worker {
+                None
+            } else {
+                Some(ots_queue.unwrap_or_else(default_queue_path))
+            };
             sealed::web_server::run_server(sealed::web_server::ServeConfig {
-                port,
-                static_dir,
-                uploads_dir,
-                key_path: key,
+                port: port.or(settings.port).unwrap_or(8000),
+                static_dir: static_dir.or(settings.static_dir.clone()).unwrap_or_else(|| PathBuf::from("static")),
+                uploads_dir: uploads_dir.or(settings.uploads_dir.clone()).unwrap_or_else(|| PathBuf::from("uploads")),
+                keypair: load_signing_key_from(
+                    key.or(settings.key.clone()).as_deref(),
+                    &passphrase.or(settings.key_passphrase.clone()).unwrap_or_default(),
+                )?,
+                ots_queue,
             })?;
         }
 
-        Commands::OtsUpgrade { hash, output_dir, ipfs_url, ipfs_key } => {
-            run_upgrade_loop(&hash, &output_dir, ipfs_url.as_deref(), ipfs_key.as_deref());
+        Commands::Check { sealed_dir } => {
+            return cmd_check(&sealed_dir, format);
+        }
+
+        Commands::Cosign { sealed_dir, key } => {
+            cmd_cosign(&sealed_dir, &key, format)?;
+        }
+
+        Commands::VerifyProof { proof, hash, block_header, tsa_anchor } => {
+            cmd_verify_proof(&proof, hash.as_deref(), block_header.as_deref(), tsa_anchor.as_deref())?;
+        }
+
+        Commands::OtsUpgrade { all, once, output_dir, hash, queue, ipfs_url, ipfs_profile } => {
+            let queue = queue.unwrap_or_else(default_queue_path);
+            cmd_ots_upgrade(
+                &queue, all, once, output_dir.as_deref(), hash.as_deref(),
+                ipfs_url.as_deref(), ipfs_profile.as_deref(),
+            )?;
+        }
+
+        Commands::Status { queue } => {
+            cmd_status(&queue.unwrap_or_else(default_queue_path))?;
         }
 
         Commands::IpfsPin {
             sealed_dir,
             ipfs_url,
             ipfs_key,
+            ipfs_profile,
+            cid_v1,
+            no_cid_v1,
+            dir,
+            car,
+        } => {
+            let cid_version = if switch(cid_v1, no_cid_v1, settings.cid_v1) { CidVersion::V1 } else { CidVersion::V0 };
+            if let Some(car_path) = car {
+                cmd_ipfs_car(&sealed_dir, &car_path, cid_version, format)?;
+            } else {
+                let config = ipfs_config_from_flags(ipfs_profile.as_deref(), ipfs_url.as_deref(), ipfs_key, cid_version, &settings)?;
+                cmd_ipfs_pin(&sealed_dir, &config, dir, format)?;
+            }
+        }
+
+        Commands::Trust { store, command } => {
+            cmd_trust(&store.unwrap_or_else(default_trust_store_path), command, format)?;
+        }
+
+        Commands::Registry { registry, command } => {
+            cmd_registry(&registry.unwrap_or_else(default_registry_path), command)?;
+        }
+
+        Commands::Scan { suspects_dir, sealed_root, output, min_confidence, exhaustive_tiles, jobs, public_key } => {
+            let config = ScanConfig {
+                min_confidence: min_confidence.parse()?,
+                exhaustive_tiles,
+                jobs,
+                public_key,
+            };
+            cmd_scan(&suspects_dir, &sealed_root, output.as_deref(), &config)?;
+        }
+
+        Commands::Watch {
+            dir,
+            output,
+            state,
+            interval,
+            settle,
+            once,
+            edge_width,
+            key,
+            passphrase,
+            ipfs,
+            no_ipfs,
+            ipfs_url,
+            ipfs_key,
+            ipfs_profile,
+            cid_v1,
+            no_cid_v1,
+            ipfs_dir,
+            no_ipfs_dir,
+            frame_interval,
+            sample_frames,
+            timestamp,
+            no_timestamp,
+            tsa,
+            on_prior_seal,
+            registry,
+            no_register,
         } => {
-            cmd_ipfs_pin(&sealed_dir, &ipfs_url, ipfs_key)?;
+            let cid_version = if switch(cid_v1, no_cid_v1, settings.cid_v1) { CidVersion::V1 } else { CidVersion::V0 };
+            let ipfs_profile = ipfs_profile.or_else(|| settings.ipfs_profile.clone());
+            let ipfs_config = if switch(ipfs, no_ipfs, settings.ipfs) {
+                Some(ipfs_config_from_flags(ipfs_profile.as_deref(), ipfs_url.as_deref(), ipfs_key, cid_version, &settings)?)
+            } else {
+                None
+            };
+            let keypair = load_signing_key_from(
+                key.or(settings.key.clone()).as_deref(),
+                &passphrase.or(settings.key_passphrase.clone()).unwrap_or_default(),
+            )?;
+            let output = output.or(settings.output.clone()).unwrap_or_else(|| PathBuf::from("sealed"));
+            let edge_width = edge_width.or(settings.edge_width).unwrap_or(DEFAULT_EDGE_WIDTH);
+            let frame_interval = frame_interval.or(settings.frame_interval).unwrap_or(DEFAULT_FRAME_INTERVAL);
+            let sample_frames = sample_frames.or(settings.sample_frames);
+            let ipfs_dir = switch(ipfs_dir, no_ipfs_dir, settings.ipfs_dir);
+            let timestamp = switch(timestamp, no_timestamp, settings.timestamp);
+            let tsa = tsa.or(settings.tsa.clone());
+            let interval = interval.or(settings.interval).unwrap_or(2);
+            let settle = settle.or(settings.settle).unwrap_or(5);
+
+            let state = state.unwrap_or_else(|| dir.join(WATCH_STATE_FILE));
+            let mut watcher = FolderWatcher::new(&dir, &state, Duration::from_secs(settle), is_media_file)?;
+            info!("Watching {} (state in {})", dir.display(), state.display());
+            let prior = PriorSealOptions {
+                policy: on_prior_seal.or(settings.on_prior_seal).unwrap_or_default(),
+                registry: registry.or(settings.registry.clone()).unwrap_or_else(default_registry_path),
+                prior_dirs: Vec::new(),
+                register: !no_register,
+            };
+            loop {
+                for file in watcher.poll()? {
+                    let sealed_dir = sealed_output_dir(&output, &file.path);
+                    info!("Sealing {}", file.path.display());
+                    let outcome = cmd_seal(
+                        &file.path, Some(&sealed_dir), edge_width, keypair.as_ref(),
+                        ipfs_config.clone(), ipfs_profile.as_deref(), ipfs_dir, frame_interval, sample_frames,
+                        timestamp, tsa.as_deref(), &prior,
+                    );
+                    let outcome = match outcome {
+                        Ok(summary) => {
+                            match format {
+                                OutputFormat::Text => print_seal_summary(summary.as_ref(), format)?,
+                                OutputFormat::Json => println!("{}", serde_json::to_string(&summary)?),
+                            }
+                            Ok(summary.map(|s| s.output).unwrap_or(sealed_dir))
+                        }
+                        Err(e) => {
+                            error!("Failed to seal {}: {:#}", file.path.display(), e);
+                            Err(format!("{:#}", e))
+                        }
+                    };
+                    watcher.record(&file, outcome)?;
+                }
+                if once && !watcher.is_settling() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_secs(interval));
+            }
+        }
+
+        Commands::Ipfs { profiles, command } => {
+            cmd_ipfs(&profiles.unwrap_or_else(default_profiles_path), command)?;
         }
     }
 
-    Ok(())
+    Ok(ExitStatus::Success)
 }
 
+const DEFAULT_EDGE_WIDTH: u32 = 20;
+const DEFAULT_FRAME_INTERVAL: u64 = 5;
+
+/// A fresh `<root>/<stem>-<uuid>` directory for sealing `input`.
+fn sealed_output_dir(root: &Path, input: &Path) -> PathBuf {
+    let file_stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "sealed".to_string());
+    root.join(format!("{}-{}", file_stem, Uuid::new_v4()))
+}
+
+#[allow(clippy::too_many_arguments)]
 fn cmd_seal(
     input: &Path,
     output: Option<&Path>,
     edge_width: u32,
-    key_path: Option<&Path>,
-    ipfs: bool,
-    ipfs_url: &str,
-    ipfs_key: Option<String>,
+    keypair: Option<&SealedKeyPair>,
+    ipfs_config: Option<IpfsConfig>,
+    ipfs_profile: Option<&str>,
+    ipfs_dir: bool,
     frame_interval: u64,
     sample_frames: Option<usize>,
     timestamp: bool,
-) -> Result<()> {
+    tsa_url: Option<&str>,
+    prior: &PriorSealOptions,
+) -> Result<Option<SealSummary>> {
     let config = SealConfig {
         edge_width,
         ..SealConfig::default()
@@ -118,7 +426,7 @@ fn cmd_seal(
 
     let final_dir = match output {
         Some(p) => p.to_path_buf(),
-        None => PathBuf::from(format!("sealed/{}-{}", file_stem, Uuid::new_v4())),
+        None => sealed_output_dir(Path::new("sealed"), input),
     };
 
     let temp_dir = final_dir.with_extension("tmp");
@@ -135,30 +443,72 @@ fn cmd_seal(
         .map(|e| e.to_string_lossy().to_lowercase())
         .unwrap_or_default();
 
+    let signer = keypair.map(|kp| kp.public_key_base64());
+    let known_seals = prior.known_seals()?;
+    let mut prior_seals = Vec::new();
+    let mut check_prior = |hashes: &HashRecord, source: &Path| -> Result<()> {
+        let Some(known) = &known_seals else { return Ok(()) };
+        let priors = known.prior_seals(hashes, signer.as_deref());
+        for p in &priors {
+            let by = match (&p.signer, p.same_signer) {
+                (_, true) => "this key".to_string(),
+                (Some(key), false) => format!("another key {}", key),
+                (None, false) => "no key".to_string(),
+            };
+            warn!("{} looks already sealed ({} match): {} (signed by {})",
+                source.display(), p.confidence, p.sealed_dir.display(), by);
+        }
+        if let Err(e) = enforce_prior_seal_policy(prior.policy, &priors) {
+            let _ = std::fs::remove_dir_all(&temp_dir);
+            return Err(e.into());
+        }
+        prior_seals.extend(priors);
+        Ok(())
+    };
+
+    let mut batch: Option<BatchManifest> = None;
+
     let artifacts = if input.is_dir() {
         info!("Processing directory: {}", input.display());
-        let mut count = 0;
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(input)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| is_image_file(p))
+            .collect();
+        paths.sort();
+
+        let mut manifest = BatchManifest::new();
         let mut last_artifacts = None;
-        for entry in std::fs::read_dir(input)? {
-            let entry = entry?;
-            let path = entry.path();
-            if is_image_file(&path) {
-                let img = open_image_by_content(&path)?;
-                let sub_dir = output_dir.join(format!("{}", count));
-                let arts = seal_image(&img, &config)?;
-                save_artifacts(&arts, &sub_dir)?;
-                write_hash_record(&arts, &sub_dir, key_path)?;
-                info!("Sealed: {} -> {}", path.display(), sub_dir.display());
-                last_artifacts = Some(arts);
-                count += 1;
-            }
-        }
-        info!("Sealed {} images from directory", count);
+        for (count, path) in paths.iter().enumerate() {
+            let img = open_image_by_content(path)?;
+            let sub_name = count.to_string();
+            let sub_dir = output_dir.join(&sub_name);
+            let arts = seal_image(&img, &config)?;
+            check_prior(&arts.original_hashes, path)?;
+            save_artifacts(&arts, &sub_dir)?;
+            let record = write_hash_record(&arts, &sub_dir, keypair)?;
+            info!("Sealed: {} -> {}", path.display(), sub_dir.display());
+            let source = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            manifest.items.push(BatchItem::new(
+                &sub_name,
+                &source,
+                &arts.original_hashes.sha256,
+                &record,
+            )?);
+            last_artifacts = Some(arts);
+        }
+        info!("Sealed {} images from directory", manifest.items.len());
         match last_artifacts {
-            Some(arts) => arts,
+            Some(arts) => {
+                batch = Some(manifest);
+                arts
+            }
             None => {
-                println!("No images found in directory.");
-                return Ok(());
+                info!("No images found in {}", input.display());
+                return Ok(None);
             }
         }
     } else if matches!(ext.as_str(), "mp4" | "avi" | "mov" | "mkv" | "webm") {
@@ -176,48 +526,82 @@ fn cmd_seal(
         arts
     };
 
-    if !input.is_dir() {
-        write_hash_record(&artifacts, &output_dir, key_path)?;
-    }
+    // The record that gets signed, pinned and timestamped: hashes.json for a single
+    // item, batch.json (anchored by its Merkle root) for a directory.
+    let (record_file, signed_file, anchor_hash) = match batch.as_mut() {
+        Some(manifest) => {
+            let root = manifest.compute_root()?;
+            manifest.save(&output_dir)?;
+            info!("Batch manifest: {} items, Merkle root {}", manifest.items.len(), root);
+            if let Some(kp) = keypair {
+                let envelope = kp.sign_canonical(&*manifest)?;
+                let signed_json = serde_json::to_string_pretty(&envelope)?;
+                std::fs::write(output_dir.join(SIGNED_BATCH_FILE), &signed_json)?;
+                info!("Signed batch manifest: {}", SIGNED_BATCH_FILE);
+            }
+            (BATCH_MANIFEST_FILE, SIGNED_BATCH_FILE, root)
+        }
+        None => {
+            check_prior(&artifacts.original_hashes, input)?;
+            write_hash_record(&artifacts, &output_dir, keypair)?;
+            ("hashes.json", "signed_record.json", artifacts.original_hashes.sha256.clone())
+        }
+    };
 
     let archive_path = create_archive(&output_dir, &file_stem)?;
     info!("Archive: {}", archive_path.display());
 
-    let ipfs_key_for_ots = ipfs_key.clone();
-    if ipfs {
-        let ipfs_key_resolved = ipfs_key.or_else(|| std::env::var("SEALED_IPFS_KEY").ok());
-        let ipfs_config = if let Some(ref api_key) = ipfs_key_resolved {
-            IpfsConfig::pinata(api_key)
-        } else {
-            IpfsConfig {
-                api_url: ipfs_url.to_string(),
-                ..IpfsConfig::default()
-            }
-        };
+    let mut summary = SealSummary {
+        output: final_dir.clone(),
+        sha256: None,
+        blake3: None,
+        items: None,
+        merkle_root: None,
+        signer: signer.clone(),
+        ipfs_cid: None,
+        ipfs_signed_cid: None,
+        ots_pending: false,
+        tsa_time: None,
+        prior_seals,
+    };
+    match batch {
+        Some(ref manifest) => {
+            summary.items = Some(manifest.items.len());
+            summary.merkle_root = Some(manifest.merkle_root.clone());
+        }
+        None => {
+            summary.sha256 = Some(artifacts.original_hashes.sha256.clone());
+            summary.blake3 = Some(artifacts.original_hashes.blake3.clone());
+        }
+    }
 
-        let hashes_path = output_dir.join("hashes.json");
-        match pin_to_ipfs(&hashes_path, &ipfs_config) {
+    // Directory pins wait until the timestamps exist so they are part of the tree.
+    if let Some(ipfs_config) = ipfs_config.as_ref().filter(|_| !ipfs_dir) {
+        let record_path = output_dir.join(record_file);
+        match pin_to_ipfs(&record_path, ipfs_config) {
             Ok(record) => {
-                info!("IPFS CID (hashes): {}", record.cid);
+                info!("IPFS CID ({}): {}", record_file, record.cid);
                 info!("IPFS Gateway: {}", record.gateway_url);
                 let ipfs_json = serde_json::to_string_pretty(&record)?;
                 let ipfs_path = output_dir.join("ipfs_record.json");
                 std::fs::write(&ipfs_path, ipfs_json)?;
+                summary.ipfs_cid = Some(record.cid);
             }
             Err(e) => {
                 error!("IPFS pinning failed: {}. Sealed record saved locally.", e);
             }
         }
 
-        let signed_path = output_dir.join("signed_record.json");
+        let signed_path = output_dir.join(signed_file);
         if signed_path.exists() {
-            match pin_to_ipfs(&signed_path, &ipfs_config) {
+            match pin_to_ipfs(&signed_path, ipfs_config) {
                 Ok(record) => {
                     info!("IPFS CID (signed): {}", record.cid);
                     info!("IPFS Gateway (signed): {}", record.gateway_url);
                     let ipfs_json = serde_json::to_string_pretty(&record)?;
                     let ipfs_path = output_dir.join("ipfs_signed_record.json");
                     std::fs::write(&ipfs_path, ipfs_json)?;
+                    summary.ipfs_signed_cid = Some(record.cid);
                 }
                 Err(e) => {
                     error!("IPFS pinning of signed record failed: {}", e);
@@ -226,14 +610,14 @@ fn cmd_seal(
         }
     }
 
-    let mut ots_submitted = false;
+    let mut ots_record: Option<TimestampRecord> = None;
     if timestamp {
         info!("Submitting hash to OpenTimestamps...");
-        match timestamp_hash(&artifacts.original_hashes.sha256, &output_dir) {
+        match timestamp_hash(&anchor_hash, &output_dir) {
             Ok(record) => {
                 info!("OpenTimestamps proof saved: {}", record.ots_file);
-                println!("OpenTimestamps: proof submitted (pending Bitcoin confirmation)");
-                ots_submitted = true;
+                info!("OpenTimestamps: proof submitted (pending Bitcoin confirmation)");
+                ots_record = Some(record);
             }
             Err(e) => {
                 error!("OpenTimestamps failed: {}. Sealed record saved locally.", e);
@@ -241,6 +625,35 @@ fn cmd_seal(
         }
     }
 
+    if let Some(url) = tsa_url {
+        match timestamp_hash_tsa(&anchor_hash, &output_dir, &TsaConfig::new(url)) {
+            Ok(record) => {
+                if let Some(tsa) = record.tsa {
+                    info!("RFC 3161: token granted at {} (serial {})", tsa.gen_time, tsa.serial_number);
+                    summary.tsa_time = Some(tsa.gen_time);
+                }
+            }
+            Err(e) => {
+                error!("RFC 3161 timestamp failed: {}. Sealed record saved locally.", e);
+            }
+        }
+    }
+
+    if let Some(ipfs_config) = ipfs_config.as_ref().filter(|_| ipfs_dir) {
+        match pin_directory(&output_dir, ipfs_config) {
+            Ok(record) => {
+                info!("IPFS root CID: {} ({} files)", record.cid, record.files.len());
+                info!("IPFS Gateway: {}", record.gateway_url);
+                let ipfs_json = serde_json::to_string_pretty(&record)?;
+                std::fs::write(output_dir.join("ipfs_record.json"), ipfs_json)?;
+                summary.ipfs_cid = Some(record.cid);
+            }
+            Err(e) => {
+                error!("IPFS directory pinning failed: {}. Sealed record saved locally.", e);
+            }
+        }
+    }
+
     if final_dir.exists() {
         std::fs::remove_dir_all(&final_dir)
             .context("Failed to remove existing output directory")?;
@@ -248,29 +661,142 @@ fn cmd_seal(
     std::fs::rename(&temp_dir, &final_dir)
         .context("Failed to move sealed output to final directory")?;
 
-    if ots_submitted {
-        let ipfs_url_arg: Option<&str> = if ipfs { Some(ipfs_url) } else { None };
-        let ipfs_key_arg = if ipfs { ipfs_key_for_ots.as_deref() } else { None };
-        spawn_upgrade_listener(&artifacts.original_hashes.sha256, &final_dir, ipfs_url_arg, ipfs_key_arg);
+    if prior.register {
+        if let Err(e) = register_seal(&prior.registry, &final_dir) {
+            error!("Failed to add seal to registry {}: {}", prior.registry.display(), e);
+        }
     }
 
+    if let Some(ref record) = ots_record {
+        let repin = ipfs_config.as_ref().filter(|_| ipfs_profile.is_none());
+        if repin.is_some_and(|c| c.provider != IpfsProvider::Kubo) {
+            warn!("The confirmed OTS proof will not be re-pinned automatically; use --ipfs-profile for that");
+        }
+        let queue_path = default_queue_path();
+        let mut entry = QueueEntry::new(&anchor_hash, &final_dir, record.calendars.clone(), repin);
+        entry.ipfs_profile = ipfs_profile.filter(|_| ipfs_config.is_some()).map(|p| p.to_string());
+        match enqueue(&queue_path, entry) {
+            Ok(()) => spawn_queue_worker(&queue_path),
+            Err(e) => error!("Failed to queue OTS upgrade in {}: {}", queue_path.display(), e),
+        }
+        summary.ots_pending = true;
+    }
+
+    Ok(Some(summary))
+}
+
+/// What `cmd_seal` produced, for the text report or `--format json`.
+#[derive(Debug, Serialize)]
+struct SealSummary {
+    output: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blake3: Option<String>,
+    /// Item count and Merkle root of a directory seal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    items: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merkle_root: Option<String>,
+    signer: Option<String>,
+    ipfs_cid: Option<String>,
+    ipfs_signed_cid: Option<String>,
+    /// An OpenTimestamps proof was submitted and queued for upgrade.
+    ots_pending: bool,
+    tsa_time: Option<String>,
+    prior_seals: Vec<PriorSeal>,
+}
+
+fn print_seal_summary(summary: Option<&SealSummary>, format: OutputFormat) -> Result<()> {
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(());
+    }
+    let Some(summary) = summary else {
+        println!("No images found in directory.");
+        return Ok(());
+    };
+    if summary.ots_pending {
+        println!("OpenTimestamps: proof submitted (pending Bitcoin confirmation)");
+    }
+    if let Some(time) = &summary.tsa_time {
+        println!("RFC 3161: token granted at {}", time);
+    }
     println!("\n=== SEALED SUCCESSFULLY ===");
-    println!("Output: {}", final_dir.display());
-    println!("SHA-256: {}", artifacts.original_hashes.sha256);
-    println!("BLAKE3:  {}", artifacts.original_hashes.blake3);
+    println!("Output: {}", summary.output.display());
+    if let (Some(items), Some(root)) = (summary.items, &summary.merkle_root) {
+        println!("Items:       {}", items);
+        println!("Merkle root: {}", root);
+    }
+    if let (Some(sha256), Some(blake3)) = (&summary.sha256, &summary.blake3) {
+        println!("SHA-256: {}", sha256);
+        println!("BLAKE3:  {}", blake3);
+    }
+    if let Some(cid) = &summary.ipfs_cid {
+        println!("IPFS CID: {}", cid);
+    }
+    if summary.ots_pending {
+        println!("OTS: queued for upgrade; check progress with `sealed-ch status`.");
+    }
+    Ok(())
+}
 
-    if ots_submitted {
-        println!("OTS: background process polling for Bitcoin confirmation.");
+/// How `cmd_seal` looks for and records earlier seals.
+struct PriorSealOptions {
+    policy: PriorSealPolicy,
+    registry: PathBuf,
+    prior_dirs: Vec<PathBuf>,
+    register: bool,
+}
+
+impl PriorSealOptions {
+    /// The registry plus any `--prior-seals` folders, or `None` when checking is off.
+    fn known_seals(&self) -> Result<Option<Registry>> {
+        if self.policy == PriorSealPolicy::Off {
+            return Ok(None);
+        }
+        let mut known = Registry::load(&self.registry)
+            .with_context(|| format!("Failed to read {}", self.registry.display()))?;
+        for dir in &self.prior_dirs {
+            for entry in collect_entries(dir)? {
+                known.add(entry);
+            }
+        }
+        Ok(Some(known))
     }
+}
 
+fn register_seal(registry_path: &Path, sealed_dir: &Path) -> Result<()> {
+    let mut registry = Registry::load(registry_path)?;
+    for entry in collect_entries(sealed_dir)? {
+        registry.add(entry);
+    }
+    registry.save(registry_path)?;
     Ok(())
 }
 
+/// Load the signing key, prompting for a password if the file is encrypted.
+fn load_signing_key(key_path: Option<&Path>) -> Result<Option<SealedKeyPair>> {
+    load_signing_key_from(key_path, &PassphraseSource::Prompt)
+}
+
+/// Load the signing key, reading the password of an encrypted file from `passphrase`.
+fn load_signing_key_from(key_path: Option<&Path>, passphrase: &PassphraseSource) -> Result<Option<SealedKeyPair>> {
+    let Some(key_file) = key_path else {
+        return Ok(None);
+    };
+    let keypair = SealedKeyPair::load_with_passphrase(key_file, passphrase)
+        .with_context(|| format!("Failed to load signing key {}", key_file.display()))?;
+    Ok(Some(keypair))
+}
+
+/// Write hashes.json, hashes.txt, tile_index.json and (if keyed) signed_record.json.
+/// Returns the hashes.json content.
 fn write_hash_record(
     artifacts: &sealed::image_processing::SealedArtifacts,
     output_dir: &Path,
-    key_path: Option<&Path>,
-) -> Result<()> {
+    keypair: Option<&SealedKeyPair>,
+) -> Result<SealedRecord> {
     info!("Generating tile hash index for crop detection...");
     let tile_index = generate_tile_index(&artifacts.original);
 
@@ -316,21 +842,8 @@ fn write_hash_record(
     writeln!(f, "Recombined SHA-256: {}", sealed_record.recombined.sha256)?;
     writeln!(f, "Recombined BLAKE3:  {}", sealed_record.recombined.blake3)?;
 
-    if let Some(key_file) = key_path {
-            let is_encrypted = {
-            let data = std::fs::read(key_file).unwrap_or_default();
-            data.starts_with(sealed::signing::ENCRYPTED_KEY_MAGIC)
-        };
-        let keypair = if is_encrypted {
-            let password = rpassword::prompt_password("Enter key password: ")
-                .context("Failed to read password")?;
-            SealedKeyPair::load_encrypted(key_file, &password)
-                .context("Failed to decrypt signing key")?  
-        } else {
-            SealedKeyPair::load(key_file)
-                .context("Failed to load signing key")?
-        };
-        let envelope = keypair.sign(&json);
+    if let Some(keypair) = keypair {
+        let envelope = keypair.sign_canonical(&sealed_record)?;
         let signed_json = serde_json::to_string_pretty(&envelope)?;
         let signed_path = output_dir.join("signed_record.json");
         std::fs::write(&signed_path, &signed_json)?;
@@ -340,55 +853,476 @@ fn write_hash_record(
         writeln!(f, "Public key: {}", envelope.public_key)?;
     }
 
-    Ok(())
+    Ok(sealed_record)
 }
 
-fn cmd_verify(suspect: &Path, sealed_dir: &Path, public_key: Option<&Path>) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+fn cmd_verify(
+    suspect: &Path,
+    sealed_dir: &Path,
+    public_key: Option<&Path>,
+    trust: &TrustStore,
+    anchors: &TimeAnchors,
+    policy: Option<&SignerPolicy>,
+    report: Option<&Path>,
+    retrieved: Option<&RetrievedRecord>,
+    format: OutputFormat,
+) -> Result<ExitStatus> {
     info!("Verifying {} against {}", suspect.display(), sealed_dir.display());
 
-    let result = verify_image(suspect, sealed_dir, public_key)?;
+    let mut result = verify_image_with(suspect, sealed_dir, public_key, retrieved.is_none())?;
+    result.proven_time = proven_sealing_time(sealed_dir, &result.sealed_record.original.sha256, anchors);
+    result.apply_trust(trust);
+    if let Some(policy) = policy {
+        result.apply_policy(policy);
+    }
+    let cid_check = check_recorded_cid(sealed_dir)?;
+    let status = verify_exit_status(&result, cid_check.as_ref(), public_key.is_some());
 
-    println!("\n=== VERIFICATION RESULT ===");
-    println!("Verdict: {}", result.verdict);
-    println!();
-    println!("Signature valid: {}", result.signature_valid);
-    println!();
-    println!("vs Original:");
-    println!("  Confidence:    {}", result.vs_original.confidence);
-    println!("  Exact match:   {}", result.vs_original.exact_match);
-    println!("  SHA-256 match: {}", result.vs_original.sha256_match);
-    println!("  BLAKE3 match:  {}", result.vs_original.blake3_match);
-    println!("  aHash distance: {}", result.vs_original.ahash_hamming);
-    println!("  dHash distance: {}", result.vs_original.dhash_hamming);
-    println!("  pHash distance: {}", result.vs_original.phash_hamming);
-    println!();
-    println!("vs Cropped/Share:");
-    println!("  Confidence:    {}", result.vs_cropped.confidence);
-    println!("  Exact match:   {}", result.vs_cropped.exact_match);
-    println!("  SHA-256 match: {}", result.vs_cropped.sha256_match);
-    println!("  aHash distance: {}", result.vs_cropped.ahash_hamming);
-    println!("  dHash distance: {}", result.vs_cropped.dhash_hamming);
-    println!("  pHash distance: {}", result.vs_cropped.phash_hamming);
-    println!();
-    println!("Suspect image hashes:");
-    println!("  SHA-256: {}", result.suspect_hashes.sha256);
-    println!("  BLAKE3:  {}", result.suspect_hashes.blake3);
-
-    let result_json = serde_json::to_string_pretty(&result)?;
-    let result_path = suspect
-        .parent()
-        .unwrap_or_else(|| Path::new("."))
-        .join(format!(
-            "{}_verification.json",
-            suspect.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| "suspect".to_string())
-        ));
-    std::fs::write(&result_path, &result_json)?;
-    info!("Verification result saved to {}", result_path.display());
+    let output = VerifyOutput {
+        result: &result,
+        ipfs: cid_check.as_ref(),
+        retrieved,
+        outcome: outcome_name(status),
+        exit_code: status.code(),
+    };
+    let result_json = serde_json::to_string_pretty(&output)?;
+
+    match format {
+        OutputFormat::Json => println!("{}", result_json),
+        OutputFormat::Text => {
+            println!("\n=== VERIFICATION RESULT ===");
+            println!("Verdict: {}", result.summary());
+            println!();
+            println!("Signature: {}", result.signature);
+            if let Some(ref signer) = result.signer {
+                println!("Signer:    {}", describe_signer(signer));
+                println!("Key ID:    {}", signer.key_id);
+            }
+            for cosigner in &result.cosigners {
+                println!("Co-signer: {}  key {}", describe_signer(cosigner), cosigner.key_id);
+            }
+            for key_id in &result.invalid_cosignatures {
+                println!("Co-signer: INVALID signature  key {}", key_id);
+            }
+            if let Some(ref policy) = result.policy {
+                println!("Policy:    {}", policy);
+            }
+            match result.proven_time {
+                Some(ref proven) => println!("Sealed by: {} ({})", proven.time, proven.source),
+                None => println!("Sealed at: {} (claimed by the signer; no verified timestamp)", result.sealed_record.sealed_at),
+            }
+            println!();
+            println!("vs Original:");
+            println!("  Confidence:    {}", result.vs_original.confidence);
+            println!("  Exact match:   {}", result.vs_original.exact_match);
+            println!("  SHA-256 match: {}", result.vs_original.sha256_match);
+            println!("  BLAKE3 match:  {}", result.vs_original.blake3_match);
+            println!("  aHash distance: {}", result.vs_original.ahash_hamming);
+            println!("  dHash distance: {}", result.vs_original.dhash_hamming);
+            println!("  pHash distance: {}", result.vs_original.phash_hamming);
+            println!();
+            println!("vs Cropped/Share:");
+            println!("  Confidence:    {}", result.vs_cropped.confidence);
+            println!("  Exact match:   {}", result.vs_cropped.exact_match);
+            println!("  SHA-256 match: {}", result.vs_cropped.sha256_match);
+            println!("  aHash distance: {}", result.vs_cropped.ahash_hamming);
+            println!("  dHash distance: {}", result.vs_cropped.dhash_hamming);
+            println!("  pHash distance: {}", result.vs_cropped.phash_hamming);
+            println!();
+            println!("Suspect image hashes:");
+            println!("  SHA-256: {}", result.suspect_hashes.sha256);
+            println!("  BLAKE3:  {}", result.suspect_hashes.blake3);
+
+            if let Some(ref integrity) = result.integrity {
+                println!();
+                print_integrity(integrity);
+            }
+
+            if let Some(ref check) = cid_check {
+                println!();
+                println!("IPFS record:");
+                println!("  {} CID: {}", check.file, check.recorded_cid);
+                println!("  CID match:     {}", check.matches);
+                if !check.matches {
+                    println!("  {} hashes to {}, not the recorded CID", check.file, check.computed_cid);
+                }
+            }
+            println!();
+            println!("Outcome: {} (exit code {})", outcome_name(status), status.code());
+        }
+    }
+
+    if let Some(path) = report {
+        std::fs::write(path, &result_json)
+            .with_context(|| format!("Failed to write verification result to {}", path.display()))?;
+        info!("Verification result saved to {}", path.display());
+    }
+
+    Ok(status)
+}
+
+fn describe_signer(signer: &Signer) -> String {
+    match &signer.trust {
+        KeyTrust::Unknown => format!("unknown key {}", signer.public_key),
+        KeyTrust::Trusted { name } => format!("{} (trusted)", name),
+        KeyTrust::Revoked { name, reason } => format!(
+            "{} (REVOKED{})",
+            name,
+            reason.as_deref().map(|r| format!(": {}", r)).unwrap_or_default()
+        ),
+        KeyTrust::OutsideValidity { name } => format!("{} (outside validity period)", name),
+    }
+}
+
+/// `verify --format json` output: the full result plus how it was classified.
+#[derive(Serialize)]
+struct VerifyOutput<'a> {
+    #[serde(flatten)]
+    result: &'a VerificationResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ipfs: Option<&'a CidCheck>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retrieved: Option<&'a RetrievedRecord>,
+    outcome: &'static str,
+    exit_code: u8,
+}
+
+/// A failed signature, CID or artifact check outranks any match, as do a
+/// signer the trust store has revoked, a bad co-signature and an unmet signer
+/// policy. With a trusted key given, the record must be signed by it.
+fn verify_exit_status(result: &VerificationResult, cid_check: Option<&CidCheck>, key_given: bool) -> ExitStatus {
+    let signature_failed = match result.signature {
+        SignatureStatus::Invalid => true,
+        SignatureStatus::Trusted => false,
+        SignatureStatus::Valid | SignatureStatus::Unsigned => key_given,
+    };
+    let tampered = result.integrity.as_ref().is_some_and(|i| !i.is_intact());
+    if cid_check.is_some_and(|c| !c.matches) || signature_failed || tampered || result.signer_distrusted() || result.cosigning_failed() {
+        return ExitStatus::SignatureFailure;
+    }
+    match result.verdict {
+        Verdict::ExactOriginal { .. } | Verdict::ExactShare { .. } => ExitStatus::Success,
+        Verdict::CropDetected { .. } => ExitStatus::CropMatch,
+        Verdict::Perceptual { .. } => ExitStatus::PerceptualMatch,
+        Verdict::NoMatch { .. } => ExitStatus::NoMatch,
+    }
+}
+
+fn outcome_name(status: ExitStatus) -> &'static str {
+    match status {
+        ExitStatus::Success => "exact",
+        ExitStatus::PerceptualMatch => "perceptual",
+        ExitStatus::CropMatch => "crop",
+        ExitStatus::NoMatch => "no-match",
+        ExitStatus::SignatureFailure => "signature-failure",
+        ExitStatus::Error | ExitStatus::Usage => "error",
+    }
+}
+
+fn print_integrity(report: &IntegrityReport) {
+    println!("Sealed artifacts (checked against {}):", report.checked_against);
+    let problems = report.problems();
+    if problems.is_empty() {
+        println!("  All intact; frame + cropped recombine to the original");
+    }
+    for problem in problems {
+        println!("  {}", problem);
+    }
+}
+
+fn cmd_check(sealed_dir: &Path, format: OutputFormat) -> Result<ExitStatus> {
+    // A directory seal keeps each item's artifacts in its own subdirectory.
+    let dirs: Vec<PathBuf> = if sealed_dir.join(BATCH_MANIFEST_FILE).exists() {
+        BatchManifest::load(sealed_dir)?.items.iter().map(|item| sealed_dir.join(&item.dir)).collect()
+    } else {
+        vec![sealed_dir.to_path_buf()]
+    };
+
+    let mut reports = Vec::new();
+    for dir in dirs {
+        let report = check_artifacts(&dir).with_context(|| format!("Failed to check {}", dir.display()))?;
+        reports.push((dir, report));
+    }
+    let intact = reports.iter().all(|(_, r)| r.is_intact());
+
+    match format {
+        OutputFormat::Json => {
+            let items: Vec<_> = reports
+                .iter()
+                .map(|(dir, report)| serde_json::json!({ "sealed_dir": dir, "intact": report.is_intact(), "report": report }))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "intact": intact, "items": items }))?);
+        }
+        OutputFormat::Text => {
+            for (dir, report) in &reports {
+                println!("\n=== {} ===", dir.display());
+                print_integrity(report);
+            }
+            println!("\nStatus: {}", if intact { "INTACT" } else { "TAMPERED" });
+        }
+    }
+
+    Ok(if intact { ExitStatus::Success } else { ExitStatus::SignatureFailure })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cmd_verify_cid(
+    suspect: &Path,
+    cid: &str,
+    signed_cid: Option<&str>,
+    source: &FetchSource,
+    public_key: Option<&Path>,
+    trust: &TrustStore,
+    anchors: &TimeAnchors,
+    policy: Option<&SignerPolicy>,
+    report: Option<&Path>,
+    format: OutputFormat,
+) -> Result<ExitStatus> {
+    let fetch_dir = std::env::temp_dir().join(format!("sealed_cid_{}", Uuid::new_v4()));
+    let retrieved = retrieve_sealed_record(cid, signed_cid, source, &fetch_dir);
+    let outcome = retrieved.map_err(anyhow::Error::from).and_then(|retrieved| {
+        if format == OutputFormat::Text {
+            println!("\n=== RETRIEVED FROM IPFS ===");
+            println!("CID: {}{}", retrieved.cid, if retrieved.directory { " (directory)" } else { "" });
+            for file in &retrieved.files {
+                println!("  {}  {}", file.cid, file.path);
+            }
+            println!("All blocks match their CIDs.");
+        }
+        cmd_verify(suspect, &fetch_dir, public_key, trust, anchors, policy, report, Some(&retrieved), format)
+    });
+    let _ = std::fs::remove_dir_all(&fetch_dir);
+    outcome
+}
+
+fn cmd_verify_proof(
+    proof: &Path,
+    hash: Option<&str>,
+    block_header: Option<&Path>,
+    tsa_anchor: Option<&Path>,
+) -> Result<()> {
+    let (ots_path, tsr_path) = if proof.is_dir() {
+        let ots = proof.join("timestamp.ots");
+        let tsr = proof.join(TSR_FILE);
+        (ots.exists().then_some(ots), tsr.exists().then_some(tsr))
+    } else if proof.extension().is_some_and(|e| e == "tsr") {
+        (None, Some(proof.to_path_buf()))
+    } else {
+        (Some(proof.to_path_buf()), None)
+    };
+    if ots_path.is_none() && tsr_path.is_none() {
+        anyhow::bail!("No timestamp.ots or {} found in {}", TSR_FILE, proof.display());
+    }
+
+    let expected = match hash {
+        Some(h) => h.to_string(),
+        None => {
+            let record_path = if proof.is_dir() {
+                proof.join("timestamp_record.json")
+            } else {
+                proof.parent().unwrap_or_else(|| Path::new(".")).join("timestamp_record.json")
+            };
+            let json = std::fs::read_to_string(&record_path).with_context(|| {
+                format!("No --hash given and {} not readable", record_path.display())
+            })?;
+            serde_json::from_str::<TimestampRecord>(&json)?.hash
+        }
+    };
+
+    if let Some(ots_path) = ots_path {
+        verify_ots_file(&ots_path, &expected, block_header)?;
+    }
+    if let Some(tsr_path) = tsr_path {
+        let anchor = tsa_anchor.context("Checking a .tsr token requires --tsa-anchor <CERT>")?;
+        verify_tsr_file(&tsr_path, &expected, anchor)?;
+    }
+
+    Ok(())
+}
+
+fn verify_ots_file(ots_path: &Path, expected: &str, block_header: Option<&Path>) -> Result<()> {
+    let proof_file = DetachedTimestampFile::load(ots_path)
+        .with_context(|| format!("Failed to read OTS proof {}", ots_path.display()))?;
+
+    let header = block_header.map(BlockHeader::load).transpose()?;
+    let result = verify_proof(&proof_file, expected, header.as_ref())?;
+
+    println!("\n=== OPENTIMESTAMPS PROOF ===");
+    println!("Proof:   {}", ots_path.display());
+    println!("Digest:  {}", result.digest);
+    println!("Matches sealed SHA-256: {}", result.digest_matches);
+    for uri in &result.pending_calendars {
+        println!("Pending: {}", uri);
+    }
+    for check in &result.bitcoin {
+        println!("Bitcoin block {}: commitment {}", check.height, check.commitment);
+        match (check.header_matches, &check.block_hash, &check.block_time) {
+            (Some(true), Some(block_hash), Some(time)) => {
+                println!("  Header MATCHES (block {}, time {})", block_hash, time);
+            }
+            (Some(_), _, _) => println!("  Header does NOT match this attestation"),
+            (None, _, _) => println!("  No block header supplied; pass --block-header to confirm"),
+        }
+    }
+
+    if !result.digest_matches {
+        return Err(SealedError::VerificationFailed(format!(
+            "Proof digest {} does not match sealed SHA-256 {}",
+            result.digest, expected
+        )).into());
+    }
+
+    if result.is_verified() {
+        println!("\nStatus: VERIFIED (anchored in Bitcoin)");
+    } else if header.is_some() && !result.bitcoin.is_empty() {
+        return Err(SealedError::VerificationFailed(
+            "Supplied block header does not match any Bitcoin attestation".to_string(),
+        ).into());
+    } else if !result.bitcoin.is_empty() {
+        println!("\nStatus: ATTESTED (header not checked)");
+    } else {
+        println!("\nStatus: PENDING (awaiting Bitcoin confirmation)");
+    }
+
+    Ok(())
+}
+
+fn verify_tsr_file(tsr_path: &Path, expected: &str, anchor_path: &Path) -> Result<()> {
+    let tsr = std::fs::read(tsr_path)
+        .with_context(|| format!("Failed to read RFC 3161 token {}", tsr_path.display()))?;
+    let anchor = load_trust_anchor(anchor_path)
+        .with_context(|| format!("Failed to load trust anchor {}", anchor_path.display()))?;
+    let result = verify_token(&tsr, expected, &anchor)?;
 
+    println!("\n=== RFC 3161 TIMESTAMP TOKEN ===");
+    println!("Token:    {}", tsr_path.display());
+    println!("Imprint:  {}", result.imprint);
+    println!("Matches sealed SHA-256: {}", result.imprint_matches);
+    println!("Time:     {}", result.gen_time);
+    println!("Serial:   {}", result.serial_number);
+    println!("Policy:   {}", result.policy);
+    println!("Signer:   {}", result.signer);
+    println!("Signature valid:        {}", result.signature_valid);
+    println!("Signer cert valid:      {}", result.signer_valid);
+    println!("Chains to trust anchor: {}", result.chain_trusted);
+
+    if !result.is_verified() {
+        return Err(SealedError::VerificationFailed(format!(
+            "RFC 3161 token {} failed verification",
+            tsr_path.display()
+        )).into());
+    }
+    println!("\nStatus: VERIFIED (RFC 3161)");
+
+    Ok(())
+}
+
+fn cmd_ots_upgrade(
+    queue: &Path,
+    all: bool,
+    once: bool,
+    output_dir: Option<&Path>,
+    hash: Option<&str>,
+    ipfs_url: Option<&str>,
+    ipfs_profile: Option<&str>,
+) -> Result<()> {
+    if let Some(dir) = output_dir {
+        let record_path = dir.join("timestamp_record.json");
+        let record: Option<TimestampRecord> = std::fs::read_to_string(&record_path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok());
+        let hash = match (hash, &record) {
+            (Some(h), _) => h.to_string(),
+            (None, Some(r)) => r.hash.clone(),
+            (None, None) => anyhow::bail!(
+                "No --hash given and {} not readable", record_path.display()
+            ),
+        };
+        let calendars = record.map(|r| r.calendars).unwrap_or_default();
+        let repin = ipfs_url.map(IpfsConfig::kubo);
+        let mut entry = QueueEntry::new(&hash, dir, calendars, repin.as_ref());
+        entry.ipfs_profile = ipfs_profile.map(|p| p.to_string());
+        enqueue(queue, entry)?;
+        println!("Queued {}", dir.display());
+    } else if !all && !once {
+        anyhow::bail!("Pass --all to resume the queue, --once for a single pass, or --output-dir to add one");
+    }
+
+    let config = CalendarConfig::default();
+    if once {
+        let Some(lock) = QueueLock::acquire(queue)? else {
+            println!("Another OTS upgrade worker is draining {}", queue.display());
+            return Ok(());
+        };
+        let summary = process_queue(queue, &config, Some(&lock), true)?;
+        for dir in &summary.confirmed {
+            println!("Confirmed: {}", dir.display());
+        }
+        for dir in &summary.dropped {
+            println!("Dropped (proof missing): {}", dir.display());
+        }
+        println!("{} still pending", summary.still_pending);
+    } else {
+        run_queue_worker(queue, &config, true)?;
+        println!("OTS upgrade queue drained.");
+    }
+
+    Ok(())
+}
+
+fn cmd_status(queue_path: &Path) -> Result<()> {
+    let queue = UpgradeQueue::load(queue_path)
+        .with_context(|| format!("Failed to read {}", queue_path.display()))?;
+
+    println!("OTS upgrade queue: {}", queue_path.display());
+    if queue.entries.is_empty() {
+        println!("Nothing pending.");
+        return Ok(());
+    }
+    for entry in &queue.entries {
+        println!("\n{}", entry.dir.display());
+        println!("  Hash:       {}", entry.hash);
+        println!("  Calendars:  {}", entry.calendars.join(", "));
+        println!("  Attempts:   {}", entry.attempts);
+        println!("  Next retry: {}", entry.next_retry.to_rfc3339());
+        if let Some(err) = &entry.last_error {
+            println!("  Last error: {}", err);
+        }
+    }
+    println!("\n{} pending", queue.entries.len());
+
+    Ok(())
+}
+
+/// Ask for a new password twice.
+fn prompt_new_password() -> Result<String> {
+    let password = rpassword::prompt_password("Enter password to encrypt secret key: ")
+        .context("Failed to read password")?;
+    let confirm = rpassword::prompt_password("Confirm password: ")
+        .context("Failed to read password confirmation")?;
+    if password != confirm {
+        anyhow::bail!("Passwords do not match");
+    }
+    Ok(password)
+}
+
+/// Write sealed.key (encrypted if asked) and sealed.pub.
+fn save_keypair(keypair: &SealedKeyPair, secret_path: &Path, public_path: &Path, encrypt: bool) -> Result<()> {
+    if encrypt {
+        let password = prompt_new_password()?;
+        keypair.save_secret_encrypted(secret_path, &password)
+            .context("Failed to save encrypted secret key")?;
+    } else {
+        keypair.save_secret(secret_path)
+            .context("Failed to save secret key")?;
+    }
+    keypair.save_public(public_path)
+        .context("Failed to save public key")?;
     Ok(())
 }
 
-fn cmd_keygen(output_dir: &Path, encrypt: bool) -> Result<()> {
+fn cmd_keygen(output_dir: &Path, encrypt: bool, format: OutputFormat) -> Result<()> {
     std::fs::create_dir_all(output_dir)?;
 
     let keypair = SealedKeyPair::generate();
@@ -396,35 +1330,86 @@ fn cmd_keygen(output_dir: &Path, encrypt: bool) -> Result<()> {
     let secret_path = output_dir.join("sealed.key");
     let public_path = output_dir.join("sealed.pub");
 
+    save_keypair(&keypair, &secret_path, &public_path, encrypt)?;
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "secret_key": secret_path,
+            "public_key": public_path,
+            "public_key_base64": keypair.public_key_base64(),
+            "key_id": keypair.key_id(),
+            "encrypted": encrypt,
+        }))?);
+        return Ok(());
+    }
     if encrypt {
-        let password = rpassword::prompt_password("Enter password to encrypt secret key: ")
-            .context("Failed to read password")?;
-        let confirm = rpassword::prompt_password("Confirm password: ")
-            .context("Failed to read password confirmation")?;
-        if password != confirm {
-            anyhow::bail!("Passwords do not match");
-        }
-        keypair.save_secret_encrypted(&secret_path, &password)
-            .context("Failed to save encrypted secret key")?;
         println!("=== Ed25519 Keypair Generated (encrypted) ===");
         println!("Secret key: {} (PASSWORD-ENCRYPTED - KEEP THIS SAFE)", secret_path.display());
     } else {
-        keypair.save_secret(&secret_path)
-            .context("Failed to save secret key")?;
         println!("=== Ed25519 Keypair Generated ===");
         println!("Secret key: {} (KEEP THIS SAFE - DO NOT SHARE)", secret_path.display());
     }
-
-    keypair.save_public(&public_path)
-        .context("Failed to save public key")?;
-
     println!("Public key: {} (share freely for verification)", public_path.display());
     println!("Public key (base64): {}", keypair.public_key_base64());
+    println!("Key ID: {}", keypair.key_id());
+
+    Ok(())
+}
+
+/// Pinning settings from `--ipfs-profile`, `--ipfs-key`/`SEALED_IPFS_KEY`
+/// (Pinata) or `--ipfs-url`, then sealed.toml, falling back to the default profile.
+fn ipfs_config_from_flags(
+    profile: Option<&str>,
+    ipfs_url: Option<&str>,
+    ipfs_key: Option<String>,
+    cid_version: CidVersion,
+    settings: &Settings,
+) -> Result<IpfsConfig> {
+    let pinata_key = ipfs_key.or_else(|| std::env::var("SEALED_IPFS_KEY").ok());
+    let flags_given = profile.is_some() || ipfs_url.is_some() || pinata_key.is_some();
+    let config = match &settings.ipfs_service {
+        Some(service) if !flags_given => service.to_config()?,
+        _ => resolve_config(
+            &default_profiles_path(),
+            profile,
+            ipfs_url.or(settings.ipfs_url.as_deref()),
+            pinata_key.as_deref(),
+        )?,
+    };
+    Ok(IpfsConfig { cid_version, ..config })
+}
 
+fn cmd_ipfs_car(sealed_dir: &Path, car_path: &Path, cid_version: CidVersion, format: OutputFormat) -> Result<()> {
+    let record = export_car(sealed_dir, car_path, cid_version)?;
+    std::fs::write(sealed_dir.join("ipfs_record.json"), serde_json::to_string_pretty(&record)?)?;
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "car": car_path, "record": record }))?);
+        return Ok(());
+    }
+    println!("\n=== CAR Export Successful ===");
+    println!("CAR: {}", car_path.display());
+    println!("Root CID: {}", record.cid);
+    println!("Files: {}", record.files.len());
     Ok(())
 }
 
-fn cmd_ipfs_pin(sealed_dir: &Path, ipfs_url: &str, ipfs_key: Option<String>) -> Result<()> {
+fn cmd_ipfs_pin(sealed_dir: &Path, config: &IpfsConfig, whole_dir: bool, format: OutputFormat) -> Result<()> {
+    if whole_dir {
+        let record = pin_directory(sealed_dir, config)?;
+        std::fs::write(sealed_dir.join("ipfs_record.json"), serde_json::to_string_pretty(&record)?)?;
+        if format == OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "record": record }))?);
+            return Ok(());
+        }
+        println!("\n=== IPFS Directory Pin Successful ===");
+        println!("Root CID: {}", record.cid);
+        println!("Gateway: {}", record.gateway_url);
+        for file in &record.files {
+            println!("  {}  {}", file.cid, file.path);
+        }
+        return Ok(());
+    }
+
     let hashes_path = sealed_dir.join("hashes.json");
     if !hashes_path.exists() {
         return Err(SealedError::FileNotFound(
@@ -432,32 +1417,32 @@ fn cmd_ipfs_pin(sealed_dir: &Path, ipfs_url: &str, ipfs_key: Option<String>) ->
         ).into());
     }
 
-    let ipfs_key_resolved = ipfs_key.or_else(|| std::env::var("SEALED_IPFS_KEY").ok());
-    let config = if let Some(ref api_key) = ipfs_key_resolved {
-        IpfsConfig::pinata(api_key)
+    let record = pin_to_ipfs(&hashes_path, config)?;
+    let ipfs_json = serde_json::to_string_pretty(&record)?;
+    let ipfs_path = sealed_dir.join("ipfs_record.json");
+    std::fs::write(&ipfs_path, &ipfs_json)?;
+
+    let signed_path = sealed_dir.join("signed_record.json");
+    let signed_record = if signed_path.exists() {
+        info!("Also pinning signed record...");
+        Some(pin_to_ipfs(&signed_path, config)?)
     } else {
-        IpfsConfig {
-            api_url: ipfs_url.to_string(),
-            ..IpfsConfig::default()
-        }
+        None
     };
 
-    let record = pin_to_ipfs(&hashes_path, &config)?;
-
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "record": record,
+            "signed_record": signed_record,
+        }))?);
+        return Ok(());
+    }
     println!("\n=== IPFS Pin Successful ===");
     println!("CID: {}", record.cid);
     println!("Gateway: {}", record.gateway_url);
     println!("Pinned at: {}", record.pinned_at);
     println!("Service: {}", record.service);
-
-    let ipfs_json = serde_json::to_string_pretty(&record)?;
-    let ipfs_path = sealed_dir.join("ipfs_record.json");
-    std::fs::write(&ipfs_path, &ipfs_json)?;
-
-    let signed_path = sealed_dir.join("signed_record.json");
-    if signed_path.exists() {
-        info!("Also pinning signed record...");
-        let signed_record = pin_to_ipfs(&signed_path, &config)?;
+    if let Some(signed_record) = signed_record {
         println!("Signed record CID: {}", signed_record.cid);
         println!("Signed record Gateway: {}", signed_record.gateway_url);
     }
@@ -465,6 +1450,576 @@ fn cmd_ipfs_pin(sealed_dir: &Path, ipfs_url: &str, ipfs_key: Option<String>) ->
     Ok(())
 }
 
+/// The given trust store, which must exist, or the default one if present.
+fn load_trust_store(path: Option<&Path>) -> Result<TrustStore> {
+    let path = match path {
+        Some(path) if !path.exists() => return Err(SealedError::FileNotFound(path.display().to_string()).into()),
+        Some(path) => path.to_path_buf(),
+        None => default_trust_store_path(),
+    };
+    TrustStore::load(&path).with_context(|| format!("Failed to read trust store {}", path.display()))
+}
+
+fn cmd_trust(store_path: &Path, command: TrustCommands, format: OutputFormat) -> Result<()> {
+    let mut store = TrustStore::load(store_path)
+        .with_context(|| format!("Failed to read {}", store_path.display()))?;
+
+    match command {
+        TrustCommands::Add { name, key, label, not_before, not_after } => {
+            let public_key = read_public_key(&key)?;
+            let mut key = TrustedKey::new(&name, &public_key);
+            key.label = label;
+            key.not_before = not_before.map(|t| normalize_time(&t, false)).transpose()?;
+            key.not_after = not_after.map(|t| normalize_time(&t, true)).transpose()?;
+            store.add(key)?;
+            store.save(store_path)?;
+            println!("Trusted {} (key {}) in {}", name, key_id_from_base64(&public_key)?, store_path.display());
+        }
+
+        TrustCommands::List => {
+            if format == OutputFormat::Json {
+                println!("{}", serde_json::to_string_pretty(&store)?);
+                return Ok(());
+            }
+            if store.keys.is_empty() {
+                println!("No trusted keys in {}", store_path.display());
+            }
+            for key in &store.keys {
+                let validity = match (&key.not_before, &key.not_after) {
+                    (None, None) => String::new(),
+                    (from, until) => format!(
+                        "  valid {} to {}",
+                        from.as_deref().unwrap_or("(any)"),
+                        until.as_deref().unwrap_or("(any)")
+                    ),
+                };
+                let revoked = match (key.revoked, &key.revoked_effective) {
+                    (false, _) => String::new(),
+                    (true, None) => "  REVOKED".to_string(),
+                    (true, Some(from)) => format!("  REVOKED from {}", from),
+                };
+                println!(
+                    "{:<16} {}  {}{}{}{}{}",
+                    key.name,
+                    key.key_id(),
+                    key.public_key,
+                    key.label.as_deref().map(|l| format!("  \"{}\"", l)).unwrap_or_default(),
+                    validity,
+                    revoked,
+                    key.replaced_by.as_deref().map(|id| format!("  replaced by {}", id)).unwrap_or_default()
+                );
+            }
+        }
+
+        TrustCommands::Revoke { target, reason, effective } => {
+            let effective = effective.map(|t| normalize_time(&t, false)).transpose()?;
+            let key = store.revoke(&target, reason.as_deref(), effective.as_deref())?;
+            match &key.revoked_effective {
+                Some(from) => println!("Revoked {} (key {}) from {}", key.name, key.key_id(), from),
+                None => println!("Revoked {} (key {})", key.name, key.key_id()),
+            }
+            store.save(store_path)?;
+        }
+
+        TrustCommands::Import { statement, name } => {
+            let parsed = read_statement(&statement)
+                .with_context(|| format!("Rejected key statement {}", statement.display()))?;
+            let known = match &parsed {
+                KeyStatement::Revocation { public_key, .. } => store.find(public_key).is_some(),
+                KeyStatement::Rotation { .. } => true,
+            };
+            let affected = store.apply_statement(&parsed, name.as_deref())?;
+            store.save(store_path)?;
+            match &parsed {
+                KeyStatement::Rotation { old_key_id, new_key_id, effective_at, .. } => println!(
+                    "Key {} rotated to {} from {}; trusted the new key as {}",
+                    old_key_id, new_key_id, effective_at, affected
+                ),
+                KeyStatement::Revocation { key_id, effective_at, .. } if known => {
+                    println!("Revoked {} (key {}) from {}", affected, key_id, effective_at)
+                }
+                KeyStatement::Revocation { key_id, .. } => {
+                    println!("Key {} was not trusted; recorded it as {}, revoked outright", key_id, affected)
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_key(command: KeyCommands, format: OutputFormat) -> Result<()> {
+    let now = || chrono::Utc::now().to_rfc3339();
+    let (envelope, output) = match command {
+        KeyCommands::Id { key } => {
+            let public_key = read_public_key(&key)?;
+            let key_id = key_id_from_base64(&public_key)?;
+            match format {
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({ "key_id": key_id, "public_key": public_key }))?
+                ),
+                OutputFormat::Text => println!("{}", key_id),
+            }
+            return Ok(());
+        }
+        KeyCommands::Rotate { key, new_key, effective, output } => {
+            let keypair = load_signing_key(Some(&key))?.expect("key path given");
+            let new_key = read_public_key(&new_key)?;
+            let envelope = rotation_statement(&keypair, &new_key, &effective.unwrap_or_else(now))?;
+            (envelope, output.unwrap_or_else(|| PathBuf::from("key_rotation.json")))
+        }
+        KeyCommands::Revoke { key, effective, reason, output } => {
+            let keypair = load_signing_key(Some(&key))?.expect("key path given");
+            let envelope = revocation_statement(&keypair, &effective.unwrap_or_else(now), reason.as_deref())?;
+            (envelope, output.unwrap_or_else(|| PathBuf::from("key_revocation.json")))
+        }
+        KeyCommands::Import { input, output, password, kid } => return cmd_key_import(&input, &output, password, kid.as_deref(), format),
+        KeyCommands::Export { key, to, public, output, comment } => {
+            return cmd_key_export(&key, to, public, output.as_deref(), comment.as_deref());
+        }
+        KeyCommands::Show { key, kid } => return cmd_key_show(&key, kid.as_deref(), format),
+        KeyCommands::Passwd { key, memory, iterations, parallelism, algorithm } => {
+            let defaults = KdfParams::default();
+            let params = KdfParams {
+                algorithm,
+                memory_kib: memory.unwrap_or(defaults.memory_kib),
+                iterations: iterations.unwrap_or(defaults.iterations),
+                parallelism: parallelism.unwrap_or(defaults.parallelism),
+                ..defaults
+            };
+            return cmd_key_passwd(&key, &params, format);
+        }
+    };
+
+    std::fs::write(&output, serde_json::to_string_pretty(&envelope)?)?;
+    let statement: KeyStatement = serde_json::from_str(&envelope.payload)?;
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "output": output, "statement": statement }))?
+        ),
+        OutputFormat::Text => {
+            match &statement {
+                KeyStatement::Rotation { old_key_id, new_key_id, effective_at, .. } => {
+                    println!("Signed rotation of key {} to {} from {}", old_key_id, new_key_id, effective_at)
+                }
+                KeyStatement::Revocation { key_id, effective_at, .. } => {
+                    println!("Signed revocation of key {} from {}", key_id, effective_at)
+                }
+            }
+            println!("Statement written to {} - publish it so verifiers can `sealed-ch trust import` it", output.display());
+        }
+    }
+    Ok(())
+}
+
+fn cmd_cosign(sealed_dir: &Path, key: &Path, format: OutputFormat) -> Result<()> {
+    let signed_path = [sealed_dir.join("signed_record.json"), sealed_dir.join(SIGNED_BATCH_FILE)]
+        .into_iter()
+        .find(|p| p.exists())
+        .with_context(|| format!("{} has no signed record; seal it with --key first", sealed_dir.display()))?;
+    let mut envelope: SignedEnvelope = serde_json::from_str(&std::fs::read_to_string(&signed_path)?)
+        .with_context(|| format!("Failed to read {}", signed_path.display()))?;
+    let keypair = load_signing_key(Some(key))?.expect("key path given");
+    let key_id = envelope
+        .cosign(&keypair)
+        .with_context(|| format!("Cannot co-sign {}", signed_path.display()))?
+        .key_id
+        .clone();
+    // The record holds every signature over the seal, so never leave it half-written.
+    save_pretty(&signed_path, &envelope)?;
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "signed_record": signed_path,
+            "key_id": key_id,
+            "signatures": 1 + envelope.cosignatures.len(),
+        }))?);
+        return Ok(());
+    }
+    println!("Co-signed {} with key {}", signed_path.display(), key_id);
+    println!(
+        "Signatures: {} (signed by {}, co-signed by {})",
+        1 + envelope.cosignatures.len(),
+        envelope.key_id.as_deref().unwrap_or(&envelope.public_key),
+        envelope.cosignatures.iter().map(|c| c.key_id.as_str()).collect::<Vec<_>>().join(", ")
+    );
+    if sealed_dir.join("ipfs_record.json").exists() {
+        println!("The pinned signed record predates this co-signature; pin it again to publish it.");
+    }
+    Ok(())
+}
+
+fn cmd_key_import(input: &Path, output_dir: &Path, encrypt: bool, kid: Option<&str>, format: OutputFormat) -> Result<()> {
+    let data = zeroize::Zeroizing::new(
+        std::fs::read(input).with_context(|| format!("Failed to read {}", input.display()))?,
+    );
+    let (keypair, source) = if is_encrypted_key(&data) {
+        (load_signing_key(Some(input))?.expect("key path given"), "sealed".to_string())
+    } else {
+        let (keypair, source) = parse_secret_key(&data, kid)
+            .with_context(|| format!("Cannot import {}", input.display()))?;
+        (keypair, source.to_string())
+    };
+
+    std::fs::create_dir_all(output_dir)?;
+    let secret_path = output_dir.join("sealed.key");
+    let public_path = output_dir.join("sealed.pub");
+    if secret_path.exists() {
+        anyhow::bail!("{} already exists; import into another directory", secret_path.display());
+    }
+    save_keypair(&keypair, &secret_path, &public_path, encrypt)?;
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "source_format": source,
+            "secret_key": secret_path,
+            "public_key": public_path,
+            "key_id": keypair.key_id(),
+            "encrypted": encrypt,
+        }))?);
+        return Ok(());
+    }
+    println!("Imported {} key {} from {}", source, keypair.key_id(), input.display());
+    println!("Secret key: {}{}", secret_path.display(), if encrypt { " (password-encrypted)" } else { "" });
+    println!("Public key: {}", public_path.display());
+    Ok(())
+}
+
+fn cmd_key_export(key: &Path, to: KeyFormat, public: bool, output: Option<&Path>, comment: Option<&str>) -> Result<()> {
+    let keypair = load_signing_key(Some(key))?.expect("key path given");
+    let comment = comment.map(str::to_string).unwrap_or_else(|| format!("sealed-{}", &keypair.key_id()[..8]));
+    let bytes = if public {
+        zeroize::Zeroizing::new(export_public_key(&keypair.verifying_key(), to, &comment)?)
+    } else {
+        export_secret_key(&keypair, to, &comment)?
+    };
+    match output {
+        Some(path) if public => std::fs::write(path, &*bytes)?,
+        Some(path) => write_secret_file(path, &bytes)?,
+        None if to == KeyFormat::Raw => anyhow::bail!("Raw keys are binary; give --output"),
+        None => {
+            std::io::stdout().write_all(&bytes)?;
+            return Ok(());
+        }
+    }
+    eprintln!(
+        "Wrote {} {} key {} to {}",
+        to,
+        if public { "public" } else { "secret" },
+        keypair.key_id(),
+        output.expect("output given").display()
+    );
+    Ok(())
+}
+
+fn cmd_key_show(key: &Path, kid: Option<&str>, format: OutputFormat) -> Result<()> {
+    let data = zeroize::Zeroizing::new(std::fs::read(key).with_context(|| format!("Failed to read {}", key.display()))?);
+    let raw_public = data.len() == 32 && key.extension().is_some_and(|e| e == "pub");
+    let kdf = is_encrypted_key(&data).then(|| encrypted_key_format(&data)).transpose()?;
+    let (source, secret, verifying_key) = if let Some((version, _)) = kdf {
+        let keypair = load_signing_key(Some(key))?.expect("key path given");
+        (format!("sealed (password-encrypted, SEALED_ENC_V{})", version), true, keypair.verifying_key())
+    } else {
+        match (!raw_public).then(|| parse_secret_key(&data, kid)).and_then(Result::ok) {
+            Some((keypair, source)) => (source.to_string(), true, keypair.verifying_key()),
+            None => {
+                let (verifying_key, source) = parse_public_key(&data, kid)
+                    .with_context(|| format!("{} is not a readable Ed25519 key", key.display()))?;
+                (source.to_string(), false, verifying_key)
+            }
+        }
+    };
+    let key_id = sealed::signing::key_id(&verifying_key.to_bytes());
+    let public_key = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, verifying_key.to_bytes());
+    let openssh = openssh_public_line(&verifying_key, "");
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "format": source,
+            "secret": secret,
+            "key_id": key_id,
+            "public_key": public_key,
+            "openssh": openssh,
+            "kdf": kdf.map(|(_, params)| params),
+        }))?);
+        return Ok(());
+    }
+    println!("Format:     {} ({} key)", source, if secret { "secret" } else { "public" });
+    if let Some((_, params)) = kdf {
+        println!("KDF:        {}", params);
+    }
+    println!("Key ID:     {}", key_id);
+    println!("Public key: {}", public_key);
+    println!("OpenSSH:    {}", openssh);
+    Ok(())
+}
+
+fn cmd_key_passwd(key: &Path, params: &KdfParams, format: OutputFormat) -> Result<()> {
+    params.validate()?;
+    let data = std::fs::read(key).with_context(|| format!("Failed to read {}", key.display()))?;
+    if !is_encrypted_key(&data) {
+        anyhow::bail!(
+            "{} is not a password-encrypted sealed key; use `sealed-ch key import --password` to encrypt one",
+            key.display()
+        );
+    }
+    let (old_version, old_params) = encrypted_key_format(&data)?;
+    let password = rpassword::prompt_password("Enter current key password: ")
+        .context("Failed to read password")?;
+    let keypair = SealedKeyPair::load_encrypted(key, &password)
+        .context("Failed to decrypt signing key")?;
+
+    let password = prompt_new_password()?;
+    // Write next to the key and rename over it, so a failure leaves the old file intact.
+    let mut temp = key.as_os_str().to_owned();
+    temp.push(".new");
+    let temp = PathBuf::from(temp);
+    keypair.save_secret_encrypted_with(&temp, &password, params)
+        .context("Failed to save encrypted secret key")?;
+    std::fs::rename(&temp, key).with_context(|| format!("Failed to replace {}", key.display()))?;
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "key": key,
+            "key_id": keypair.key_id(),
+            "previous_format": format!("SEALED_ENC_V{}", old_version),
+            "previous_kdf": old_params,
+            "kdf": params,
+        }))?);
+        return Ok(());
+    }
+    println!("Re-encrypted {} (key {})", key.display(), keypair.key_id());
+    println!("Was: SEALED_ENC_V{}, {}", old_version, old_params);
+    println!("Now: SEALED_ENC_V2, {}", params);
+    Ok(())
+}
+
+fn cmd_registry(registry_path: &Path, command: RegistryCommands) -> Result<()> {
+    let mut registry = Registry::load(registry_path)
+        .with_context(|| format!("Failed to read {}", registry_path.display()))?;
+
+    match command {
+        RegistryCommands::Add { paths } => {
+            let (mut added, mut updated) = (0, 0);
+            for path in &paths {
+                for entry in collect_entries(path)? {
+                    println!("{}  {}", entry.id, entry.sealed_dir.display());
+                    if registry.add(entry) { added += 1 } else { updated += 1 }
+                }
+            }
+            registry.save(registry_path)?;
+            println!("{} added, {} updated ({} works in {})", added, updated, registry.entries.len(), registry_path.display());
+        }
+
+        RegistryCommands::Search { image, min_confidence, limit } => {
+            let min: SimilarityConfidence = min_confidence.parse()?;
+            let img = open_image_by_content(&image)?;
+            let hashes = compute_hash_record(&img)?;
+            let matches = registry.search(&hashes, min);
+            if matches.is_empty() {
+                println!("No sealed work matches {} at {} or better.", image.display(), min);
+            }
+            for m in matches.iter().take(limit) {
+                println!(
+                    "{:<6} {}  {}  (vs {}, aHash {} dHash {} pHash {})",
+                    m.report.confidence.to_string(), m.entry.id, m.entry.sealed_dir.display(), m.matched,
+                    m.report.ahash_hamming, m.report.dhash_hamming, m.report.phash_hamming,
+                );
+                if let Some(signer) = &m.entry.signer {
+                    println!("       signed by {}", signer);
+                }
+            }
+        }
+
+        RegistryCommands::List => {
+            for entry in &registry.entries {
+                let signer = entry.signer.as_deref().unwrap_or("unsigned");
+                println!("{}  {}  {}x{}  {}", entry.id, entry.sealed_dir.display(), entry.original.width, entry.original.height, signer);
+            }
+            println!("{} works in {}", registry.entries.len(), registry_path.display());
+        }
+
+        RegistryCommands::Remove { target } => {
+            let removed = registry.remove(&target)?;
+            if removed == 0 {
+                anyhow::bail!("No registry entry matches {}", target);
+            }
+            registry.save(registry_path)?;
+            println!("Removed {} entr{}", removed, if removed == 1 { "y" } else { "ies" });
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_ipfs(profiles_path: &Path, command: IpfsCommands) -> Result<()> {
+    let config_for = |profile: Option<&str>| -> Result<IpfsConfig> {
+        Ok(resolve_config(profiles_path, profile, None, None)?)
+    };
+
+    match command {
+        IpfsCommands::List { profile, cid } => {
+            let config = config_for(profile.as_deref())?;
+            let pins = list_pins(&config, cid.as_deref())?;
+            println!("{} pins at {} ({})", pins.len(), config.api_url, config.provider.as_str());
+            for pin in &pins {
+                print_pin(pin);
+            }
+        }
+
+        IpfsCommands::Status { target, profile } => {
+            let config = config_for(profile.as_deref())?;
+            let pins = pin_status(&config, &target)?;
+            if pins.is_empty() {
+                println!("{}: not pinned", target);
+            }
+            for pin in &pins {
+                print_pin(pin);
+            }
+        }
+
+        IpfsCommands::Unpin { target, profile } => {
+            let config = config_for(profile.as_deref())?;
+            let removed = unpin(&config, &target)?;
+            if removed == 0 {
+                anyhow::bail!("No pin found for {}", target);
+            }
+            println!("Removed {} pin(s) for {}", removed, target);
+        }
+
+        IpfsCommands::Profile { command } => {
+            let mut profiles = IpfsProfiles::load(profiles_path)?;
+            match command {
+                ProfileCommands::Add {
+                    name,
+                    provider,
+                    api_url,
+                    token_env,
+                    token,
+                    gateway_url,
+                    source_node,
+                    default,
+                } => {
+                    let api_url = match (api_url, provider) {
+                        (Some(url), _) => url,
+                        (None, IpfsProvider::Kubo) => IpfsConfig::local().api_url,
+                        (None, IpfsProvider::Pinata) => IpfsConfig::pinata("").api_url,
+                        (None, IpfsProvider::PinningService) => {
+                            anyhow::bail!("--api-url is required for a pinning-service profile")
+                        }
+                    };
+                    let profile = IpfsProfile { provider, api_url, token, token_env, gateway_url, source_node };
+                    // Catch a missing token now rather than at the next seal.
+                    if profile.token.is_none() && profile.token_env.is_none() && provider != IpfsProvider::Kubo {
+                        anyhow::bail!("A {} profile needs --token or --token-env", provider.as_str());
+                    }
+                    profiles.profiles.insert(name.clone(), profile);
+                    if default || profiles.default.is_none() {
+                        profiles.default = Some(name.clone());
+                    }
+                    profiles.save(profiles_path)?;
+                    println!("Saved profile '{}' to {}", name, profiles_path.display());
+                }
+
+                ProfileCommands::List => {
+                    if profiles.profiles.is_empty() {
+                        println!("No profiles in {}", profiles_path.display());
+                    }
+                    for (name, profile) in &profiles.profiles {
+                        let marker = if profiles.default.as_deref() == Some(name) { "*" } else { " " };
+                        let token = match (&profile.token_env, &profile.token) {
+                            (Some(var), _) => format!("token from ${}", var),
+                            (None, Some(_)) => "token stored".to_string(),
+                            (None, None) => "no token".to_string(),
+                        };
+                        println!("{} {:<16} {:<16} {} ({})", marker, name, profile.provider.as_str(), profile.api_url, token);
+                    }
+                }
+
+                ProfileCommands::Remove { name } => {
+                    if profiles.profiles.remove(&name).is_none() {
+                        anyhow::bail!("No IPFS profile named '{}'", name);
+                    }
+                    if profiles.default.as_deref() == Some(name.as_str()) {
+                        profiles.default = None;
+                    }
+                    profiles.save(profiles_path)?;
+                    println!("Removed profile '{}'", name);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_pin(pin: &PinInfo) {
+    print!("{}  {}", pin.cid, pin.status);
+    if let Some(name) = &pin.name {
+        print!("  {}", name);
+    }
+    if let Some(id) = &pin.request_id {
+        print!("  request={}", id);
+    }
+    if let Some(created) = &pin.created {
+        print!("  {}", created);
+    }
+    println!();
+}
+
+fn cmd_scan(suspects_dir: &Path, sealed_root: &Path, output: Option<&Path>, config: &ScanConfig) -> Result<()> {
+    if !suspects_dir.is_dir() {
+        anyhow::bail!("{} is not a directory", suspects_dir.display());
+    }
+    let mut suspects: Vec<PathBuf> = std::fs::read_dir(suspects_dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && is_image_file(p))
+        .collect();
+    suspects.sort();
+    let registry = Registry { entries: collect_entries(sealed_root)? };
+    if registry.entries.is_empty() {
+        anyhow::bail!("No sealed records found under {}", sealed_root.display());
+    }
+
+    let report = scan_suspects(&suspects, &registry, config);
+
+    println!("\n=== SCAN RESULT ===");
+    println!("{} suspects vs {} sealed records: {} matches", report.suspects, report.records, report.matches.len());
+    for m in &report.matches {
+        let offset = match m.tile_offset {
+            Some((dx, dy)) => format!(", crop at ({}, {})", dx, dy),
+            None => String::new(),
+        };
+        println!(
+            "{:<6} {}  ->  {}  (vs {}{}, signature {})",
+            m.confidence.to_string(), m.suspect.display(), m.sealed_dir.display(), m.matched, offset, m.signature,
+        );
+    }
+    for failure in &report.failures {
+        println!("FAILED {}: {}", failure.suspect.display(), failure.error);
+    }
+
+    let output = output.map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("scan_report.json"));
+    let contents = if output.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv")) {
+        report.to_csv()
+    } else {
+        serde_json::to_string_pretty(&report)?
+    };
+    std::fs::write(&output, contents)?;
+    println!("Report written to {}", output.display());
+    Ok(())
+}
+
+/// Images plus the video and PDF formats `cmd_seal` accepts.
+fn is_media_file(path: &Path) -> bool {
+    let ext = path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    is_image_file(path) || matches!(ext.as_str(), "mp4" | "avi" | "mov" | "mkv" | "webm" | "pdf")
+}
+
 /// Check if a file is likely an image based on extension.
 fn is_image_file(path: &Path) -> bool {
     let ext = path.extension()
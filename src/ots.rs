@@ -0,0 +1,652 @@
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+
+use crate::errors::{SealedError, SealedResult};
+
+/// OTS proof file magic header.
+pub const OTS_MAGIC: &[u8] = b"\x00OpenTimestamps\x00\x00Proof\x00\xbf\x89\xe2\xe8\x84\xe8\x92\x94";
+
+/// Major version of the detached timestamp file format.
+pub const OTS_VERSION: u64 = 1;
+
+const TAG_SHA1: u8 = 0x02;
+const TAG_RIPEMD160: u8 = 0x03;
+const TAG_SHA256: u8 = 0x08;
+const TAG_KECCAK256: u8 = 0x67;
+const TAG_APPEND: u8 = 0xf0;
+const TAG_PREPEND: u8 = 0xf1;
+const TAG_REVERSE: u8 = 0xf2;
+const TAG_HEXLIFY: u8 = 0xf3;
+
+/// Marks an attestation (rather than an op) in the timestamp tree.
+const TAG_ATTESTATION: u8 = 0x00;
+/// Prefixes every branch except the last at a node with several children.
+const TAG_FORK: u8 = 0xff;
+
+const ATTESTATION_PENDING: [u8; 8] = [0x83, 0xdf, 0xe3, 0x0d, 0x2e, 0xf9, 0x0c, 0x8e];
+const ATTESTATION_BITCOIN: [u8; 8] = [0x05, 0x88, 0x96, 0x0d, 0x73, 0xd7, 0x19, 0x01];
+const ATTESTATION_LITECOIN: [u8; 8] = [0x06, 0x86, 0x9a, 0x0d, 0x73, 0xd7, 0x1b, 0x45];
+
+/// Upper bounds matching the reference implementation.
+const MAX_OP_ARG: usize = 4096;
+const MAX_MSG_LEN: usize = 4096;
+const MAX_PAYLOAD: usize = 8192;
+const MAX_URI_LEN: usize = 1000;
+const MAX_DEPTH: usize = 256;
+
+/// A commitment operation in the timestamp tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    Sha1,
+    Ripemd160,
+    Sha256,
+    Keccak256,
+    Append(Vec<u8>),
+    Prepend(Vec<u8>),
+    Reverse,
+    Hexlify,
+}
+
+impl Op {
+    fn tag(&self) -> u8 {
+        match self {
+            Op::Sha1 => TAG_SHA1,
+            Op::Ripemd160 => TAG_RIPEMD160,
+            Op::Sha256 => TAG_SHA256,
+            Op::Keccak256 => TAG_KECCAK256,
+            Op::Append(_) => TAG_APPEND,
+            Op::Prepend(_) => TAG_PREPEND,
+            Op::Reverse => TAG_REVERSE,
+            Op::Hexlify => TAG_HEXLIFY,
+        }
+    }
+
+    /// Apply the op to a message.
+    pub fn apply(&self, msg: &[u8]) -> Vec<u8> {
+        match self {
+            Op::Sha1 => sha1::Sha1::digest(msg).to_vec(),
+            Op::Ripemd160 => ripemd::Ripemd160::digest(msg).to_vec(),
+            Op::Sha256 => Sha256::digest(msg).to_vec(),
+            Op::Keccak256 => sha3::Keccak256::digest(msg).to_vec(),
+            Op::Append(arg) => [msg, arg.as_slice()].concat(),
+            Op::Prepend(arg) => [arg.as_slice(), msg].concat(),
+            Op::Reverse => msg.iter().rev().copied().collect(),
+            Op::Hexlify => hex::encode(msg).into_bytes(),
+        }
+    }
+
+    /// Digest length for hash ops, used by the file header.
+    fn digest_len(&self) -> Option<usize> {
+        match self {
+            Op::Sha1 | Op::Ripemd160 => Some(20),
+            Op::Sha256 | Op::Keccak256 => Some(32),
+            _ => None,
+        }
+    }
+
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.push(self.tag());
+        if let Op::Append(arg) | Op::Prepend(arg) = self {
+            write_varbytes(out, arg);
+        }
+    }
+
+    fn parse_from_tag(tag: u8, reader: &mut Reader) -> SealedResult<Self> {
+        Ok(match tag {
+            TAG_SHA1 => Op::Sha1,
+            TAG_RIPEMD160 => Op::Ripemd160,
+            TAG_SHA256 => Op::Sha256,
+            TAG_KECCAK256 => Op::Keccak256,
+            TAG_APPEND => Op::Append(reader.read_varbytes(1, MAX_OP_ARG)?),
+            TAG_PREPEND => Op::Prepend(reader.read_varbytes(1, MAX_OP_ARG)?),
+            TAG_REVERSE => Op::Reverse,
+            TAG_HEXLIFY => Op::Hexlify,
+            other => {
+                return Err(SealedError::TimestampError(format!(
+                    "Unknown OTS op tag 0x{:02x}",
+                    other
+                )))
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Op::Sha1 => write!(f, "sha1"),
+            Op::Ripemd160 => write!(f, "ripemd160"),
+            Op::Sha256 => write!(f, "sha256"),
+            Op::Keccak256 => write!(f, "keccak256"),
+            Op::Append(arg) => write!(f, "append {}", hex::encode(arg)),
+            Op::Prepend(arg) => write!(f, "prepend {}", hex::encode(arg)),
+            Op::Reverse => write!(f, "reverse"),
+            Op::Hexlify => write!(f, "hexlify"),
+        }
+    }
+}
+
+/// A claim that a commitment existed at some point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Attestation {
+    /// Submitted to a calendar; not yet anchored in a blockchain.
+    Pending { uri: String },
+    /// Commitment is the merkle root of the Bitcoin block at `height`.
+    Bitcoin { height: u64 },
+    /// Commitment is the merkle root of the Litecoin block at `height`.
+    Litecoin { height: u64 },
+    /// Attestation type this parser doesn't understand; kept for round-tripping.
+    Unknown { tag: [u8; 8], payload: Vec<u8> },
+}
+
+impl Attestation {
+    fn serialize(&self, out: &mut Vec<u8>) {
+        let mut payload = Vec::new();
+        let tag = match self {
+            Attestation::Pending { uri } => {
+                write_varbytes(&mut payload, uri.as_bytes());
+                ATTESTATION_PENDING
+            }
+            Attestation::Bitcoin { height } => {
+                write_varuint(&mut payload, *height);
+                ATTESTATION_BITCOIN
+            }
+            Attestation::Litecoin { height } => {
+                write_varuint(&mut payload, *height);
+                ATTESTATION_LITECOIN
+            }
+            Attestation::Unknown { tag, payload: raw } => {
+                payload.extend_from_slice(raw);
+                *tag
+            }
+        };
+        out.extend_from_slice(&tag);
+        write_varbytes(out, &payload);
+    }
+
+    fn parse(reader: &mut Reader) -> SealedResult<Self> {
+        let tag: [u8; 8] = reader
+            .read_bytes(8)?
+            .try_into()
+            .map_err(|_| SealedError::TimestampError("Truncated attestation tag".to_string()))?;
+        let payload = reader.read_varbytes(0, MAX_PAYLOAD)?;
+        let mut inner = Reader::new(&payload);
+
+        let attestation = match tag {
+            ATTESTATION_PENDING => {
+                let uri = inner.read_varbytes(0, MAX_URI_LEN)?;
+                let uri = String::from_utf8(uri).map_err(|_| {
+                    SealedError::TimestampError("Pending attestation URI is not UTF-8".to_string())
+                })?;
+                if !uri
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-._/:".contains(c))
+                {
+                    return Err(SealedError::TimestampError(format!(
+                        "Invalid character in pending attestation URI: {}",
+                        uri
+                    )));
+                }
+                Attestation::Pending { uri }
+            }
+            ATTESTATION_BITCOIN => Attestation::Bitcoin { height: inner.read_varuint()? },
+            ATTESTATION_LITECOIN => Attestation::Litecoin { height: inner.read_varuint()? },
+            _ => {
+                return Ok(Attestation::Unknown { tag, payload })
+            }
+        };
+
+        if !inner.is_empty() {
+            return Err(SealedError::TimestampError(
+                "Trailing bytes in attestation payload".to_string(),
+            ));
+        }
+        Ok(attestation)
+    }
+}
+
+impl std::fmt::Display for Attestation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Attestation::Pending { uri } => write!(f, "pending at {}", uri),
+            Attestation::Bitcoin { height } => write!(f, "Bitcoin block {}", height),
+            Attestation::Litecoin { height } => write!(f, "Litecoin block {}", height),
+            Attestation::Unknown { tag, .. } => write!(f, "unknown attestation {}", hex::encode(tag)),
+        }
+    }
+}
+
+/// A node in the timestamp tree: attestations on the current message plus
+/// op branches leading to further commitments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timestamp {
+    pub attestations: Vec<Attestation>,
+    pub ops: Vec<(Op, Timestamp)>,
+}
+
+impl Timestamp {
+    /// Serialize the tree (without file header).
+    pub fn serialize(&self, out: &mut Vec<u8>) {
+        let branches = self.attestations.len() + self.ops.len();
+        let mut written = 0;
+
+        for attestation in &self.attestations {
+            written += 1;
+            if written < branches {
+                out.push(TAG_FORK);
+            }
+            out.push(TAG_ATTESTATION);
+            attestation.serialize(out);
+        }
+        for (op, child) in &self.ops {
+            written += 1;
+            if written < branches {
+                out.push(TAG_FORK);
+            }
+            op.serialize(out);
+            child.serialize(out);
+        }
+    }
+
+    /// Parse a serialized tree (as returned by calendar servers).
+    pub fn parse(data: &[u8]) -> SealedResult<Self> {
+        let mut reader = Reader::new(data);
+        let timestamp = Self::parse_from(&mut reader, 0)?;
+        if !reader.is_empty() {
+            return Err(SealedError::TimestampError(
+                "Trailing bytes after OTS timestamp".to_string(),
+            ));
+        }
+        Ok(timestamp)
+    }
+
+    fn parse_from(reader: &mut Reader, depth: usize) -> SealedResult<Self> {
+        if depth > MAX_DEPTH {
+            return Err(SealedError::TimestampError(
+                "OTS timestamp nested too deeply".to_string(),
+            ));
+        }
+
+        let mut timestamp = Timestamp::default();
+        loop {
+            let mut tag = reader.read_u8()?;
+            let is_fork = tag == TAG_FORK;
+            if is_fork {
+                tag = reader.read_u8()?;
+            }
+
+            if tag == TAG_ATTESTATION {
+                timestamp.attestations.push(Attestation::parse(reader)?);
+            } else {
+                let op = Op::parse_from_tag(tag, reader)?;
+                let child = Self::parse_from(reader, depth + 1)?;
+                timestamp.ops.push((op, child));
+            }
+
+            if !is_fork {
+                return Ok(timestamp);
+            }
+        }
+    }
+
+    /// Every attestation in the tree with the commitment it attests to,
+    /// obtained by replaying the ops from `msg`.
+    pub fn attestations_from(&self, msg: &[u8]) -> SealedResult<Vec<(Vec<u8>, Attestation)>> {
+        let mut found = Vec::new();
+        self.collect_attestations(msg, &mut found)?;
+        Ok(found)
+    }
+
+    fn collect_attestations(
+        &self,
+        msg: &[u8],
+        found: &mut Vec<(Vec<u8>, Attestation)>,
+    ) -> SealedResult<()> {
+        for attestation in &self.attestations {
+            found.push((msg.to_vec(), attestation.clone()));
+        }
+        for (op, child) in &self.ops {
+            let next = op.apply(msg);
+            if next.len() > MAX_MSG_LEN {
+                return Err(SealedError::TimestampError(format!(
+                    "OTS op '{}' produced an oversized message",
+                    op
+                )));
+            }
+            child.collect_attestations(&next, found)?;
+        }
+        Ok(())
+    }
+
+    /// Merge another tree for the same message into this one.
+    pub fn merge(&mut self, other: Timestamp) {
+        for attestation in other.attestations {
+            if !self.attestations.contains(&attestation) {
+                self.attestations.push(attestation);
+            }
+        }
+        for (op, child) in other.ops {
+            match self.ops.iter_mut().find(|(existing, _)| *existing == op) {
+                Some((_, existing_child)) => existing_child.merge(child),
+                None => self.ops.push((op, child)),
+            }
+        }
+    }
+
+    /// The node whose message is `commitment`, replaying ops from `msg`.
+    pub fn node_for_commitment_mut(
+        &mut self,
+        msg: &[u8],
+        commitment: &[u8],
+    ) -> Option<&mut Timestamp> {
+        if msg == commitment {
+            return Some(self);
+        }
+        for (op, child) in self.ops.iter_mut() {
+            let next = op.apply(msg);
+            if next.len() > MAX_MSG_LEN {
+                continue;
+            }
+            if let Some(node) = child.node_for_commitment_mut(&next, commitment) {
+                return Some(node);
+            }
+        }
+        None
+    }
+
+    /// Pending calendar attestations as (commitment, calendar URI) pairs.
+    pub fn pending_from(&self, msg: &[u8]) -> SealedResult<Vec<(Vec<u8>, String)>> {
+        Ok(self
+            .attestations_from(msg)?
+            .into_iter()
+            .filter_map(|(commitment, attestation)| match attestation {
+                Attestation::Pending { uri } => Some((commitment, uri)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// True if any branch carries a blockchain attestation.
+    pub fn is_complete(&self) -> bool {
+        self.attestations
+            .iter()
+            .any(|a| matches!(a, Attestation::Bitcoin { .. } | Attestation::Litecoin { .. }))
+            || self.ops.iter().any(|(_, child)| child.is_complete())
+    }
+}
+
+/// A `.ots` file: header, hash of the timestamped data and the timestamp tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetachedTimestampFile {
+    pub file_hash_op: Op,
+    pub digest: Vec<u8>,
+    pub timestamp: Timestamp,
+}
+
+impl DetachedTimestampFile {
+    /// Serialize to `.ots` bytes.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(128);
+        out.extend_from_slice(OTS_MAGIC);
+        write_varuint(&mut out, OTS_VERSION);
+        self.file_hash_op.serialize(&mut out);
+        out.extend_from_slice(&self.digest);
+        self.timestamp.serialize(&mut out);
+        out
+    }
+
+    /// Parse `.ots` bytes.
+    pub fn parse(data: &[u8]) -> SealedResult<Self> {
+        if !data.starts_with(OTS_MAGIC) {
+            return Err(SealedError::TimestampError(
+                "Not an OpenTimestamps proof (bad magic header)".to_string(),
+            ));
+        }
+        let mut reader = Reader::new(&data[OTS_MAGIC.len()..]);
+
+        let version = reader.read_varuint()?;
+        if version != OTS_VERSION {
+            return Err(SealedError::TimestampError(format!(
+                "Unsupported OTS version {}",
+                version
+            )));
+        }
+
+        let tag = reader.read_u8()?;
+        let file_hash_op = Op::parse_from_tag(tag, &mut reader)?;
+        let digest_len = file_hash_op.digest_len().ok_or_else(|| {
+            SealedError::TimestampError(format!("File hash op '{}' is not a hash", file_hash_op))
+        })?;
+        let digest = reader.read_bytes(digest_len)?.to_vec();
+
+        let timestamp = Timestamp::parse_from(&mut reader, 0)?;
+        if !reader.is_empty() {
+            return Err(SealedError::TimestampError(
+                "Trailing bytes after OTS timestamp".to_string(),
+            ));
+        }
+
+        Ok(Self { file_hash_op, digest, timestamp })
+    }
+
+    /// Timestamp for a SHA-256 digest with an empty tree.
+    pub fn from_sha256(digest: &[u8]) -> Self {
+        Self {
+            file_hash_op: Op::Sha256,
+            digest: digest.to_vec(),
+            timestamp: Timestamp::default(),
+        }
+    }
+
+    /// Read and parse a `.ots` file.
+    pub fn load(path: &Path) -> SealedResult<Self> {
+        if !path.exists() {
+            return Err(SealedError::FileNotFound(path.display().to_string()));
+        }
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// Write the proof to a `.ots` file.
+    pub fn save(&self, path: &Path) -> SealedResult<()> {
+        std::fs::write(path, self.serialize())?;
+        Ok(())
+    }
+}
+
+/// Bitcoin block header supplied for offline verification.
+#[derive(Debug, Clone)]
+pub struct BlockHeader {
+    raw: [u8; 80],
+}
+
+impl BlockHeader {
+    /// Header from its 80-byte serialization.
+    pub fn from_bytes(bytes: &[u8]) -> SealedResult<Self> {
+        let raw: [u8; 80] = bytes.try_into().map_err(|_| {
+            SealedError::InvalidInput(format!(
+                "Block header must be 80 bytes, got {}",
+                bytes.len()
+            ))
+        })?;
+        Ok(Self { raw })
+    }
+
+    /// Load a header file: raw 80 bytes or 160 hex characters.
+    pub fn load(path: &Path) -> SealedResult<Self> {
+        if !path.exists() {
+            return Err(SealedError::FileNotFound(path.display().to_string()));
+        }
+        let data = std::fs::read(path)?;
+        if data.len() == 80 {
+            return Self::from_bytes(&data);
+        }
+        let text = String::from_utf8_lossy(&data);
+        let bytes = hex::decode(text.trim()).map_err(|e| {
+            SealedError::InvalidInput(format!("Block header is neither raw nor hex: {}", e))
+        })?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Merkle root in internal byte order, as committed to by OTS.
+    pub fn merkle_root(&self) -> &[u8] {
+        &self.raw[36..68]
+    }
+
+    /// Block timestamp (Unix seconds).
+    pub fn time(&self) -> u32 {
+        u32::from_le_bytes([self.raw[68], self.raw[69], self.raw[70], self.raw[71]])
+    }
+
+    /// Block hash in the usual display (reversed) hex form.
+    pub fn block_hash(&self) -> String {
+        let first = Sha256::digest(self.raw);
+        let mut hash = Sha256::digest(first).to_vec();
+        hash.reverse();
+        hex::encode(hash)
+    }
+}
+
+/// Outcome of checking one Bitcoin attestation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BitcoinCheck {
+    pub height: u64,
+    /// Commitment the ops produce; must equal the block's merkle root.
+    pub commitment: String,
+    /// `Some(true)` if the supplied header's merkle root matches the commitment.
+    pub header_matches: Option<bool>,
+    pub block_hash: Option<String>,
+    pub block_time: Option<String>,
+}
+
+/// Result of replaying an OTS proof from the sealed hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtsVerification {
+    pub digest: String,
+    pub digest_matches: bool,
+    pub pending_calendars: Vec<String>,
+    pub bitcoin: Vec<BitcoinCheck>,
+}
+
+impl OtsVerification {
+    /// True if some Bitcoin attestation was confirmed against a supplied header.
+    pub fn is_verified(&self) -> bool {
+        self.digest_matches && self.bitcoin.iter().any(|b| b.header_matches == Some(true))
+    }
+}
+
+/// Replay a proof's ops from `expected_sha256_hex` and report its attestations.
+/// A block header, if supplied, is checked against each Bitcoin attestation.
+pub fn verify_proof(
+    proof: &DetachedTimestampFile,
+    expected_sha256_hex: &str,
+    header: Option<&BlockHeader>,
+) -> SealedResult<OtsVerification> {
+    let expected = hex::decode(expected_sha256_hex)
+        .map_err(|e| SealedError::InvalidInput(format!("Invalid SHA-256 hex: {}", e)))?;
+
+    let digest_matches = proof.file_hash_op == Op::Sha256 && proof.digest == expected;
+
+    let mut pending_calendars = Vec::new();
+    let mut bitcoin = Vec::new();
+    for (commitment, attestation) in proof.timestamp.attestations_from(&proof.digest)? {
+        match attestation {
+            Attestation::Pending { uri } => pending_calendars.push(uri),
+            Attestation::Bitcoin { height } => {
+                let header_matches = header.map(|h| h.merkle_root() == commitment.as_slice());
+                let matched = header.filter(|_| header_matches == Some(true));
+                bitcoin.push(BitcoinCheck {
+                    height,
+                    commitment: hex::encode(&commitment),
+                    header_matches,
+                    block_hash: matched.map(|h| h.block_hash()),
+                    block_time: matched.and_then(|h| {
+                        chrono::DateTime::from_timestamp(h.time() as i64, 0)
+                            .map(|t| t.to_rfc3339())
+                    }),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(OtsVerification {
+        digest: hex::encode(&proof.digest),
+        digest_matches,
+        pending_calendars,
+        bitcoin,
+    })
+}
+
+fn write_varuint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_varbytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_varuint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Bounds-checked cursor over proof bytes.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn read_u8(&mut self) -> SealedResult<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_bytes(&mut self, n: usize) -> SealedResult<&'a [u8]> {
+        if self.data.len() - self.pos < n {
+            return Err(SealedError::TimestampError("Truncated OTS proof".to_string()));
+        }
+        let bytes = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn read_varuint(&mut self) -> SealedResult<u64> {
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            if shift >= 64 {
+                return Err(SealedError::TimestampError("OTS varuint overflow".to_string()));
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn read_varbytes(&mut self, min: usize, max: usize) -> SealedResult<Vec<u8>> {
+        let len = self.read_varuint()? as usize;
+        if len < min || len > max {
+            return Err(SealedError::TimestampError(format!(
+                "OTS field length {} outside {}..={}",
+                len, min, max
+            )));
+        }
+        Ok(self.read_bytes(len)?.to_vec())
+    }
+}
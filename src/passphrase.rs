@@ -0,0 +1,129 @@
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use serde::{Serialize, Deserialize};
+use zeroize::Zeroizing;
+
+use crate::errors::{SealedError, SealedResult};
+
+/// Where the passphrase of an encrypted signing key comes from, so `seal`,
+/// `watch` and `serve` can run without anyone at a terminal.
+///
+/// Written as `prompt`, `env:VAR`, `fd:N`, `file:PATH` or `stdin`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum PassphraseSource {
+    /// Ask on the terminal.
+    #[default]
+    Prompt,
+    /// The value of an environment variable.
+    Env(String),
+    /// The first line read from an inherited file descriptor.
+    Fd(u32),
+    /// The first line of a file.
+    File(PathBuf),
+    /// The first line piped to standard input.
+    Stdin,
+}
+
+impl PassphraseSource {
+    /// Read the passphrase. A trailing newline is not part of it.
+    pub fn read(&self, prompt: &str) -> SealedResult<Zeroizing<String>> {
+        let passphrase = match self {
+            PassphraseSource::Prompt => Zeroizing::new(
+                rpassword::prompt_password(prompt)
+                    .map_err(|e| SealedError::KeyError(format!("Failed to read password: {}", e)))?,
+            ),
+            PassphraseSource::Env(var) => Zeroizing::new(
+                std::env::var(var)
+                    .map_err(|_| SealedError::KeyError(format!("Passphrase variable {} is not set", var)))?,
+            ),
+            PassphraseSource::Fd(fd) => read_first_line(Path::new(&format!("/dev/fd/{}", fd)))?,
+            PassphraseSource::File(path) => read_first_line(path)?,
+            PassphraseSource::Stdin => {
+                let mut line = Zeroizing::new(String::new());
+                std::io::stdin()
+                    .lock()
+                    .read_line(&mut line)
+                    .map_err(|e| SealedError::KeyError(format!("Failed to read passphrase from stdin: {}", e)))?;
+                trim_newline(line)
+            }
+        };
+        if passphrase.is_empty() && *self != PassphraseSource::Prompt {
+            return Err(SealedError::KeyError(format!("Empty passphrase from {}", self)));
+        }
+        Ok(passphrase)
+    }
+
+    /// Make a relative passphrase file relative to `base`.
+    pub fn rebase(&mut self, base: &Path) {
+        if let PassphraseSource::File(path) = self {
+            if path.is_relative() {
+                *path = base.join(&*path);
+            }
+        }
+    }
+}
+
+fn read_first_line(path: &Path) -> SealedResult<Zeroizing<String>> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| SealedError::KeyError(format!("Failed to open passphrase source {}: {}", path.display(), e)))?;
+    let mut line = Zeroizing::new(String::new());
+    std::io::BufReader::new(file)
+        .read_line(&mut line)
+        .map_err(|e| SealedError::KeyError(format!("Failed to read passphrase from {}: {}", path.display(), e)))?;
+    Ok(trim_newline(line))
+}
+
+fn trim_newline(mut line: Zeroizing<String>) -> Zeroizing<String> {
+    let len = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(len);
+    line
+}
+
+impl FromStr for PassphraseSource {
+    type Err = SealedError;
+
+    fn from_str(s: &str) -> SealedResult<Self> {
+        let invalid = || {
+            SealedError::InvalidInput(format!(
+                "Invalid passphrase source '{}': expected prompt, env:VAR, fd:N, file:PATH or stdin",
+                s
+            ))
+        };
+        match s.split_once(':') {
+            None if s == "prompt" => Ok(PassphraseSource::Prompt),
+            None if s == "stdin" => Ok(PassphraseSource::Stdin),
+            Some(("env", var)) if !var.is_empty() => Ok(PassphraseSource::Env(var.to_string())),
+            Some(("fd", fd)) => fd.parse().map(PassphraseSource::Fd).map_err(|_| invalid()),
+            Some(("file", path)) if !path.is_empty() => Ok(PassphraseSource::File(PathBuf::from(path))),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for PassphraseSource {
+    type Error = SealedError;
+
+    fn try_from(s: String) -> SealedResult<Self> {
+        s.parse()
+    }
+}
+
+impl From<PassphraseSource> for String {
+    fn from(source: PassphraseSource) -> String {
+        source.to_string()
+    }
+}
+
+impl std::fmt::Display for PassphraseSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PassphraseSource::Prompt => f.write_str("prompt"),
+            PassphraseSource::Env(var) => write!(f, "env:{}", var),
+            PassphraseSource::Fd(fd) => write!(f, "fd:{}", fd),
+            PassphraseSource::File(path) => write!(f, "file:{}", path.display()),
+            PassphraseSource::Stdin => f.write_str("stdin"),
+        }
+    }
+}
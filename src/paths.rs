@@ -0,0 +1,12 @@
+use std::path::PathBuf;
+
+/// Per-user state directory: `$SEALED_HOME`, else `~/.sealed`.
+pub fn sealed_home() -> PathBuf {
+    if let Some(dir) = std::env::var_os("SEALED_HOME") {
+        return PathBuf::from(dir);
+    }
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".sealed"))
+        .unwrap_or_else(|| PathBuf::from(".sealed"))
+}
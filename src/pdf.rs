@@ -43,7 +43,7 @@ pub fn process_pdf(
     let mut page_files: Vec<_> = std::fs::read_dir(&pages_dir)?
         .filter_map(|e| e.ok())
         .map(|e| e.path())
-        .filter(|p| p.extension().map_or(false, |ext| ext == "png"))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "png"))
         .collect();
 
     page_files.sort();
@@ -59,7 +59,7 @@ pub fn process_pdf(
     let mut xor_image: Option<DynamicImage> = None;
     for page_path in &page_files {
         let page_img = ImageReader::open(page_path)
-            .map_err(|e| SealedError::Io(e))?
+            .map_err(SealedError::Io)?
             .decode()?;
 
             let cropped_page = crop_towards_center(&page_img, config)?;
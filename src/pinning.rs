@@ -0,0 +1,452 @@
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::cid::{cid_for_bytes, Cid};
+use crate::errors::{SealedError, SealedResult};
+use crate::ipfs::{import_sealed_dir, pin_directory, pin_to_local_ipfs, IpfsConfig, IpfsProvider, IpfsRecord};
+use crate::json_file::{load_or_default, save_pretty_private};
+
+pub const PROFILES_FILE: &str = "ipfs_profiles.json";
+
+/// Pinning Service statuses a listing asks for when no filter is given.
+const ALL_STATUSES: &str = "queued,pinning,pinned,failed";
+
+const POLL_START: Duration = Duration::from_millis(500);
+const POLL_MAX: Duration = Duration::from_secs(5);
+
+/// `~/.sealed/ipfs_profiles.json` (or under `$SEALED_HOME`).
+pub fn default_profiles_path() -> PathBuf {
+    crate::paths::sealed_home().join(PROFILES_FILE)
+}
+
+/// Named pinning credentials.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpfsProfile {
+    pub provider: IpfsProvider,
+    pub api_url: String,
+    /// Bearer token stored in the profile file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Environment variable to read the token from instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_env: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway_url: Option<String>,
+    /// Kubo node that provides content to a Pinning Service.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_node: Option<String>,
+}
+
+impl IpfsProfile {
+    /// Build pinning settings, resolving `token_env` now.
+    pub fn to_config(&self) -> SealedResult<IpfsConfig> {
+        let token = match &self.token_env {
+            Some(var) => Some(std::env::var(var).map_err(|_| {
+                SealedError::IpfsError(format!("Profile token variable {} is not set", var))
+            })?),
+            None => self.token.clone(),
+        };
+        if token.is_none() && self.provider != IpfsProvider::Kubo {
+            return Err(SealedError::IpfsError(format!(
+                "A {} profile needs a token or token_env", self.provider.as_str()
+            )));
+        }
+
+        let defaults = match self.provider {
+            IpfsProvider::Pinata => IpfsConfig::pinata(""),
+            _ => IpfsConfig::default(),
+        };
+        Ok(IpfsConfig {
+            provider: self.provider,
+            api_url: self.api_url.trim_end_matches('/').to_string(),
+            api_key: token,
+            gateway_url: self.gateway_url.clone().unwrap_or(defaults.gateway_url),
+            source_node: self.source_node.clone(),
+            ..defaults
+        })
+    }
+}
+
+/// The profile file: named profiles plus an optional default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IpfsProfiles {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, IpfsProfile>,
+}
+
+impl IpfsProfiles {
+    /// Load profiles; a missing file is an empty set.
+    pub fn load(path: &Path) -> SealedResult<Self> {
+        load_or_default(path)
+    }
+
+    /// Write the profile file atomically, readable only by the owner on Unix.
+    pub fn save(&self, path: &Path) -> SealedResult<()> {
+        save_pretty_private(path, self)
+    }
+
+    pub fn get(&self, name: &str) -> SealedResult<&IpfsProfile> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| SealedError::IpfsError(format!("No IPFS profile named '{}'", name)))
+    }
+}
+
+/// Pick pinning settings: a named profile, else a legacy Pinata key, else an
+/// explicit Kubo URL, else the default profile, else the local node. A key and
+/// a URL together are refused: the key is Pinata's and the URL a Kubo node's.
+pub fn resolve_config(
+    profiles_path: &Path,
+    profile: Option<&str>,
+    api_url: Option<&str>,
+    pinata_key: Option<&str>,
+) -> SealedResult<IpfsConfig> {
+    let profiles = IpfsProfiles::load(profiles_path)?;
+    if let Some(name) = profile {
+        return profiles.get(name)?.to_config();
+    }
+    match (pinata_key, api_url, &profiles.default) {
+        (Some(_), Some(url), _) => Err(SealedError::IpfsError(format!(
+            "A Pinata key cannot be used with the Kubo node at {}; use an IPFS profile for another provider",
+            url
+        ))),
+        (Some(key), None, _) => Ok(IpfsConfig::pinata(key)),
+        (None, Some(url), _) => Ok(IpfsConfig::kubo(url)),
+        (None, None, Some(name)) => profiles.get(name)?.to_config(),
+        (None, None, None) => Ok(IpfsConfig::local()),
+    }
+}
+
+/// One pin as reported by a provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinInfo {
+    pub cid: String,
+    /// `queued`, `pinning`, `pinned` or `failed`.
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Pinning Service request id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+}
+
+impl PinInfo {
+    /// Parse a Pinning Service `PinStatus` object.
+    fn from_pin_status(value: &serde_json::Value) -> SealedResult<Self> {
+        let field = |v: &serde_json::Value| v.as_str().map(|s| s.to_string());
+        Ok(Self {
+            cid: field(&value["pin"]["cid"])
+                .ok_or_else(|| SealedError::IpfsError("PinStatus without pin.cid".to_string()))?,
+            status: field(&value["status"])
+                .ok_or_else(|| SealedError::IpfsError("PinStatus without status".to_string()))?,
+            name: field(&value["pin"]["name"]),
+            request_id: field(&value["requestid"]),
+            created: field(&value["created"]),
+        })
+    }
+}
+
+fn client() -> reqwest::blocking::Client {
+    reqwest::blocking::Client::new()
+}
+
+fn with_auth(
+    request: reqwest::blocking::RequestBuilder,
+    config: &IpfsConfig,
+) -> reqwest::blocking::RequestBuilder {
+    match &config.api_key {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    }
+}
+
+/// Send a request and fail on a non-2xx status.
+fn send(
+    request: reqwest::blocking::RequestBuilder,
+    config: &IpfsConfig,
+) -> SealedResult<reqwest::blocking::Response> {
+    let response = with_auth(request, config).send().map_err(|e| {
+        SealedError::IpfsError(format!("{} request to {} failed: {}", config.provider.as_str(), config.api_url, e))
+    })?;
+    if !response.status().is_success() {
+        return Err(SealedError::IpfsError(format!(
+            "{} API returned status {}: {}",
+            config.provider.as_str(),
+            response.status(),
+            response.text().unwrap_or_default()
+        )));
+    }
+    Ok(response)
+}
+
+fn json(response: reqwest::blocking::Response) -> SealedResult<serde_json::Value> {
+    response
+        .json()
+        .map_err(|e| SealedError::IpfsError(format!("Failed to parse pinning response: {}", e)))
+}
+
+fn kubo_node(url: &str, config: &IpfsConfig) -> IpfsConfig {
+    IpfsConfig {
+        provider: IpfsProvider::Kubo,
+        api_url: url.to_string(),
+        api_key: None,
+        cid_version: config.cid_version,
+        ..IpfsConfig::default()
+    }
+}
+
+/// Multiaddrs a Kubo node listens on, passed to a Pinning Service as origins.
+fn node_origins(node_url: &str) -> Vec<String> {
+    client()
+        .post(format!("{}/api/v0/id", node_url))
+        .send()
+        .ok()
+        .and_then(|r| r.json::<serde_json::Value>().ok())
+        .and_then(|id| {
+            id["Addresses"].as_array().map(|addrs| {
+                addrs.iter().filter_map(|a| a.as_str().map(|s| s.to_string())).collect()
+            })
+        })
+        .unwrap_or_default()
+}
+
+/// Ask a Pinning Service to pin `cid` and poll until it settles or
+/// `poll_timeout_secs` passes. A `failed` pin is an error; a pin still
+/// queued at the deadline is returned as-is.
+pub fn request_pin(cid: &Cid, name: &str, config: &IpfsConfig) -> SealedResult<PinInfo> {
+    let mut body = serde_json::json!({ "cid": cid.to_string(), "name": name });
+    if let Some(node) = &config.source_node {
+        let origins = node_origins(node);
+        if !origins.is_empty() {
+            body["origins"] = serde_json::json!(origins);
+        }
+    }
+
+    info!("Requesting pin of {} from {}", cid, config.api_url);
+    let response = send(client().post(format!("{}/pins", config.api_url)).json(&body), config)?;
+    let status = json(response)?;
+    let mut pin = PinInfo::from_pin_status(&status)?;
+    check_pin_cid(cid, &pin)?;
+
+    // Let the service's delegates dial our node so it can fetch the blocks.
+    if let (Some(node), Some(delegates)) = (&config.source_node, status["delegates"].as_array()) {
+        for addr in delegates.iter().filter_map(|d| d.as_str()) {
+            let _ = client()
+                .post(format!("{}/api/v0/swarm/connect", node))
+                .query(&[("arg", addr)])
+                .send();
+        }
+    }
+
+    let request_id = pin
+        .request_id
+        .clone()
+        .ok_or_else(|| SealedError::IpfsError("PinStatus without requestid".to_string()))?;
+    let deadline = Instant::now() + Duration::from_secs(config.poll_timeout_secs);
+    let mut wait = POLL_START;
+    while matches!(pin.status.as_str(), "queued" | "pinning") && Instant::now() < deadline {
+        std::thread::sleep(wait.min(deadline.saturating_duration_since(Instant::now())));
+        wait = (wait * 2).min(POLL_MAX);
+        let response = send(client().get(format!("{}/pins/{}", config.api_url, request_id)), config)?;
+        pin = PinInfo::from_pin_status(&json(response)?)?;
+    }
+
+    match pin.status.as_str() {
+        "pinned" => info!("Pinned {} (request {})", cid, request_id),
+        "failed" => {
+            return Err(SealedError::IpfsError(format!(
+                "Pinning service failed to pin {} (request {})", cid, request_id
+            )));
+        }
+        other => warn!(
+            "Pin of {} still {}; check later with `sealed-ch ipfs status {}`",
+            cid, other, request_id
+        ),
+    }
+    Ok(pin)
+}
+
+fn check_pin_cid(expected: &Cid, pin: &PinInfo) -> SealedResult<()> {
+    let matches = pin.cid.parse::<Cid>().map(|c| c.same_content(expected)).unwrap_or(false);
+    if !matches {
+        return Err(SealedError::IpfsError(format!(
+            "Pinning service acknowledged CID {} but the content hashes to {}",
+            pin.cid, expected
+        )));
+    }
+    Ok(())
+}
+
+fn service_record(cid: &Cid, file: String, config: &IpfsConfig, pin: PinInfo) -> IpfsRecord {
+    IpfsRecord {
+        cid: cid.to_string(),
+        gateway_url: format!("{}/{}", config.gateway_url, cid),
+        service: IpfsProvider::PinningService.as_str().to_string(),
+        pinned_at: chrono::Utc::now().to_rfc3339(),
+        file,
+        files: Vec::new(),
+        request_id: pin.request_id,
+    }
+}
+
+/// Pin a file through a Pinning Service. The service pins by CID, so the
+/// bytes are first added to `source_node` when one is configured.
+pub fn pin_to_service(file_path: &Path, config: &IpfsConfig) -> SealedResult<IpfsRecord> {
+    if !file_path.exists() {
+        return Err(SealedError::FileNotFound(file_path.display().to_string()));
+    }
+    let file_name = file_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let cid = match &config.source_node {
+        Some(node) => pin_to_local_ipfs(file_path, &kubo_node(node, config))?.cid.parse()?,
+        None => {
+            warn!("No source node configured; the pinning service must find {} on the network", file_name);
+            cid_for_bytes(&std::fs::read(file_path)?, config.cid_version)
+        }
+    };
+
+    let pin = request_pin(&cid, &file_name, config)?;
+    Ok(service_record(&cid, file_name, config, pin))
+}
+
+/// Pin a sealed directory's root CID through a Pinning Service.
+pub fn pin_directory_to_service(dir: &Path, config: &IpfsConfig) -> SealedResult<IpfsRecord> {
+    let (root, files) = match &config.source_node {
+        Some(node) => {
+            let record = pin_directory(dir, &kubo_node(node, config))?;
+            (record.cid.parse::<Cid>()?, record.files)
+        }
+        None => {
+            let import = import_sealed_dir(dir, config.cid_version)?;
+            (import.root, import.files)
+        }
+    };
+
+    let name = dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let pin = request_pin(&root, &name, config)?;
+    Ok(IpfsRecord { files, ..service_record(&root, String::new(), config, pin) })
+}
+
+/// List pins held by the provider, optionally only those for `cid`.
+pub fn list_pins(config: &IpfsConfig, cid: Option<&str>) -> SealedResult<Vec<PinInfo>> {
+    match config.provider {
+        IpfsProvider::Kubo => {
+            let mut request = client()
+                .post(format!("{}/api/v0/pin/ls", config.api_url))
+                .query(&[("type", "recursive")]);
+            if let Some(cid) = cid {
+                request = request.query(&[("arg", cid)]);
+            }
+            let response = match send(request, config) {
+                Ok(r) => r,
+                // Kubo reports an unpinned `arg` as an error.
+                Err(SealedError::IpfsError(msg)) if cid.is_some() && msg.contains("not pinned") => {
+                    return Ok(Vec::new());
+                }
+                Err(e) => return Err(e),
+            };
+            let body = json(response)?;
+            let keys = body["Keys"].as_object().cloned().unwrap_or_default();
+            Ok(keys
+                .keys()
+                .map(|cid| PinInfo {
+                    cid: cid.clone(),
+                    status: "pinned".to_string(),
+                    name: None,
+                    request_id: None,
+                    created: None,
+                })
+                .collect())
+        }
+        IpfsProvider::Pinata => {
+            let mut request = client()
+                .get(format!("{}/data/pinList", config.api_url))
+                .query(&[("status", "pinned"), ("pageLimit", "1000")]);
+            if let Some(cid) = cid {
+                request = request.query(&[("hashContains", cid)]);
+            }
+            let body = json(send(request, config)?)?;
+            let rows = body["rows"].as_array().cloned().unwrap_or_default();
+            Ok(rows
+                .iter()
+                .filter_map(|row| {
+                    Some(PinInfo {
+                        cid: row["ipfs_pin_hash"].as_str()?.to_string(),
+                        status: "pinned".to_string(),
+                        name: row["metadata"]["name"].as_str().map(|s| s.to_string()),
+                        request_id: None,
+                        created: row["date_pinned"].as_str().map(|s| s.to_string()),
+                    })
+                })
+                .collect())
+        }
+        IpfsProvider::PinningService => {
+            let mut request = client()
+                .get(format!("{}/pins", config.api_url))
+                .query(&[("status", ALL_STATUSES), ("limit", "1000")]);
+            if let Some(cid) = cid {
+                request = request.query(&[("cid", cid)]);
+            }
+            let body = json(send(request, config)?)?;
+            body["results"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+                .iter()
+                .map(PinInfo::from_pin_status)
+                .collect()
+        }
+    }
+}
+
+/// Status of a CID, or of a Pinning Service request id. Empty if not pinned.
+pub fn pin_status(config: &IpfsConfig, target: &str) -> SealedResult<Vec<PinInfo>> {
+    if config.provider == IpfsProvider::PinningService && target.parse::<Cid>().is_err() {
+        let response = send(client().get(format!("{}/pins/{}", config.api_url, target)), config)?;
+        return Ok(vec![PinInfo::from_pin_status(&json(response)?)?]);
+    }
+    list_pins(config, Some(target))
+}
+
+/// Remove the pin(s) for a CID, or one Pinning Service request id.
+/// Returns how many pins were removed.
+pub fn unpin(config: &IpfsConfig, target: &str) -> SealedResult<usize> {
+    match config.provider {
+        IpfsProvider::Kubo => {
+            send(
+                client().post(format!("{}/api/v0/pin/rm", config.api_url)).query(&[("arg", target)]),
+                config,
+            )?;
+            Ok(1)
+        }
+        IpfsProvider::Pinata => {
+            send(client().delete(format!("{}/pinning/unpin/{}", config.api_url, target)), config)?;
+            Ok(1)
+        }
+        IpfsProvider::PinningService => {
+            let request_ids: Vec<String> = if target.parse::<Cid>().is_ok() {
+                list_pins(config, Some(target))?
+                    .into_iter()
+                    .filter_map(|p| p.request_id)
+                    .collect()
+            } else {
+                vec![target.to_string()]
+            };
+            for id in &request_ids {
+                send(client().delete(format!("{}/pins/{}", config.api_url, id)), config)?;
+            }
+            Ok(request_ids.len())
+        }
+    }
+}
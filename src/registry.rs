@@ -0,0 +1,400 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use tracing::warn;
+
+use crate::batch::{item_signer, BatchManifest, BATCH_MANIFEST_FILE};
+use crate::errors::{SealedError, SealedResult};
+use crate::hashing::{compare_hashes, HashRecord, SimilarityConfidence, SimilarityReport};
+use crate::ipfs::IpfsRecord;
+use crate::json_file::{load_or_default, save_pretty};
+use crate::tile_hashing::TileHashIndex;
+use crate::verification::{record_signature_status, record_signers, SealedRecord, SignatureStatus};
+
+/// File name of the registry inside the sealed home directory.
+pub const REGISTRY_FILE: &str = "registry.json";
+
+/// Any match `compare_hashes` rates LOW or better has at least one perceptual
+/// hash within this distance, so searching this radius loses nothing.
+const SEARCH_RADIUS: u32 = 15;
+
+/// `~/.sealed/registry.json` (or under `$SEALED_HOME`).
+pub fn default_registry_path() -> PathBuf {
+    crate::paths::sealed_home().join(REGISTRY_FILE)
+}
+
+/// One sealed work in the registry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    pub id: String,
+    /// Absolute path of the directory holding the item's `hashes.json`.
+    pub sealed_dir: PathBuf,
+    pub original: HashRecord,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub share: Option<HashRecord>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tile_index: Option<TileHashIndex>,
+    /// Base64 public key of a valid signature over the record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipfs_cid: Option<String>,
+    #[serde(default)]
+    pub sealed_at: String,
+    pub added_at: String,
+}
+
+impl RegistryEntry {
+    /// Index one sealed item directory (one with a `hashes.json`). The
+    /// signer is recorded only when its signature covers this `hashes.json`:
+    /// through `signed_record.json`, or as an item of a signed directory seal.
+    pub fn from_sealed_dir(dir: &Path) -> SealedResult<Self> {
+        let hashes_path = dir.join("hashes.json");
+        if !hashes_path.exists() {
+            return Err(SealedError::FileNotFound(hashes_path.display().to_string()));
+        }
+        let record: SealedRecord = serde_json::from_str(&std::fs::read_to_string(&hashes_path)?)?;
+
+        let tile_index = record.tile_index.clone().or_else(|| {
+            std::fs::read_to_string(dir.join("tile_index.json"))
+                .ok()
+                .and_then(|json| serde_json::from_str(&json).ok())
+        });
+        let signer = if dir.join("signed_record.json").exists() {
+            match record_signature_status(dir, &record, None) {
+                Ok(SignatureStatus::Valid) => record_signers(dir).0.map(|s| s.public_key),
+                _ => None,
+            }
+        } else {
+            item_signer(dir).unwrap_or_else(|e| {
+                warn!("Not attributing {} to the batch signer: {}", dir.display(), e);
+                None
+            })
+        };
+        let ipfs_cid = std::fs::read_to_string(dir.join("ipfs_record.json"))
+            .ok()
+            .and_then(|json| serde_json::from_str::<IpfsRecord>(&json).ok())
+            .map(|r| r.cid);
+
+        Ok(Self {
+            id: uuid::Uuid::new_v4().simple().to_string()[..12].to_string(),
+            sealed_dir: dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf()),
+            original: record.original,
+            share: record.share,
+            tile_index,
+            signer,
+            ipfs_cid,
+            sealed_at: record.sealed_at,
+            added_at: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+/// Find every sealed item under `path`: a single seal, each item of a
+/// directory seal, or any seals nested in subdirectories.
+pub fn collect_entries(path: &Path) -> SealedResult<Vec<RegistryEntry>> {
+    if !path.is_dir() {
+        return Err(SealedError::FileNotFound(path.display().to_string()));
+    }
+    let mut entries = Vec::new();
+    collect_into(path, &mut entries)?;
+    Ok(entries)
+}
+
+fn collect_into(dir: &Path, entries: &mut Vec<RegistryEntry>) -> SealedResult<()> {
+    if dir.join("hashes.json").exists() {
+        entries.push(RegistryEntry::from_sealed_dir(dir)?);
+        return Ok(());
+    }
+    if dir.join(BATCH_MANIFEST_FILE).exists() {
+        let manifest = BatchManifest::load(dir)?;
+        for item in &manifest.items {
+            match RegistryEntry::from_sealed_dir(&dir.join(&item.dir)) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warn!("Skipping batch item {}: {}", item.dir, e),
+            }
+        }
+        return Ok(());
+    }
+
+    let mut subdirs: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .collect();
+    subdirs.sort();
+    for sub in subdirs {
+        collect_into(&sub, entries)?;
+    }
+    Ok(())
+}
+
+/// A registry entry that resembles a queried image.
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryMatch<'a> {
+    pub entry: &'a RegistryEntry,
+    /// `original` or `share`: which sealed artifact matched best.
+    pub matched: &'static str,
+    pub report: SimilarityReport,
+}
+
+/// The persistent registry of sealed works.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Registry {
+    pub entries: Vec<RegistryEntry>,
+}
+
+impl Registry {
+    /// Load the registry; a missing file is an empty registry.
+    pub fn load(path: &Path) -> SealedResult<Self> {
+        load_or_default(path)
+    }
+
+    /// Write atomically, creating the parent directory.
+    pub fn save(&self, path: &Path) -> SealedResult<()> {
+        save_pretty(path, self)
+    }
+
+    /// Add an entry, replacing any existing one for the same directory.
+    /// Returns false if it replaced one.
+    pub fn add(&mut self, mut entry: RegistryEntry) -> bool {
+        match self.entries.iter_mut().find(|e| e.sealed_dir == entry.sealed_dir) {
+            Some(existing) => {
+                entry.id = existing.id.clone();
+                *existing = entry;
+                false
+            }
+            None => {
+                self.entries.push(entry);
+                true
+            }
+        }
+    }
+
+    /// Remove the entry for a sealed directory, or the one whose id starts
+    /// with `target`. Returns how many were removed.
+    pub fn remove(&mut self, target: &str) -> SealedResult<usize> {
+        let before = self.entries.len();
+        if let Ok(dir) = Path::new(target).canonicalize() {
+            self.entries.retain(|e| e.sealed_dir != dir);
+            if self.entries.len() < before {
+                return Ok(before - self.entries.len());
+            }
+        }
+        let matching = self.entries.iter().filter(|e| e.id.starts_with(target)).count();
+        if matching > 1 {
+            return Err(SealedError::InvalidInput(format!(
+                "'{}' matches {} registry entries; give more of the id", target, matching
+            )));
+        }
+        self.entries.retain(|e| !e.id.starts_with(target));
+        Ok(before - self.entries.len())
+    }
+
+    /// Entries resembling `hashes` at `min` confidence or better, best first.
+    pub fn search(&self, hashes: &HashRecord, min: SimilarityConfidence) -> Vec<RegistryMatch<'_>> {
+        RegistryIndex::build(self).search(hashes, min)
+    }
+}
+
+/// What sealing does when the input matches an earlier seal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum PriorSealPolicy {
+    /// Don't look for earlier seals.
+    Off,
+    /// Report matches and seal anyway.
+    #[default]
+    Warn,
+    /// Refuse if the match was sealed by another key (or unsigned).
+    RefuseOtherKey,
+    /// Refuse on any match.
+    Refuse,
+}
+
+/// An earlier seal that is an EXACT or HIGH match of the input.
+#[derive(Debug, Clone, Serialize)]
+pub struct PriorSeal {
+    pub id: String,
+    pub sealed_dir: PathBuf,
+    pub signer: Option<String>,
+    pub confidence: SimilarityConfidence,
+    /// Both seals carry a valid signature by the same key.
+    pub same_signer: bool,
+}
+
+impl Registry {
+    /// Earlier seals of `hashes` at HIGH confidence or better. `signer` is
+    /// the public key (base64) about to seal it.
+    pub fn prior_seals(&self, hashes: &HashRecord, signer: Option<&str>) -> Vec<PriorSeal> {
+        self.search(hashes, SimilarityConfidence::High)
+            .into_iter()
+            .map(|m| PriorSeal {
+                id: m.entry.id.clone(),
+                sealed_dir: m.entry.sealed_dir.clone(),
+                signer: m.entry.signer.clone(),
+                confidence: m.report.confidence,
+                same_signer: signer.is_some() && m.entry.signer.as_deref() == signer,
+            })
+            .collect()
+    }
+}
+
+/// Fail if `policy` forbids sealing over any of `priors`.
+pub fn enforce_prior_seal_policy(policy: PriorSealPolicy, priors: &[PriorSeal]) -> SealedResult<()> {
+    let blocking: Vec<&PriorSeal> = priors
+        .iter()
+        .filter(|p| match policy {
+            PriorSealPolicy::Off | PriorSealPolicy::Warn => false,
+            PriorSealPolicy::RefuseOtherKey => !p.same_signer,
+            PriorSealPolicy::Refuse => true,
+        })
+        .collect();
+    match blocking.first() {
+        None => Ok(()),
+        Some(first) => Err(SealedError::PriorSeal(format!(
+            "{} match of {} (signed by {}){}",
+            first.confidence,
+            first.sealed_dir.display(),
+            first.signer.as_deref().unwrap_or("nobody"),
+            match blocking.len() {
+                1 => String::new(),
+                n => format!(" and {} more", n - 1),
+            }
+        ))),
+    }
+}
+
+/// BK-trees over every entry's perceptual hashes, for nearest-neighbour lookup.
+pub struct RegistryIndex<'a> {
+    registry: &'a Registry,
+    /// One tree per hash kind (aHash, dHash, pHash); items are entry indices.
+    trees: [BkTree; 3],
+}
+
+impl<'a> RegistryIndex<'a> {
+    pub fn build(registry: &'a Registry) -> Self {
+        let mut trees = [BkTree::default(), BkTree::default(), BkTree::default()];
+        for (i, entry) in registry.entries.iter().enumerate() {
+            for record in std::iter::once(&entry.original).chain(entry.share.as_ref()) {
+                for (tree, hash) in trees.iter_mut().zip(perceptual_hashes(record)) {
+                    if let Some(hash) = hash {
+                        tree.insert(hash, i);
+                    }
+                }
+            }
+        }
+        Self { registry, trees }
+    }
+
+    /// Entries resembling `hashes` at `min` confidence or better, best first.
+    pub fn search(&self, hashes: &HashRecord, min: SimilarityConfidence) -> Vec<RegistryMatch<'a>> {
+        let mut candidates = HashSet::new();
+        for (tree, hash) in self.trees.iter().zip(perceptual_hashes(hashes)) {
+            if let Some(hash) = hash {
+                candidates.extend(tree.find(hash, SEARCH_RADIUS).into_iter().map(|(item, _)| item));
+            }
+        }
+        // Exact pixel matches are found even when perceptual hashes are unusable.
+        candidates.extend(
+            self.registry.entries.iter().enumerate()
+                .filter(|(_, e)| e.original.sha256 == hashes.sha256)
+                .map(|(i, _)| i),
+        );
+
+        let mut matches: Vec<RegistryMatch<'a>> = candidates
+            .into_iter()
+            .filter_map(|i| {
+                let entry = &self.registry.entries[i];
+                let vs_original = ("original", compare_hashes(hashes, &entry.original));
+                let best = match &entry.share {
+                    Some(share) => {
+                        let vs_share = ("share", compare_hashes(hashes, share));
+                        if score(&vs_share.1) > score(&vs_original.1) { vs_share } else { vs_original }
+                    }
+                    None => vs_original,
+                };
+                (best.1.confidence.rank() >= min.rank()).then_some(RegistryMatch {
+                    entry,
+                    matched: best.0,
+                    report: best.1,
+                })
+            })
+            .collect();
+        matches.sort_by_key(|m| std::cmp::Reverse(score(&m.report)));
+        matches
+    }
+}
+
+/// Sort key: confidence first, then smaller total Hamming distance.
+fn score(report: &SimilarityReport) -> (u8, std::cmp::Reverse<u32>) {
+    let total = report.ahash_hamming + report.dhash_hamming + report.phash_hamming;
+    (report.confidence.rank(), std::cmp::Reverse(total))
+}
+
+fn perceptual_hashes(record: &HashRecord) -> [Option<u64>; 3] {
+    [&record.ahash, &record.dhash, &record.phash].map(|h| u64::from_str_radix(h, 16).ok())
+}
+
+/// Burkhard-Keller tree over 64-bit hashes under Hamming distance.
+#[derive(Debug, Clone, Default)]
+pub struct BkTree {
+    nodes: Vec<BkNode>,
+}
+
+#[derive(Debug, Clone)]
+struct BkNode {
+    hash: u64,
+    items: Vec<usize>,
+    children: BTreeMap<u32, usize>,
+}
+
+impl BkTree {
+    pub fn insert(&mut self, hash: u64, item: usize) {
+        if self.nodes.is_empty() {
+            self.nodes.push(BkNode { hash, items: vec![item], children: BTreeMap::new() });
+            return;
+        }
+        let mut current = 0;
+        loop {
+            let distance = (self.nodes[current].hash ^ hash).count_ones();
+            if distance == 0 {
+                self.nodes[current].items.push(item);
+                return;
+            }
+            match self.nodes[current].children.get(&distance) {
+                Some(&child) => current = child,
+                None => {
+                    let index = self.nodes.len();
+                    self.nodes.push(BkNode { hash, items: vec![item], children: BTreeMap::new() });
+                    self.nodes[current].children.insert(distance, index);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Items whose hash is within `radius` of `hash`, with their distance.
+    pub fn find(&self, hash: u64, radius: u32) -> Vec<(usize, u32)> {
+        let mut found = Vec::new();
+        let mut stack = if self.nodes.is_empty() { vec![] } else { vec![0] };
+        while let Some(current) = stack.pop() {
+            let node = &self.nodes[current];
+            let distance = (node.hash ^ hash).count_ones();
+            if distance <= radius {
+                found.extend(node.items.iter().map(|&item| (item, distance)));
+            }
+            // Triangle inequality: only children at distance d ± radius can hold matches.
+            let low = distance.saturating_sub(radius);
+            stack.extend(node.children.range(low..=distance + radius).map(|(_, &child)| child));
+        }
+        found
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.iter().map(|n| n.items.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use std::path::Path;
+use tracing::info;
+
+use crate::car::decode_car;
+use crate::cid::{directory_links, BlockStore, Cid};
+use crate::errors::{SealedError, SealedResult};
+use crate::ipfs::IpfsFileEntry;
+
+/// Files pulled out of a directory pin; only `hashes.json` is required.
+pub const RETRIEVED_FILES: &[&str] = &["hashes.json", "signed_record.json", "tile_index.json", "original.png"];
+
+/// Where blocks are fetched from. Nothing fetched is trusted until it has
+/// been hashed against the CID that named it.
+#[derive(Debug, Clone)]
+pub enum FetchSource {
+    /// Trustless HTTP gateway base, e.g. `https://ipfs.io/ipfs`.
+    Gateway(String),
+    /// Kubo RPC API, e.g. `http://127.0.0.1:5001`.
+    Node(String),
+}
+
+impl FetchSource {
+    fn get(&self, cid: &Cid, gateway_format: &str, node_command: &str) -> SealedResult<Vec<u8>> {
+        let client = reqwest::blocking::Client::new();
+        let (request, base) = match self {
+            FetchSource::Gateway(base) => (
+                client
+                    .get(format!("{}/{}", base.trim_end_matches('/'), cid))
+                    .query(&[("format", gateway_format)])
+                    .header("Accept", format!("application/vnd.ipld.{}", gateway_format)),
+                base,
+            ),
+            FetchSource::Node(base) => (
+                client
+                    .post(format!("{}/api/v0/{}", base.trim_end_matches('/'), node_command))
+                    .query(&[("arg", cid.to_string())]),
+                base,
+            ),
+        };
+        let response = request
+            .send()
+            .map_err(|e| SealedError::IpfsError(format!("Failed to fetch {} from {}: {}", cid, base, e)))?;
+        if !response.status().is_success() {
+            return Err(SealedError::IpfsError(format!(
+                "{} returned status {} for {}",
+                base,
+                response.status(),
+                cid
+            )));
+        }
+        Ok(response.bytes()?.to_vec())
+    }
+
+    /// One raw block, checked against its CID.
+    pub fn fetch_block(&self, cid: &Cid) -> SealedResult<Vec<u8>> {
+        let data = self.get(cid, "raw", "block/get")?;
+        if Sha256::digest(&data).as_slice() != cid.digest {
+            return Err(SealedError::VerificationFailed(format!("Block fetched for {} does not hash to it", cid)));
+        }
+        Ok(data)
+    }
+
+    /// The UnixFS file `cid`, rebuilt from a CAR whose blocks are all checked.
+    pub fn fetch_file(&self, cid: &Cid) -> SealedResult<Vec<u8>> {
+        let (root, blocks) = decode_car(&self.get(cid, "car", "dag/export")?)?;
+        if !root.same_content(cid) {
+            return Err(SealedError::VerificationFailed(format!(
+                "CAR fetched for {} has root {}", cid, root
+            )));
+        }
+        BlockStore::new(blocks).read_file(cid)
+    }
+}
+
+/// What `retrieve_sealed_record` pulled down.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetrievedRecord {
+    pub cid: String,
+    /// Whether `cid` named a directory pin rather than `hashes.json` itself.
+    pub directory: bool,
+    pub files: Vec<IpfsFileEntry>,
+}
+
+/// Fetch a sealed record by CID into `dest` so it can be verified like a
+/// sealed directory. `cid` may name `hashes.json` or a directory pin; a
+/// separately pinned signed record can be given as `signed_cid`.
+pub fn retrieve_sealed_record(
+    cid: &str,
+    signed_cid: Option<&str>,
+    source: &FetchSource,
+    dest: &Path,
+) -> SealedResult<RetrievedRecord> {
+    let root: Cid = cid.parse()?;
+    std::fs::create_dir_all(dest)?;
+
+    let links = directory_links(&root, &source.fetch_block(&root)?)?;
+    let directory = links.is_some();
+    let mut wanted: Vec<(String, Cid)> = match links {
+        Some(links) => links
+            .into_iter()
+            .filter(|l| RETRIEVED_FILES.contains(&l.name.as_str()))
+            .map(|l| (l.name, l.cid))
+            .collect(),
+        None => vec![("hashes.json".to_string(), root)],
+    };
+    if !wanted.iter().any(|(name, _)| name == "hashes.json") {
+        return Err(SealedError::FileNotFound(format!("No hashes.json under {}", cid)));
+    }
+    if let Some(signed) = signed_cid {
+        wanted.retain(|(name, _)| name != "signed_record.json");
+        wanted.push(("signed_record.json".to_string(), signed.parse()?));
+    }
+
+    let mut files = Vec::new();
+    for (name, file_cid) in wanted {
+        info!("Fetching {} ({})", name, file_cid);
+        let data = source.fetch_file(&file_cid)?;
+        std::fs::write(dest.join(&name), data)?;
+        files.push(IpfsFileEntry { path: name, cid: file_cid.to_string() });
+    }
+
+    Ok(RetrievedRecord { cid: root.to_string(), directory, files })
+}
@@ -0,0 +1,330 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use serde::{Serialize, Deserialize};
+use tracing::{info, warn};
+
+use crate::batch::item_signer;
+use crate::errors::SealedResult;
+use crate::hashing::{compute_hash_record, SimilarityConfidence};
+use crate::image_processing::open_image_by_content;
+use crate::registry::{Registry, RegistryEntry, RegistryIndex};
+use crate::tile_hashing::{compare_against_tiles, TileMatchResult};
+use crate::trust::read_public_key;
+use crate::verification::{record_signature_status, SealedRecord, SignatureStatus};
+
+/// Options for `scan_suspects`.
+#[derive(Debug, Clone)]
+pub struct ScanConfig {
+    /// Weakest confidence worth reporting.
+    pub min_confidence: SimilarityConfidence,
+    /// Run tile crop detection against every record with a tile index, not
+    /// only those the perceptual index shortlists. Finds tight crops; slow.
+    pub exhaustive_tiles: bool,
+    /// Worker threads; 0 uses every available core.
+    pub jobs: usize,
+    /// Only accept signatures by this key.
+    pub public_key: Option<PathBuf>,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            min_confidence: SimilarityConfidence::Low,
+            exhaustive_tiles: false,
+            jobs: 0,
+            public_key: None,
+        }
+    }
+}
+
+/// One suspect that resembles one sealed record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanMatch {
+    pub suspect: PathBuf,
+    pub sealed_dir: PathBuf,
+    pub confidence: SimilarityConfidence,
+    /// `original`, `share` or `tiles`: what produced the confidence.
+    pub matched: String,
+    pub ahash_hamming: u32,
+    pub dhash_hamming: u32,
+    pub phash_hamming: u32,
+    pub crop_detected: bool,
+    /// Where the suspect sits inside the sealed original, in original pixels.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tile_offset: Option<(i32, i32)>,
+    pub tile_votes: usize,
+    pub signature: SignatureStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+}
+
+/// A suspect that could not be scanned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanFailure {
+    pub suspect: PathBuf,
+    pub error: String,
+}
+
+/// Every match of every suspect, best first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanReport {
+    pub scanned_at: String,
+    pub suspects: usize,
+    pub records: usize,
+    pub matches: Vec<ScanMatch>,
+    pub failures: Vec<ScanFailure>,
+}
+
+impl ScanReport {
+    /// The report as CSV, one row per match.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "rank,suspect,sealed_dir,confidence,matched,ahash,dhash,phash,crop_detected,offset_x,offset_y,tile_votes,signature,signer\n",
+        );
+        for (i, m) in self.matches.iter().enumerate() {
+            let (dx, dy) = match m.tile_offset {
+                Some((dx, dy)) => (dx.to_string(), dy.to_string()),
+                None => (String::new(), String::new()),
+            };
+            let fields = [
+                (i + 1).to_string(),
+                m.suspect.display().to_string(),
+                m.sealed_dir.display().to_string(),
+                m.confidence.to_string(),
+                m.matched.clone(),
+                m.ahash_hamming.to_string(),
+                m.dhash_hamming.to_string(),
+                m.phash_hamming.to_string(),
+                m.crop_detected.to_string(),
+                dx,
+                dy,
+                m.tile_votes.to_string(),
+                m.signature.to_string(),
+                m.signer.clone().unwrap_or_default(),
+            ];
+            let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            out.push_str(&row.join(","));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Check every suspect against every sealed record in `registry`. The
+/// perceptual index shortlists records per suspect; shortlisted records with a
+/// tile index and `original.png` are also checked for crops to get an offset.
+pub fn scan_suspects(suspects: &[PathBuf], registry: &Registry, config: &ScanConfig) -> ScanReport {
+    let index = RegistryIndex::build(registry);
+    let signatures: Vec<SignatureStatus> = registry
+        .entries
+        .iter()
+        .map(|entry| signature_status(entry, config.public_key.as_deref()))
+        .collect();
+
+    let jobs = match config.jobs {
+        0 => std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        n => n,
+    }
+    .min(suspects.len().max(1));
+    info!("Scanning {} suspects against {} records on {} threads", suspects.len(), registry.entries.len(), jobs);
+
+    let next = AtomicUsize::new(0);
+    let matches = Mutex::new(Vec::new());
+    let failures = Mutex::new(Vec::new());
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(suspect) = suspects.get(i) else { break };
+                match scan_one(suspect, registry, &index, &signatures, config) {
+                    Ok(found) => matches.lock().unwrap().extend(found),
+                    Err(e) => {
+                        warn!("Could not scan {}: {}", suspect.display(), e);
+                        failures.lock().unwrap().push(ScanFailure {
+                            suspect: suspect.clone(),
+                            error: e.to_string(),
+                        });
+                    }
+                }
+            });
+        }
+    });
+
+    let mut matches = matches.into_inner().unwrap();
+    matches.sort_by(|a, b| {
+        rank_key(b).cmp(&rank_key(a))
+            .then_with(|| a.suspect.cmp(&b.suspect))
+            .then_with(|| a.sealed_dir.cmp(&b.sealed_dir))
+    });
+    let mut failures = failures.into_inner().unwrap();
+    failures.sort_by(|a, b| a.suspect.cmp(&b.suspect));
+
+    ScanReport {
+        scanned_at: chrono::Utc::now().to_rfc3339(),
+        suspects: suspects.len(),
+        records: registry.entries.len(),
+        matches,
+        failures,
+    }
+}
+
+fn scan_one(
+    suspect: &Path,
+    registry: &Registry,
+    index: &RegistryIndex<'_>,
+    signatures: &[SignatureStatus],
+    config: &ScanConfig,
+) -> SealedResult<Vec<ScanMatch>> {
+    let img = open_image_by_content(suspect)?;
+    let hashes = compute_hash_record(&img)?;
+
+    let mut found = Vec::new();
+    let mut seen = vec![false; registry.entries.len()];
+    for m in index.search(&hashes, SimilarityConfidence::Low) {
+        let i = entry_index(registry, m.entry);
+        seen[i] = true;
+        let mut result = ScanMatch {
+            suspect: suspect.to_path_buf(),
+            sealed_dir: m.entry.sealed_dir.clone(),
+            confidence: m.report.confidence,
+            matched: m.matched.to_string(),
+            ahash_hamming: m.report.ahash_hamming,
+            dhash_hamming: m.report.dhash_hamming,
+            phash_hamming: m.report.phash_hamming,
+            crop_detected: false,
+            tile_offset: None,
+            tile_votes: 0,
+            signature: signatures[i],
+            signer: m.entry.signer.clone(),
+        };
+        if m.report.confidence != SimilarityConfidence::Exact {
+            if let Some(tiles) = tile_match(&img, m.entry) {
+                apply_tiles(&mut result, &tiles);
+            }
+        }
+        found.push(result);
+    }
+
+    if config.exhaustive_tiles {
+        for (i, entry) in registry.entries.iter().enumerate().filter(|(i, _)| !seen[*i]) {
+            let Some(tiles) = tile_match(&img, entry).filter(|t| t.crop_detected) else { continue };
+            let mut result = ScanMatch {
+                suspect: suspect.to_path_buf(),
+                sealed_dir: entry.sealed_dir.clone(),
+                confidence: SimilarityConfidence::Low,
+                matched: "tiles".to_string(),
+                ahash_hamming: 64,
+                dhash_hamming: 64,
+                phash_hamming: 64,
+                crop_detected: false,
+                tile_offset: None,
+                tile_votes: 0,
+                signature: signatures[i],
+                signer: entry.signer.clone(),
+            };
+            apply_tiles(&mut result, &tiles);
+            found.push(result);
+        }
+    }
+
+    found.retain(|m| m.confidence.rank() >= config.min_confidence.rank());
+    Ok(found)
+}
+
+/// Fold a tile comparison into a match, upgrading its confidence when the
+/// refined crop comparison beats the whole-image one.
+fn apply_tiles(result: &mut ScanMatch, tiles: &TileMatchResult) {
+    result.tile_votes = tiles.consistent_votes;
+    if !tiles.crop_detected {
+        return;
+    }
+    result.crop_detected = true;
+    result.tile_offset = tiles.estimated_offset;
+    if let Some(refined) = &tiles.refined_similarity {
+        if refined.confidence.rank() > result.confidence.rank() {
+            result.confidence = refined.confidence;
+            result.matched = "tiles".to_string();
+            result.ahash_hamming = refined.ahash_hamming;
+            result.dhash_hamming = refined.dhash_hamming;
+            result.phash_hamming = refined.phash_hamming;
+        }
+    }
+}
+
+fn tile_match(img: &image::DynamicImage, entry: &RegistryEntry) -> Option<TileMatchResult> {
+    let index = entry.tile_index.as_ref()?;
+    let original_path = entry.sealed_dir.join("original.png");
+    if !original_path.exists() {
+        return None;
+    }
+    match open_image_by_content(&original_path) {
+        Ok(original) => Some(compare_against_tiles(img, &original, index)),
+        Err(e) => {
+            warn!("Could not load {}: {}", original_path.display(), e);
+            None
+        }
+    }
+}
+
+fn entry_index(registry: &Registry, entry: &RegistryEntry) -> usize {
+    registry
+        .entries
+        .iter()
+        .position(|e| std::ptr::eq(e, entry))
+        .expect("match refers to a registry entry")
+}
+
+/// Confidence first, then crops with more votes, then smaller total distance.
+fn rank_key(m: &ScanMatch) -> (u8, bool, std::cmp::Reverse<u32>, usize) {
+    (
+        m.confidence.rank(),
+        m.crop_detected,
+        std::cmp::Reverse(m.ahash_hamming + m.dhash_hamming + m.phash_hamming),
+        m.tile_votes,
+    )
+}
+
+fn signature_status(entry: &RegistryEntry, public_key: Option<&Path>) -> SignatureStatus {
+    let dir = &entry.sealed_dir;
+    if !dir.join("signed_record.json").exists() {
+        // A directory-seal item is covered by the batch signature only if its
+        // hashes.json is still a leaf under the signed Merkle root.
+        let signer = match item_signer(dir) {
+            Ok(signer) => signer,
+            Err(e) => {
+                warn!("Batch signature does not cover {}: {}", dir.display(), e);
+                return SignatureStatus::Invalid;
+            }
+        };
+        return match (signer, public_key) {
+            (None, _) => SignatureStatus::Unsigned,
+            (Some(_), None) => SignatureStatus::Valid,
+            // Any key format `--public-key` takes; another key's valid
+            // signature is still valid, just not trusted.
+            (Some(signer), Some(path)) => match read_public_key(&path.to_string_lossy()) {
+                Ok(trusted) if trusted == signer => SignatureStatus::Trusted,
+                Ok(_) => SignatureStatus::Valid,
+                Err(e) => {
+                    warn!("Could not read public key {}: {}", path.display(), e);
+                    SignatureStatus::Invalid
+                }
+            },
+        };
+    }
+    let record = std::fs::read_to_string(dir.join("hashes.json"))
+        .ok()
+        .and_then(|json| serde_json::from_str::<SealedRecord>(&json).ok());
+    match record.map(|r| record_signature_status(dir, &r, public_key)) {
+        Some(Ok(status)) => status,
+        _ => SignatureStatus::Invalid,
+    }
+}
@@ -7,16 +7,190 @@ use std::path::Path;
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD as BASE64;
 use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
-use aes_gcm::aead::Aead;
+use aes_gcm::aead::{Aead, Payload};
 use argon2::Argon2;
+use sha2::{Digest, Sha256};
 
 use zeroize::Zeroize;
 
+use crate::canonical::{canonicalize_str, to_canonical_json, JCS};
 use crate::errors::{SealedError, SealedResult};
+use crate::key_formats::{parse_public_key, parse_secret_key, write_secret_file};
+use crate::passphrase::PassphraseSource;
 
-/// Magic header for encrypted key files.
+/// Magic header for encrypted key files written before KDF parameters were
+/// recorded (Argon2id with the argon2 crate defaults).
 pub const ENCRYPTED_KEY_MAGIC: &[u8] = b"SEALED_ENC_V1";
 
+/// Magic header for encrypted key files that record their KDF parameters.
+pub const ENCRYPTED_KEY_MAGIC_V2: &[u8] = b"SEALED_ENC_V2";
+
+/// Common prefix of every encrypted key format.
+const ENCRYPTED_KEY_PREFIX: &[u8] = b"SEALED_ENC_V";
+
+/// Upper bounds on parameters read from a key file, so a crafted file cannot
+/// make loading it take unbounded memory or time.
+const MAX_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+const MAX_ITERATIONS: u32 = 64;
+const MAX_PARALLELISM: u32 = 64;
+
+/// Argon2 variant used to derive a key file's encryption key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum KdfAlgorithm {
+    Argon2d,
+    Argon2i,
+    Argon2id,
+}
+
+impl std::fmt::Display for KdfAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            KdfAlgorithm::Argon2d => "argon2d",
+            KdfAlgorithm::Argon2i => "argon2i",
+            KdfAlgorithm::Argon2id => "argon2id",
+        })
+    }
+}
+
+/// Key derivation parameters stored in a `SEALED_ENC_V2` key file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub algorithm: KdfAlgorithm,
+    /// Argon2 version (0x13 is the current one).
+    pub version: u32,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// The argon2 crate defaults, which is also what `SEALED_ENC_V1` used.
+    fn default() -> Self {
+        Self {
+            algorithm: KdfAlgorithm::Argon2id,
+            version: argon2::Version::V0x13 as u32,
+            memory_kib: argon2::Params::DEFAULT_M_COST,
+            iterations: argon2::Params::DEFAULT_T_COST,
+            parallelism: argon2::Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl std::fmt::Display for KdfParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} v{:#x}, {} KiB, {} iterations, parallelism {}",
+            self.algorithm, self.version, self.memory_kib, self.iterations, self.parallelism
+        )
+    }
+}
+
+impl KdfParams {
+    /// Bytes the parameters take in a key file: algorithm (1), then version,
+    /// memory, iterations and parallelism as big-endian u32s.
+    const ENCODED_LEN: usize = 17;
+
+    /// Check the parameters are ones Argon2 accepts and within the limits
+    /// sealed will load.
+    pub fn validate(&self) -> SealedResult<()> {
+        if self.memory_kib > MAX_MEMORY_KIB || self.iterations > MAX_ITERATIONS || self.parallelism > MAX_PARALLELISM {
+            return Err(SealedError::KeyError(format!(
+                "KDF parameters exceed the limits ({} KiB, {} iterations, parallelism {}): {}",
+                MAX_MEMORY_KIB, MAX_ITERATIONS, MAX_PARALLELISM, self
+            )));
+        }
+        self.argon2().map(|_| ())
+    }
+
+    fn argon2(&self) -> SealedResult<Argon2<'static>> {
+        let algorithm = match self.algorithm {
+            KdfAlgorithm::Argon2d => argon2::Algorithm::Argon2d,
+            KdfAlgorithm::Argon2i => argon2::Algorithm::Argon2i,
+            KdfAlgorithm::Argon2id => argon2::Algorithm::Argon2id,
+        };
+        let version = argon2::Version::try_from(self.version)
+            .map_err(|_| SealedError::KeyError(format!("Unsupported Argon2 version {:#x}", self.version)))?;
+        let params = argon2::Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| SealedError::KeyError(format!("Invalid KDF parameters ({}): {}", self, e)))?;
+        Ok(Argon2::new(algorithm, version, params))
+    }
+
+    fn to_bytes(self) -> [u8; Self::ENCODED_LEN] {
+        let mut out = [0u8; Self::ENCODED_LEN];
+        // Argon2's own type numbering: 0 = d, 1 = i, 2 = id.
+        out[0] = match self.algorithm {
+            KdfAlgorithm::Argon2d => 0,
+            KdfAlgorithm::Argon2i => 1,
+            KdfAlgorithm::Argon2id => 2,
+        };
+        for (i, value) in [self.version, self.memory_kib, self.iterations, self.parallelism].into_iter().enumerate() {
+            out[1 + 4 * i..5 + 4 * i].copy_from_slice(&value.to_be_bytes());
+        }
+        out
+    }
+
+    fn from_bytes(bytes: &[u8]) -> SealedResult<Self> {
+        let word = |i: usize| u32::from_be_bytes(bytes[1 + 4 * i..5 + 4 * i].try_into().expect("4 bytes"));
+        let params = Self {
+            algorithm: match bytes[0] {
+                0 => KdfAlgorithm::Argon2d,
+                1 => KdfAlgorithm::Argon2i,
+                2 => KdfAlgorithm::Argon2id,
+                other => return Err(SealedError::KeyError(format!("Unknown KDF algorithm {}", other))),
+            },
+            version: word(0),
+            memory_kib: word(1),
+            iterations: word(2),
+            parallelism: word(3),
+        };
+        params.validate()?;
+        Ok(params)
+    }
+}
+
+/// Whether `data` is a sealed password-encrypted key file of any version.
+pub fn is_encrypted_key(data: &[u8]) -> bool {
+    data.starts_with(ENCRYPTED_KEY_PREFIX)
+}
+
+/// Format version and KDF parameters of an encrypted key file. Anything that
+/// is not exactly a known version with the expected length is refused.
+pub fn encrypted_key_format(data: &[u8]) -> SealedResult<(u32, KdfParams)> {
+    // Salt, nonce, then the 32-byte key and its 16-byte tag.
+    const BODY_LEN: usize = 16 + 12 + 32 + 16;
+    let invalid = |why: &str| SealedError::KeyError(format!("Invalid encrypted key file: {}", why));
+    if data.starts_with(ENCRYPTED_KEY_MAGIC_V2) {
+        let rest = &data[ENCRYPTED_KEY_MAGIC_V2.len()..];
+        if rest.len() != KdfParams::ENCODED_LEN + BODY_LEN {
+            return Err(invalid("wrong length"));
+        }
+        Ok((2, KdfParams::from_bytes(&rest[..KdfParams::ENCODED_LEN])?))
+    } else if data.starts_with(ENCRYPTED_KEY_MAGIC) {
+        if data.len() != ENCRYPTED_KEY_MAGIC.len() + BODY_LEN {
+            return Err(invalid("wrong length"));
+        }
+        Ok((1, KdfParams::default()))
+    } else if is_encrypted_key(data) {
+        Err(invalid("unsupported format version; it may need a newer sealed"))
+    } else {
+        Err(invalid("missing SEALED_ENC header"))
+    }
+}
+
+fn derive_cipher(password: &str, salt: &[u8], params: &KdfParams) -> SealedResult<Aes256Gcm> {
+    let mut derived_key = [0u8; 32];
+    params
+        .argon2()?
+        .hash_password_into(password.as_bytes(), salt, &mut derived_key)
+        .map_err(|e| SealedError::KeyError(format!("Key derivation failed: {}", e)))?;
+    let cipher = Aes256Gcm::new_from_slice(&derived_key)
+        .map_err(|e| SealedError::KeyError(format!("Cipher init failed: {}", e)));
+    derived_key.zeroize();
+    cipher
+}
+
 /// Ed25519 signing keypair.
 pub struct SealedKeyPair {
     signing_key: SigningKey,
@@ -31,6 +205,23 @@ impl Drop for SealedKeyPair {
     }
 }
 
+/// Stable identifier for an Ed25519 public key: the first 16 bytes of the
+/// SHA-256 of its raw 32 bytes, as hex.
+pub fn key_id(public_key: &[u8; 32]) -> String {
+    hex::encode(&Sha256::digest(public_key)[..16])
+}
+
+/// [`key_id`] of a base64 public key.
+pub fn key_id_from_base64(public_key: &str) -> SealedResult<String> {
+    let bytes = BASE64
+        .decode(public_key)
+        .map_err(|e| SealedError::KeyError(format!("Invalid public key encoding: {}", e)))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| SealedError::KeyError("Invalid public key: expected 32 bytes".to_string()))?;
+    Ok(key_id(&bytes))
+}
+
 /// Signed envelope: payload + signature + public key.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignedEnvelope {
@@ -38,6 +229,28 @@ pub struct SignedEnvelope {
     pub signature: String,
     pub public_key: String,
     pub algorithm: String,
+    /// `jcs` when the signature is over the RFC 8785 canonical form of the
+    /// payload, so re-indenting it does not break the signature. Absent for
+    /// signatures over the exact payload string.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonicalization: Option<String>,
+    /// [`key_id`] of the signing key. Absent in envelopes made before key
+    /// IDs existed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    /// Further independent signatures over the same signed bytes, e.g. by an
+    /// agency and a client attesting to a photographer's seal.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cosignatures: Vec<Cosignature>,
+}
+
+/// One co-signer's signature in a [`SignedEnvelope`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cosignature {
+    pub signature: String,
+    pub public_key: String,
+    pub key_id: String,
+    pub algorithm: String,
 }
 
 impl SealedKeyPair {
@@ -47,102 +260,93 @@ impl SealedKeyPair {
         Self { signing_key }
     }
 
-    /// Load from a 32-byte raw secret key file.
+    pub(crate) fn from_signing_key(signing_key: SigningKey) -> Self {
+        Self { signing_key }
+    }
+
+    pub(crate) fn signing_key(&self) -> &SigningKey {
+        &self.signing_key
+    }
+
+    /// Load an unencrypted secret key file: 32 raw bytes, PKCS#8 PEM, an
+    /// OpenSSH private key or a JWK (see [`crate::key_formats`]).
     pub fn load(path: &Path) -> SealedResult<Self> {
-        let bytes = fs::read(path).map_err(|_| {
+        let mut bytes = fs::read(path).map_err(|_| {
             SealedError::KeyError(format!("Failed to read key file: {}", path.display()))
         })?;
-        if bytes.len() != 32 {
-            return Err(SealedError::KeyError(
-                "Invalid key file: expected 32 bytes".to_string(),
-            ));
+        let parsed = parse_secret_key(&bytes, None);
+        bytes.zeroize();
+        Ok(parsed?.0)
+    }
+
+    /// Load a secret key file, reading the passphrase from `passphrase` only
+    /// if the file is password-encrypted.
+    pub fn load_with_passphrase(path: &Path, passphrase: &PassphraseSource) -> SealedResult<Self> {
+        let data = fs::read(path).map_err(|_| {
+            SealedError::KeyError(format!("Failed to read key file: {}", path.display()))
+        })?;
+        if !is_encrypted_key(&data) {
+            return Self::load(path);
         }
-        let mut key_bytes = [0u8; 32];
-        key_bytes.copy_from_slice(&bytes);
-        let signing_key = SigningKey::from_bytes(&key_bytes);
-        key_bytes.zeroize();
-        Ok(Self { signing_key })
+        let password = passphrase.read("Enter key password: ")?;
+        Self::load_encrypted(path, &password)
     }
 
     /// Save the secret key to a file (unencrypted, 0600 on Unix).
     pub fn save_secret(&self, path: &Path) -> SealedResult<()> {
-        fs::write(path, self.signing_key.to_bytes())?;
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
-        }
-        Ok(())
+        write_secret_file(path, &self.signing_key.to_bytes())
     }
 
-    /// Save the secret key encrypted (AES-256-GCM + Argon2).
+    /// Save the secret key encrypted (AES-256-GCM + Argon2id with the
+    /// default [`KdfParams`]).
     pub fn save_secret_encrypted(&self, path: &Path, password: &str) -> SealedResult<()> {
+        self.save_secret_encrypted_with(path, password, &KdfParams::default())
+    }
+
+    /// Save the secret key encrypted in the `SEALED_ENC_V2` format, which
+    /// records the Argon2 parameters used to derive the key.
+    pub fn save_secret_encrypted_with(&self, path: &Path, password: &str, params: &KdfParams) -> SealedResult<()> {
         let mut salt = [0u8; 16];
         OsRng.fill_bytes(&mut salt);
-
-        let mut derived_key = [0u8; 32];
-        Argon2::default()
-            .hash_password_into(password.as_bytes(), &salt, &mut derived_key)
-            .map_err(|e| SealedError::KeyError(format!("Key derivation failed: {}", e)))?;
-
-        let cipher = Aes256Gcm::new_from_slice(&derived_key)
-            .map_err(|e| SealedError::KeyError(format!("Cipher init failed: {}", e)))?;
-        derived_key.zeroize();
-
         let mut nonce_bytes = [0u8; 12];
         OsRng.fill_bytes(&mut nonce_bytes);
-        let nonce = Nonce::from_slice(&nonce_bytes);
 
+        let mut header = ENCRYPTED_KEY_MAGIC_V2.to_vec();
+        header.extend_from_slice(&params.to_bytes());
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&nonce_bytes);
+
+        let cipher = derive_cipher(password, &salt, params)?;
+        // The header is authenticated, so its parameters cannot be lowered.
         let ciphertext = cipher
-            .encrypt(nonce, self.signing_key.to_bytes().as_ref())
+            .encrypt(
+                Nonce::from_slice(&nonce_bytes),
+                Payload { msg: self.signing_key.to_bytes().as_ref(), aad: &header },
+            )
             .map_err(|e| SealedError::KeyError(format!("Encryption failed: {}", e)))?;
 
-        let mut output = Vec::with_capacity(ENCRYPTED_KEY_MAGIC.len() + 16 + 12 + ciphertext.len());
-        output.extend_from_slice(ENCRYPTED_KEY_MAGIC);
-        output.extend_from_slice(&salt);
-        output.extend_from_slice(&nonce_bytes);
-        output.extend_from_slice(&ciphertext);
-
-        fs::write(path, &output)?;
-        Ok(())
+        header.extend_from_slice(&ciphertext);
+        write_secret_file(path, &header)
     }
 
-    /// Load from a password-encrypted file.
+    /// Load from a password-encrypted file (`SEALED_ENC_V1` or `SEALED_ENC_V2`).
     pub fn load_encrypted(path: &Path, password: &str) -> SealedResult<Self> {
         let data = fs::read(path).map_err(|_| {
             SealedError::KeyError(format!("Failed to read key file: {}", path.display()))
         })?;
-
-        let magic_len = ENCRYPTED_KEY_MAGIC.len();
-
-        let payload = if data.starts_with(ENCRYPTED_KEY_MAGIC) {
-            &data[magic_len..]
+        let (version, params) = encrypted_key_format(&data)?;
+        let (cipher, nonce, ciphertext, aad) = if version == 1 {
+            let payload = &data[ENCRYPTED_KEY_MAGIC.len()..];
+            (derive_cipher(password, &payload[..16], &params)?, &payload[16..28], &payload[28..], &[][..])
         } else {
-            &data
+            let header_len = ENCRYPTED_KEY_MAGIC_V2.len() + KdfParams::ENCODED_LEN + 28;
+            let (header, ciphertext) = data.split_at(header_len);
+            let salt = &header[header_len - 28..header_len - 12];
+            (derive_cipher(password, salt, &params)?, &header[header_len - 12..], ciphertext, header)
         };
 
-        if payload.len() < 76 {
-            return Err(SealedError::KeyError(
-                "Invalid encrypted key file: too short".to_string(),
-            ));
-        }
-
-        let salt = &payload[..16];
-        let nonce_bytes = &payload[16..28];
-        let ciphertext = &payload[28..];
-
-        let mut derived_key = [0u8; 32];
-        Argon2::default()
-            .hash_password_into(password.as_bytes(), salt, &mut derived_key)
-            .map_err(|e| SealedError::KeyError(format!("Key derivation failed: {}", e)))?;
-
-        let cipher = Aes256Gcm::new_from_slice(&derived_key)
-            .map_err(|e| SealedError::KeyError(format!("Cipher init failed: {}", e)))?;
-        derived_key.zeroize();
-
-        let nonce = Nonce::from_slice(nonce_bytes);
         let mut plaintext = cipher
-            .decrypt(nonce, ciphertext)
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
             .map_err(|_| SealedError::KeyError("Decryption failed: wrong password".to_string()))?;
 
         if plaintext.len() != 32 {
@@ -167,13 +371,22 @@ impl SealedKeyPair {
         Ok(())
     }
 
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.signing_key.verifying_key()
+    }
+
     /// Public key as base64.
     pub fn public_key_base64(&self) -> String {
         let verifying_key = self.signing_key.verifying_key();
         BASE64.encode(verifying_key.to_bytes())
     }
 
-    /// Sign a payload string.
+    /// Stable identifier of the public key; see [`key_id`].
+    pub fn key_id(&self) -> String {
+        key_id(&self.signing_key.verifying_key().to_bytes())
+    }
+
+    /// Sign a payload string byte for byte.
     pub fn sign(&self, payload: &str) -> SignedEnvelope {
         let signature = self.signing_key.sign(payload.as_bytes());
         SignedEnvelope {
@@ -181,57 +394,133 @@ impl SealedKeyPair {
             signature: BASE64.encode(signature.to_bytes()),
             public_key: self.public_key_base64(),
             algorithm: "Ed25519".to_string(),
+            canonicalization: None,
+            key_id: Some(self.key_id()),
+            cosignatures: Vec::new(),
         }
     }
+
+    /// Sign the RFC 8785 canonical JSON form of `value`.
+    pub fn sign_canonical<T: Serialize + ?Sized>(&self, value: &T) -> SealedResult<SignedEnvelope> {
+        let payload = to_canonical_json(value)?;
+        let signature = self.signing_key.sign(payload.as_bytes());
+        Ok(SignedEnvelope {
+            payload,
+            signature: BASE64.encode(signature.to_bytes()),
+            public_key: self.public_key_base64(),
+            algorithm: "Ed25519".to_string(),
+            canonicalization: Some(JCS.to_string()),
+            key_id: Some(self.key_id()),
+            cosignatures: Vec::new(),
+        })
+    }
 }
 
 impl SignedEnvelope {
     /// Verify using the embedded public key.
     pub fn verify(&self) -> SealedResult<()> {
-        let pub_bytes = BASE64.decode(&self.public_key).map_err(|e| {
-            SealedError::KeyError(format!("Invalid public key encoding: {}", e))
-        })?;
-        if pub_bytes.len() != 32 {
-            return Err(SealedError::KeyError(
-                "Invalid public key: expected 32 bytes".to_string(),
-            ));
-        }
-        let mut key_bytes = [0u8; 32];
-        key_bytes.copy_from_slice(&pub_bytes);
-        let verifying_key = VerifyingKey::from_bytes(&key_bytes)?;
-        self.verify_with_verifying_key(&verifying_key)
+        let verifying_key = decode_public_key(&self.public_key)?;
+        verify_signature(&verifying_key, self.key_id.as_deref(), &self.signature, &self.signed_bytes()?)
     }
 
     /// Verify against a known public key file.
     pub fn verify_with_key(&self, public_key_path: &Path) -> SealedResult<()> {
-        let bytes = fs::read(public_key_path).map_err(|_| {
-            SealedError::KeyError(format!("Failed to read public key: {}", public_key_path.display()))
-        })?;
-        if bytes.len() != 32 {
-            return Err(SealedError::KeyError("Invalid public key file".to_string()));
+        let verifying_key = read_public_key_file(public_key_path)?;
+        verify_signature(&verifying_key, self.key_id.as_deref(), &self.signature, &self.signed_bytes()?)
+    }
+
+    /// Verify the co-signature made by the key in `public_key_path`.
+    pub fn verify_cosignature_with_key(&self, public_key_path: &Path) -> SealedResult<()> {
+        let verifying_key = read_public_key_file(public_key_path)?;
+        let public_key = BASE64.encode(verifying_key.to_bytes());
+        let cosignature = self
+            .cosignatures
+            .iter()
+            .find(|c| c.public_key == public_key)
+            .ok_or_else(|| SealedError::VerificationFailed("No co-signature by this key".to_string()))?;
+        verify_signature(&verifying_key, Some(&cosignature.key_id), &cosignature.signature, &self.signed_bytes()?)
+    }
+
+    /// Check each co-signature on its own, in order.
+    pub fn verify_cosignatures(&self) -> Vec<SealedResult<()>> {
+        self.cosignatures
+            .iter()
+            .map(|c| {
+                verify_signature(&decode_public_key(&c.public_key)?, Some(&c.key_id), &c.signature, &self.signed_bytes()?)
+            })
+            .collect()
+    }
+
+    /// Add `key`'s signature over the same signed bytes. The envelope's own
+    /// signature must verify first, and each key signs at most once.
+    pub fn cosign(&mut self, key: &SealedKeyPair) -> SealedResult<&Cosignature> {
+        self.verify()?;
+        let public_key = key.public_key_base64();
+        if self.public_key == public_key || self.cosignatures.iter().any(|c| c.public_key == public_key) {
+            return Err(SealedError::InvalidInput(format!("Key {} has already signed this record", key.key_id())));
         }
-        let mut key_bytes = [0u8; 32];
-        key_bytes.copy_from_slice(&bytes);
-        let verifying_key = VerifyingKey::from_bytes(&key_bytes)?;
-        self.verify_with_verifying_key(&verifying_key)
+        let signature = key.signing_key.sign(self.signed_bytes()?.as_bytes());
+        self.cosignatures.push(Cosignature {
+            signature: BASE64.encode(signature.to_bytes()),
+            public_key,
+            key_id: key.key_id(),
+            algorithm: "Ed25519".to_string(),
+        });
+        Ok(self.cosignatures.last().expect("just pushed"))
     }
 
-    /// Check signature against a verifying key.
-    fn verify_with_verifying_key(&self, verifying_key: &VerifyingKey) -> SealedResult<()> {
-        let sig_bytes = BASE64.decode(&self.signature).map_err(|e| {
-            SealedError::KeyError(format!("Invalid signature encoding: {}", e))
-        })?;
-        if sig_bytes.len() != 64 {
-            return Err(SealedError::KeyError(
-                "Invalid signature: expected 64 bytes".to_string(),
-            ));
+    /// The bytes every signature in the envelope is over.
+    fn signed_bytes(&self) -> SealedResult<String> {
+        match self.canonicalization.as_deref() {
+            None => Ok(self.payload.clone()),
+            Some(JCS) => canonicalize_str(&self.payload),
+            Some(other) => Err(SealedError::VerificationFailed(format!("Unknown canonicalization '{}'", other))),
         }
-        let mut sig_arr = [0u8; 64];
-        sig_arr.copy_from_slice(&sig_bytes);
-        let signature = Signature::from_bytes(&sig_arr);
+    }
+}
+
+fn decode_public_key(public_key: &str) -> SealedResult<VerifyingKey> {
+    let pub_bytes = BASE64.decode(public_key).map_err(|e| {
+        SealedError::KeyError(format!("Invalid public key encoding: {}", e))
+    })?;
+    let key_bytes: [u8; 32] = pub_bytes.try_into().map_err(|_| {
+        SealedError::KeyError("Invalid public key: expected 32 bytes".to_string())
+    })?;
+    Ok(VerifyingKey::from_bytes(&key_bytes)?)
+}
 
-        verifying_key
-            .verify(self.payload.as_bytes(), &signature)
-            .map_err(|e| SealedError::VerificationFailed(format!("Signature invalid: {}", e)))
+/// A public key file in any format [`parse_public_key`] reads.
+fn read_public_key_file(public_key_path: &Path) -> SealedResult<VerifyingKey> {
+    let bytes = fs::read(public_key_path).map_err(|_| {
+        SealedError::KeyError(format!("Failed to read public key: {}", public_key_path.display()))
+    })?;
+    Ok(parse_public_key(&bytes, None)?.0)
+}
+
+/// Check one base64 signature over `signed_bytes`, and that a claimed key ID
+/// belongs to the verifying key.
+fn verify_signature(
+    verifying_key: &VerifyingKey,
+    claimed_key_id: Option<&str>,
+    signature: &str,
+    signed_bytes: &str,
+) -> SealedResult<()> {
+    if let Some(id) = claimed_key_id {
+        if id != key_id(&verifying_key.to_bytes()) {
+            return Err(SealedError::VerificationFailed(format!(
+                "Envelope key ID {} does not match the verifying key",
+                id
+            )));
+        }
     }
+    let sig_bytes = BASE64.decode(signature).map_err(|e| {
+        SealedError::KeyError(format!("Invalid signature encoding: {}", e))
+    })?;
+    let sig_arr: [u8; 64] = sig_bytes.try_into().map_err(|_| {
+        SealedError::KeyError("Invalid signature: expected 64 bytes".to_string())
+    })?;
+    let signature = Signature::from_bytes(&sig_arr);
+    verifying_key
+        .verify(signed_bytes.as_bytes(), &signature)
+        .map_err(|e| SealedError::VerificationFailed(format!("Signature invalid: {}", e)))
 }
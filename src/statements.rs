@@ -0,0 +1,111 @@
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+use crate::errors::{SealedError, SealedResult};
+use crate::signing::{key_id_from_base64, SealedKeyPair, SignedEnvelope};
+use crate::trust::{normalize_public_key, normalize_time};
+
+/// A signed statement a publisher makes about one of their own keys,
+/// published as a [`SignedEnvelope`] over its canonical JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "statement", rename_all = "kebab-case")]
+pub enum KeyStatement {
+    /// The old key hands over to the new one from `effective_at`. Signed by
+    /// the old key.
+    Rotation {
+        old_key_id: String,
+        old_public_key: String,
+        new_key_id: String,
+        new_public_key: String,
+        effective_at: String,
+        issued_at: String,
+    },
+    /// Seals made with the key from `effective_at` on are not to be trusted.
+    /// Signed by the revoked key itself.
+    Revocation {
+        key_id: String,
+        public_key: String,
+        effective_at: String,
+        issued_at: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+}
+
+impl KeyStatement {
+    /// The key that must have signed the statement.
+    pub fn signer_public_key(&self) -> &str {
+        match self {
+            KeyStatement::Rotation { old_public_key, .. } => old_public_key,
+            KeyStatement::Revocation { public_key, .. } => public_key,
+        }
+    }
+
+    pub fn effective_at(&self) -> &str {
+        match self {
+            KeyStatement::Rotation { effective_at, .. } | KeyStatement::Revocation { effective_at, .. } => effective_at,
+        }
+    }
+}
+
+/// Sign, with `old`, a statement handing over to `new_public_key` (base64)
+/// from `effective_at` (RFC 3339 or YYYY-MM-DD).
+pub fn rotation_statement(old: &SealedKeyPair, new_public_key: &str, effective_at: &str) -> SealedResult<SignedEnvelope> {
+    let new_public_key = normalize_public_key(new_public_key)?;
+    if new_public_key == old.public_key_base64() {
+        return Err(SealedError::InvalidInput("A key cannot be rotated to itself".to_string()));
+    }
+    old.sign_canonical(&KeyStatement::Rotation {
+        old_key_id: old.key_id(),
+        old_public_key: old.public_key_base64(),
+        new_key_id: key_id_from_base64(&new_public_key)?,
+        new_public_key,
+        effective_at: normalize_time(effective_at, false)?,
+        issued_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Sign, with `key`, a statement revoking it from `effective_at` (RFC 3339 or
+/// YYYY-MM-DD).
+pub fn revocation_statement(key: &SealedKeyPair, effective_at: &str, reason: Option<&str>) -> SealedResult<SignedEnvelope> {
+    key.sign_canonical(&KeyStatement::Revocation {
+        key_id: key.key_id(),
+        public_key: key.public_key_base64(),
+        effective_at: normalize_time(effective_at, false)?,
+        issued_at: chrono::Utc::now().to_rfc3339(),
+        reason: reason.map(str::to_string),
+    })
+}
+
+/// Check the signature on a statement and that it was made by the key it
+/// speaks for, with key IDs that match their keys.
+pub fn verify_statement(envelope: &SignedEnvelope) -> SealedResult<KeyStatement> {
+    envelope.verify()?;
+    let statement: KeyStatement = serde_json::from_str(&envelope.payload)
+        .map_err(|e| SealedError::VerificationFailed(format!("Not a key statement: {}", e)))?;
+    if envelope.public_key != statement.signer_public_key() {
+        return Err(SealedError::VerificationFailed(
+            "Key statement is not signed by the key it speaks for".to_string(),
+        ));
+    }
+    let ids = match &statement {
+        KeyStatement::Rotation { old_key_id, old_public_key, new_key_id, new_public_key, .. } => {
+            vec![(old_key_id, old_public_key), (new_key_id, new_public_key)]
+        }
+        KeyStatement::Revocation { key_id, public_key, .. } => vec![(key_id, public_key)],
+    };
+    for (id, public_key) in ids {
+        if *id != key_id_from_base64(public_key)? {
+            return Err(SealedError::VerificationFailed(format!("Key ID {} does not match its public key", id)));
+        }
+    }
+    normalize_time(statement.effective_at(), false)?;
+    Ok(statement)
+}
+
+/// Read a statement file and verify it.
+pub fn read_statement(path: &Path) -> SealedResult<KeyStatement> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|_| SealedError::FileNotFound(path.display().to_string()))?;
+    verify_statement(&serde_json::from_str(&json)?)
+}
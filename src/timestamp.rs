@@ -1,17 +1,53 @@
 use std::path::Path;
+use rand::rngs::OsRng;
+use rand::RngCore;
 use serde::{Serialize, Deserialize};
 use tracing::info;
 
 use crate::errors::{SealedError, SealedResult};
+use crate::ots::{DetachedTimestampFile, Op, Timestamp};
+use crate::tsa::{TimeStampReq, TimeStampToken};
 
-/// OpenTimestamps proof record.
+/// File name of the RFC 3161 timestamp response saved beside `timestamp.ots`.
+pub const TSR_FILE: &str = "timestamp.tsr";
+
+/// Timestamp proof record (OpenTimestamps and/or RFC 3161).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimestampRecord {
     pub hash: String,
     pub calendars: Vec<String>,
     pub ots_file: String,
+    /// OpenTimestamps status: "pending", "confirmed", or "none" for TSA-only seals.
     pub status: String,
     pub submitted_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tsa: Option<TsaRecord>,
+}
+
+/// RFC 3161 token details, as reported by the TSA.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TsaRecord {
+    pub url: String,
+    pub tsr_file: String,
+    pub gen_time: String,
+    pub serial_number: String,
+    pub policy: String,
+}
+
+/// RFC 3161 Time-Stamp Authority endpoint.
+#[derive(Debug, Clone)]
+pub struct TsaConfig {
+    pub url: String,
+    pub timeout_secs: u64,
+}
+
+impl TsaConfig {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            timeout_secs: 15,
+        }
+    }
 }
 
 /// OpenTimestamps calendar servers.
@@ -20,30 +56,71 @@ const OTS_CALENDARS: &[&str] = &[
     "https://b.pool.opentimestamps.org",
 ];
 
-/// OTS proof file magic header.
-const OTS_MAGIC: &[u8] = b"\x00OpenTimestamps\x00\x00Proof\x00\xbf\x89\xe2\xe8\x84\xe8\x92\x94";
+/// Calendar hosts whose pending attestations we will contact during upgrade,
+/// in addition to the configured calendars. Aggregators hand out these URIs.
+const OTS_UPGRADE_WHITELIST: &[&str] = &[
+    ".calendar.opentimestamps.org",
+    ".pool.opentimestamps.org",
+    ".calendar.eternitywall.com",
+    ".calendar.catallaxy.com",
+];
 
-/// OTS v1 version byte.
-const OTS_VERSION: u8 = 0x01;
+/// Calendar servers used for submission and upgrade.
+#[derive(Debug, Clone)]
+pub struct CalendarConfig {
+    pub calendars: Vec<String>,
+    /// Minimum calendars that must accept a submission.
+    pub min_calendars: usize,
+    pub timeout_secs: u64,
+}
 
-/// OTS SHA-256 tag.
-const OTS_TAG_SHA256: u8 = 0x08;
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            calendars: OTS_CALENDARS.iter().map(|c| c.to_string()).collect(),
+            min_calendars: 1,
+            timeout_secs: 15,
+        }
+    }
+}
 
-/// Build a .ots proof file from a SHA-256 hash and calendar response.
-fn build_ots_proof(hash_bytes: &[u8], _calendar_url: &str, calendar_response: &[u8]) -> Vec<u8> {
-    let mut proof = Vec::with_capacity(128 + calendar_response.len());
+impl CalendarConfig {
+    /// Whether a pending attestation URI may be contacted for an upgrade.
+    fn allows(&self, uri: &str) -> bool {
+        let uri = uri.trim_end_matches('/');
+        if self.calendars.iter().any(|c| c.trim_end_matches('/') == uri) {
+            return true;
+        }
+        match uri.strip_prefix("https://") {
+            Some(host) if !host.contains('/') => {
+                OTS_UPGRADE_WHITELIST.iter().any(|suffix| host.ends_with(suffix))
+            }
+            _ => false,
+        }
+    }
 
-    proof.extend_from_slice(OTS_MAGIC);
-    proof.push(OTS_VERSION);
-    proof.push(OTS_TAG_SHA256);
-    proof.extend_from_slice(hash_bytes);
-    proof.extend_from_slice(calendar_response);
+    fn client(&self) -> SealedResult<reqwest::blocking::Client> {
+        reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(self.timeout_secs))
+            .build()
+            .map_err(|e| SealedError::TimestampError(format!("HTTP client error: {}", e)))
+    }
+}
 
-    proof
+/// Submit a SHA-256 hash to the default OpenTimestamps calendars.
+/// Returns the serialized .ots proof and the calendars that accepted it.
+pub fn submit_to_opentimestamps(sha256_hex: &str) -> SealedResult<(Vec<u8>, Vec<String>)> {
+    submit_to_calendars(sha256_hex, &CalendarConfig::default())
 }
 
-/// Submit a SHA-256 hash to OpenTimestamps. Returns a .ots proof.
-pub fn submit_to_opentimestamps(sha256_hex: &str) -> SealedResult<(Vec<u8>, String)> {
+/// Submit a SHA-256 hash to each configured calendar and merge the answers.
+///
+/// The proof commits to `sha256(digest || nonce)` rather than the digest itself,
+/// so calendars never learn the sealed hash.
+pub fn submit_to_calendars(
+    sha256_hex: &str,
+    config: &CalendarConfig,
+) -> SealedResult<(Vec<u8>, Vec<String>)> {
     let hash_bytes = hex::decode(sha256_hex).map_err(|e| {
         SealedError::InvalidInput(format!("Invalid SHA-256 hex: {}", e))
     })?;
@@ -54,14 +131,18 @@ pub fn submit_to_opentimestamps(sha256_hex: &str) -> SealedResult<(Vec<u8>, Stri
         ));
     }
 
-    let client = reqwest::blocking::Client::builder()
-        .timeout(std::time::Duration::from_secs(15))
-        .build()
-        .map_err(|e| SealedError::TimestampError(format!("HTTP client error: {}", e)))?;
+    let mut nonce = [0u8; 16];
+    OsRng.fill_bytes(&mut nonce);
+    let nonce_op = Op::Append(nonce.to_vec());
+    let commitment = Op::Sha256.apply(&nonce_op.apply(&hash_bytes));
+
+    let client = config.client()?;
 
+    let mut calendar_stamp = Timestamp::default();
+    let mut accepted = Vec::new();
     let mut last_error = String::new();
-    for calendar in OTS_CALENDARS {
-        let url = format!("{}/digest", calendar);
+    for calendar in &config.calendars {
+        let url = format!("{}/digest", calendar.trim_end_matches('/'));
         info!("Submitting hash to OpenTimestamps calendar: {}", calendar);
 
         match client
@@ -69,30 +150,33 @@ pub fn submit_to_opentimestamps(sha256_hex: &str) -> SealedResult<(Vec<u8>, Stri
             .header("Content-Type", "application/octet-stream")
             .header("User-Agent", "sealed-ch/2.0")
             .header("Accept", "application/vnd.opentimestamps.v1")
-            .body(hash_bytes.clone())
+            .body(commitment.clone())
             .send()
         {
-            Ok(response) => {
-                if response.status().is_success() {
-                    let calendar_response = response.bytes().map_err(|e| {
-                        SealedError::TimestampError(format!("Failed to read OTS response: {}", e))
-                    })?;
-                    info!(
-                        "OpenTimestamps attestation received from {} ({} bytes)",
-                        calendar,
-                        calendar_response.len()
-                    );
-                    let ots_proof = build_ots_proof(&hash_bytes, calendar, &calendar_response);
-                    return Ok((ots_proof, calendar.to_string()));
-                } else {
-                    last_error = format!(
-                        "{} returned status {}",
-                        calendar,
-                        response.status()
-                    );
-                    info!("Calendar {} failed: {}", calendar, last_error);
+            Ok(response) if response.status().is_success() => {
+                let body = response.bytes().map_err(|e| {
+                    SealedError::TimestampError(format!("Failed to read OTS response: {}", e))
+                })?;
+                match Timestamp::parse(&body) {
+                    Ok(stamp) => {
+                        info!(
+                            "OpenTimestamps attestation received from {} ({} bytes)",
+                            calendar,
+                            body.len()
+                        );
+                        calendar_stamp.merge(stamp);
+                        accepted.push(calendar.clone());
+                    }
+                    Err(e) => {
+                        last_error = format!("{} returned an invalid timestamp: {}", calendar, e);
+                        info!("{}", last_error);
+                    }
                 }
             }
+            Ok(response) => {
+                last_error = format!("{} returned status {}", calendar, response.status());
+                info!("Calendar {} failed: {}", calendar, last_error);
+            }
             Err(e) => {
                 last_error = format!("{} connection failed: {}", calendar, e);
                 info!("{}", last_error);
@@ -100,170 +184,183 @@ pub fn submit_to_opentimestamps(sha256_hex: &str) -> SealedResult<(Vec<u8>, Stri
         }
     }
 
-    Err(SealedError::TimestampError(format!(
-        "All OpenTimestamps calendars failed. Last error: {}",
-        last_error
-    )))
+    if accepted.is_empty() || accepted.len() < config.min_calendars {
+        return Err(SealedError::TimestampError(format!(
+            "Only {} of {} required OpenTimestamps calendars responded. Last error: {}",
+            accepted.len(),
+            config.min_calendars.max(1),
+            last_error
+        )));
+    }
+
+    let mut proof = DetachedTimestampFile::from_sha256(&hash_bytes);
+    proof.timestamp.ops.push((
+        nonce_op,
+        Timestamp {
+            attestations: Vec::new(),
+            ops: vec![(Op::Sha256, calendar_stamp)],
+        },
+    ));
+
+    Ok((proof.serialize(), accepted))
 }
 
-/// Poll a calendar server to upgrade a pending OTS proof to a Bitcoin-confirmed one.
-/// Returns the upgraded proof bytes if successful, None if still pending.
-pub fn try_upgrade_ots(sha256_hex: &str) -> SealedResult<Option<Vec<u8>>> {
+/// Request an RFC 3161 timestamp token over a SHA-256 hash.
+/// Returns the DER `TimeStampResp` once its imprint and nonce check out.
+pub fn submit_to_tsa(sha256_hex: &str, config: &TsaConfig) -> SealedResult<Vec<u8>> {
     let hash_bytes = hex::decode(sha256_hex).map_err(|e| {
         SealedError::InvalidInput(format!("Invalid SHA-256 hex: {}", e))
     })?;
 
+    let nonce = OsRng.next_u64();
+    let request = TimeStampReq::for_sha256(&hash_bytes, nonce)?;
+    let body = der::Encode::to_der(&request)?;
+
     let client = reqwest::blocking::Client::builder()
-        .timeout(std::time::Duration::from_secs(15))
+        .timeout(std::time::Duration::from_secs(config.timeout_secs))
         .build()
         .map_err(|e| SealedError::TimestampError(format!("HTTP client error: {}", e)))?;
 
-    for calendar in OTS_CALENDARS {
-        let url = format!("{}/timestamp", calendar);
+    info!("Requesting RFC 3161 timestamp from {}", config.url);
+    let response = client
+        .post(&config.url)
+        .header("Content-Type", "application/timestamp-query")
+        .header("Accept", "application/timestamp-reply")
+        .header("User-Agent", "sealed-ch/2.0")
+        .body(body)
+        .send()?;
+    if !response.status().is_success() {
+        return Err(SealedError::TimestampError(format!(
+            "TSA {} returned status {}",
+            config.url,
+            response.status()
+        )));
+    }
+    let tsr = response.bytes()?.to_vec();
 
-        match client
-            .post(&url)
-            .header("Content-Type", "application/octet-stream")
-            .header("User-Agent", "sealed-ch/2.0")
-            .header("Accept", "application/vnd.opentimestamps.v1")
-            .body(hash_bytes.clone())
-            .send()
-        {
-            Ok(response) if response.status().is_success() => {
-                let body = response.bytes().map_err(|e| {
-                    SealedError::TimestampError(format!("Failed to read upgrade response: {}", e))
-                })?;
-                if body.len() > 0 {
-                    let upgraded = build_ots_proof(&hash_bytes, calendar, &body);
-                    return Ok(Some(upgraded));
-                }
-            }
-            _ => continue,
-        }
+    let token = TimeStampToken::from_response(&tsr)?;
+    if !token.tst_info.imprint_matches(&hash_bytes) {
+        return Err(SealedError::TimestampError(
+            "TSA token does not cover the submitted hash".to_string(),
+        ));
+    }
+    if token.tst_info.nonce != request.nonce {
+        return Err(SealedError::TimestampError(
+            "TSA token nonce does not match the request".to_string(),
+        ));
     }
 
-    Ok(None)
+    info!("RFC 3161 token received from {} ({} bytes)", config.url, tsr.len());
+    Ok(tsr)
 }
 
-/// Spawn a detached background process that polls for OTS Bitcoin confirmation.
-/// Re-invokes the current binary with the hidden `ots-upgrade` subcommand.
-pub fn spawn_upgrade_listener(sha256_hex: &str, output_dir: &Path, ipfs_url: Option<&str>, ipfs_key: Option<&str>) {
-    let exe = match std::env::current_exe() {
-        Ok(p) => p,
-        Err(e) => {
-            info!("OTS upgrade: could not determine executable path: {}", e);
-            return;
+/// Ask each pending calendar for the rest of the proof and merge what it returns.
+/// Returns true if anything was merged.
+pub fn upgrade_proof(
+    proof: &mut DetachedTimestampFile,
+    config: &CalendarConfig,
+) -> SealedResult<bool> {
+    let client = config.client()?;
+    let digest = proof.digest.clone();
+    let mut changed = false;
+
+    for (commitment, uri) in proof.timestamp.pending_from(&digest)? {
+        if !config.allows(&uri) {
+            info!("Skipping calendar not on the upgrade whitelist: {}", uri);
+            continue;
         }
-    };
+        let url = format!("{}/timestamp/{}", uri.trim_end_matches('/'), hex::encode(&commitment));
 
-    let log_path = output_dir.join("ots_upgrade.log");
-    let log_file = match std::fs::File::create(&log_path) {
-        Ok(f) => f,
-        Err(e) => {
-            info!("OTS upgrade: could not create log file: {}", e);
-            return;
+        let response = match client
+            .get(&url)
+            .header("User-Agent", "sealed-ch/2.0")
+            .header("Accept", "application/vnd.opentimestamps.v1")
+            .send()
+        {
+            Ok(r) => r,
+            Err(e) => {
+                info!("Calendar {} unreachable: {}", uri, e);
+                continue;
+            }
+        };
+        if !response.status().is_success() {
+            // 404 means the calendar hasn't committed this digest to Bitcoin yet.
+            continue;
         }
-    };
-
-    let mut cmd = std::process::Command::new(exe);
-    cmd.arg("ots-upgrade")
-        .arg("--hash")
-        .arg(sha256_hex)
-        .arg("--output-dir")
-        .arg(output_dir);
+        // One calendar answering badly must not hold up the others' branches.
+        let upgrade = match response.bytes().map_err(SealedError::from).and_then(|body| Timestamp::parse(&body)) {
+            Ok(upgrade) => upgrade,
+            Err(e) => {
+                info!("Ignoring bad upgrade from calendar {}: {}", uri, e);
+                continue;
+            }
+        };
 
-    if let Some(url) = ipfs_url {
-        cmd.arg("--ipfs-url").arg(url);
-    }
-    if let Some(key) = ipfs_key {
-        cmd.arg("--ipfs-key").arg(key);
+        if let Some(node) = proof.timestamp.node_for_commitment_mut(&digest, &commitment) {
+            node.merge(upgrade);
+            changed = true;
+        }
     }
 
-    cmd.stdin(std::process::Stdio::null())
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::from(log_file));
+    Ok(changed)
+}
 
-    #[cfg(windows)]
-    {
-        use std::os::windows::process::CommandExt;
-        const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
-        const DETACHED_PROCESS: u32 = 0x00000008;
-        cmd.creation_flags(CREATE_NEW_PROCESS_GROUP | DETACHED_PROCESS);
+/// Try to upgrade the .ots file in place. Returns true once the proof
+/// carries a Bitcoin attestation.
+pub fn try_upgrade_ots(ots_path: &Path, config: &CalendarConfig) -> SealedResult<bool> {
+    let mut proof = DetachedTimestampFile::load(ots_path)?;
+    if proof.timestamp.is_complete() {
+        return Ok(true);
     }
 
-    match cmd.spawn() {
-        Ok(child) => {
-            info!("OTS upgrade listener spawned (pid {})", child.id());
-            drop(child);
-        }
-        Err(e) => info!("OTS upgrade: failed to spawn background process: {}", e),
+    if upgrade_proof(&mut proof, config)? {
+        proof.save(ots_path)?;
     }
-}
 
-/// Run the OTS upgrade polling loop (called by the hidden `ots-upgrade` subcommand).
-pub fn run_upgrade_loop(sha256_hex: &str, output_dir: &Path, ipfs_url: Option<&str>, ipfs_key: Option<&str>) {
-    let intervals = [30, 60, 120, 300, 600, 900, 1800, 3600];
-
-    for attempt in 0..24 {
-        let wait = intervals[attempt.min(intervals.len() - 1)];
-        eprintln!("[OTS] attempt {}: sleeping {}s", attempt + 1, wait);
-        std::thread::sleep(std::time::Duration::from_secs(wait as u64));
-
-        match try_upgrade_ots(sha256_hex) {
-            Ok(Some(upgraded_proof)) => {
-                let ots_path = output_dir.join("timestamp.ots");
-                if let Err(e) = std::fs::write(&ots_path, &upgraded_proof) {
-                    eprintln!("[OTS] failed to write proof: {}", e);
-                    return;
-                }
-
-                let record_path = output_dir.join("timestamp_record.json");
-                if let Ok(json) = std::fs::read_to_string(&record_path) {
-                    if let Ok(mut record) = serde_json::from_str::<TimestampRecord>(&json) {
-                        record.status = "confirmed".to_string();
-                        record.ots_file = ots_path.display().to_string();
-                        if let Ok(updated) = serde_json::to_string_pretty(&record) {
-                            let _ = std::fs::write(&record_path, updated);
-                        }
-                    }
-                }
-
-                if let Some(url) = ipfs_url {
-                    eprintln!("[OTS] re-pinning to IPFS with confirmed proof...");
-                    repin_with_ots(output_dir, url, ipfs_key);
-                }
+    Ok(proof.timestamp.is_complete())
+}
 
-                eprintln!("[OTS] confirmed! proof upgraded.");
-                return;
-            }
-            Ok(None) => {
-                eprintln!("[OTS] attempt {}: still pending", attempt + 1);
-            }
-            Err(e) => {
-                eprintln!("[OTS] attempt {}: error: {}", attempt + 1, e);
+/// Mark `timestamp_record.json` in a sealed directory as confirmed.
+pub(crate) fn mark_confirmed(output_dir: &Path) {
+    let record_path = output_dir.join("timestamp_record.json");
+    if let Ok(json) = std::fs::read_to_string(&record_path) {
+        if let Ok(mut record) = serde_json::from_str::<TimestampRecord>(&json) {
+            record.status = "confirmed".to_string();
+            record.ots_file = "timestamp.ots".to_string();
+            if let Ok(updated) = serde_json::to_string_pretty(&record) {
+                let _ = std::fs::write(&record_path, updated);
             }
         }
     }
-    eprintln!("[OTS] gave up after 24 attempts");
 }
 
 /// Re-pin hashes.json + confirmed OTS proof to IPFS.
-fn repin_with_ots(output_dir: &Path, ipfs_url: &str, ipfs_key: Option<&str>) {
-    use crate::ipfs::{pin_to_ipfs, IpfsConfig};
-
-    let config = IpfsConfig {
-        api_url: ipfs_url.to_string(),
-        api_key: ipfs_key.map(|k| k.to_string()),
-        gateway_url: if ipfs_url.contains("pinata") {
-            "https://gateway.pinata.cloud/ipfs".to_string()
-        } else {
-            "https://ipfs.io/ipfs".to_string()
-        },
-    };
+pub(crate) fn repin_with_ots(output_dir: &Path, config: &crate::ipfs::IpfsConfig) {
+    use crate::ipfs::pin_to_ipfs;
+
+    // A directory pin is refreshed as a whole so the new proof joins the tree.
+    let existing = std::fs::read_to_string(output_dir.join("ipfs_record.json"))
+        .ok()
+        .and_then(|json| serde_json::from_str::<crate::ipfs::IpfsRecord>(&json).ok());
+    if existing.is_some_and(|r| !r.files.is_empty()) {
+        match crate::ipfs::pin_directory(output_dir, config) {
+            Ok(record) => {
+                eprintln!("[OTS] IPFS directory re-pin: root CID={}", record.cid);
+                let ipfs_json = serde_json::to_string_pretty(&record).unwrap_or_default();
+                let _ = std::fs::write(output_dir.join("ipfs_record.json"), ipfs_json);
+            }
+            Err(e) => eprintln!("[OTS] IPFS directory re-pin failed: {}", e),
+        }
+        return;
+    }
 
-    let hashes_path = output_dir.join("hashes.json");
+    // Directory seals anchor batch.json rather than a single hashes.json.
+    let hashes_path = match output_dir.join("hashes.json") {
+        p if p.exists() => p,
+        _ => output_dir.join(crate::batch::BATCH_MANIFEST_FILE),
+    };
     if hashes_path.exists() {
-        match pin_to_ipfs(&hashes_path, &config) {
+        match pin_to_ipfs(&hashes_path, config) {
             Ok(record) => {
                 eprintln!("[OTS] IPFS re-pin: CID={}", record.cid);
                 let ipfs_json = serde_json::to_string_pretty(&record).unwrap_or_default();
@@ -275,7 +372,7 @@ fn repin_with_ots(output_dir: &Path, ipfs_url: &str, ipfs_key: Option<&str>) {
 
     let ots_path = output_dir.join("timestamp.ots");
     if ots_path.exists() {
-        match pin_to_ipfs(&ots_path, &config) {
+        match pin_to_ipfs(&ots_path, config) {
             Ok(record) => {
                 eprintln!("[OTS] IPFS pin (ots proof): CID={}", record.cid);
                 let ipfs_json = serde_json::to_string_pretty(&record).unwrap_or_default();
@@ -288,7 +385,16 @@ fn repin_with_ots(output_dir: &Path, ipfs_url: &str, ipfs_key: Option<&str>) {
 
 /// Submit a hash and save the .ots proof file.
 pub fn timestamp_hash(sha256_hex: &str, output_dir: &Path) -> SealedResult<TimestampRecord> {
-    let (ots_proof, calendar_used) = submit_to_opentimestamps(sha256_hex)?;
+    timestamp_hash_with(sha256_hex, output_dir, &CalendarConfig::default())
+}
+
+/// Submit a hash to the given calendars and save the .ots proof file.
+pub fn timestamp_hash_with(
+    sha256_hex: &str,
+    output_dir: &Path,
+    config: &CalendarConfig,
+) -> SealedResult<TimestampRecord> {
+    let (ots_proof, calendars_used) = submit_to_calendars(sha256_hex, config)?;
 
     let ots_path = output_dir.join("timestamp.ots");
     std::fs::write(&ots_path, &ots_proof)?;
@@ -296,10 +402,11 @@ pub fn timestamp_hash(sha256_hex: &str, output_dir: &Path) -> SealedResult<Times
 
     let record = TimestampRecord {
         hash: sha256_hex.to_string(),
-        calendars: vec![calendar_used],
+        calendars: calendars_used,
         ots_file: "timestamp.ots".to_string(),
         status: "pending".to_string(),
         submitted_at: chrono::Utc::now().to_rfc3339(),
+        tsa: None,
     };
 
     let record_json = serde_json::to_string_pretty(&record)?;
@@ -308,3 +415,43 @@ pub fn timestamp_hash(sha256_hex: &str, output_dir: &Path) -> SealedResult<Times
 
     Ok(record)
 }
+
+/// Request an RFC 3161 token, save it as `timestamp.tsr`, and record it in
+/// `timestamp_record.json` alongside any OpenTimestamps submission.
+pub fn timestamp_hash_tsa(
+    sha256_hex: &str,
+    output_dir: &Path,
+    config: &TsaConfig,
+) -> SealedResult<TimestampRecord> {
+    let tsr = submit_to_tsa(sha256_hex, config)?;
+    let token = TimeStampToken::from_response(&tsr)?;
+
+    let tsr_path = output_dir.join(TSR_FILE);
+    std::fs::write(&tsr_path, &tsr)?;
+    info!("RFC 3161 token saved to {} ({} bytes)", tsr_path.display(), tsr.len());
+
+    let record_path = output_dir.join("timestamp_record.json");
+    let existing = std::fs::read_to_string(&record_path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<TimestampRecord>(&json).ok())
+        .filter(|record| record.hash == sha256_hex);
+    let mut record = existing.unwrap_or_else(|| TimestampRecord {
+        hash: sha256_hex.to_string(),
+        calendars: Vec::new(),
+        ots_file: String::new(),
+        status: "none".to_string(),
+        submitted_at: chrono::Utc::now().to_rfc3339(),
+        tsa: None,
+    });
+    record.tsa = Some(TsaRecord {
+        url: config.url.clone(),
+        tsr_file: TSR_FILE.to_string(),
+        gen_time: token.tst_info.gen_time()?.to_rfc3339(),
+        serial_number: hex::encode(token.tst_info.serial_number.as_bytes()),
+        policy: token.tst_info.policy.to_string(),
+    });
+
+    std::fs::write(&record_path, serde_json::to_string_pretty(&record)?)?;
+
+    Ok(record)
+}
@@ -0,0 +1,316 @@
+use std::path::{Path, PathBuf};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::errors::{SealedError, SealedResult};
+use crate::json_file::{load_or_default, save_pretty};
+use crate::key_formats::parse_public_key;
+use crate::signing::key_id_from_base64;
+use crate::statements::KeyStatement;
+
+/// Default trust store file name, under the sealed home directory.
+pub const TRUST_STORE_FILE: &str = "trust.json";
+
+/// `~/.sealed/trust.json` (or under `$SEALED_HOME`).
+pub fn default_trust_store_path() -> PathBuf {
+    crate::paths::sealed_home().join(TRUST_STORE_FILE)
+}
+
+/// A publisher key the user has chosen to trust.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedKey {
+    /// Short unique name, shown in verification results.
+    pub name: String,
+    /// Ed25519 public key, base64 (as in `signed_record.json`).
+    pub public_key: String,
+    /// Free-form description, e.g. the publisher's full name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub added_at: String,
+    /// Records sealed before this time (RFC 3339) are not trusted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<String>,
+    /// Records sealed after this time (RFC 3339) are not trusted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<String>,
+    #[serde(default)]
+    pub revoked: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revocation_reason: Option<String>,
+    /// Records sealed from this time (RFC 3339) on are untrusted. Absent on a
+    /// revoked key means nothing it sealed is trusted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_effective: Option<String>,
+    /// Key ID of the key this one was rotated to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<String>,
+}
+
+impl TrustedKey {
+    /// A key trusted from now on with no validity period.
+    pub fn new(name: &str, public_key: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            public_key: public_key.to_string(),
+            label: None,
+            added_at: Utc::now().to_rfc3339(),
+            not_before: None,
+            not_after: None,
+            revoked: false,
+            revoked_at: None,
+            revocation_reason: None,
+            revoked_effective: None,
+            replaced_by: None,
+        }
+    }
+
+    /// See [`crate::signing::key_id`].
+    pub fn key_id(&self) -> String {
+        key_id_from_base64(&self.public_key).unwrap_or_default()
+    }
+
+    /// Whether the revocation covers a record sealed at `sealed_at`. Records
+    /// with an unreadable time are covered by any revocation.
+    fn revoked_for(&self, sealed_at: Option<DateTime<Utc>>) -> bool {
+        self.revoked
+            && self.revoked_effective.as_deref().is_none_or(|t| {
+                !matches!((parse_time(t), sealed_at), (Ok(from), Some(at)) if at < from)
+            })
+    }
+}
+
+/// What the trust store says about a signing key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum KeyTrust {
+    /// A known key, not revoked, and the record falls in its validity period.
+    Trusted { name: String },
+    /// A known key that has been revoked; nothing it signed is trusted.
+    Revoked {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+    /// A known key, but the record was sealed outside its validity period.
+    OutsideValidity { name: String },
+    /// Not in the trust store.
+    Unknown,
+}
+
+impl KeyTrust {
+    pub fn is_trusted(&self) -> bool {
+        matches!(self, KeyTrust::Trusted { .. })
+    }
+}
+
+/// Named publisher keys, persisted as JSON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrustStore {
+    pub keys: Vec<TrustedKey>,
+}
+
+impl TrustStore {
+    /// Load the store; a missing file is an empty store.
+    pub fn load(path: &Path) -> SealedResult<Self> {
+        load_or_default(path)
+    }
+
+    /// Write atomically, creating the parent directory.
+    pub fn save(&self, path: &Path) -> SealedResult<()> {
+        save_pretty(path, self)
+    }
+
+    /// Add a key under a new name. Names and keys must both be unique.
+    pub fn add(&mut self, key: TrustedKey) -> SealedResult<()> {
+        normalize_public_key(&key.public_key)?;
+        for bound in [&key.not_before, &key.not_after, &key.revoked_effective].into_iter().flatten() {
+            parse_time(bound)?;
+        }
+        if let Some(existing) = self.keys.iter().find(|k| k.name == key.name || k.public_key == key.public_key) {
+            return Err(SealedError::InvalidInput(format!(
+                "Trust store already has '{}' ({})",
+                existing.name, existing.public_key
+            )));
+        }
+        self.keys.push(key);
+        Ok(())
+    }
+
+    /// Mark a key revoked, by name, base64 public key or key ID. With
+    /// `effective` (RFC 3339), records sealed before it stay trusted.
+    pub fn revoke(&mut self, target: &str, reason: Option<&str>, effective: Option<&str>) -> SealedResult<&TrustedKey> {
+        let effective = effective.map(|t| parse_time(t).map(|t| t.to_rfc3339())).transpose()?;
+        let key = self
+            .keys
+            .iter_mut()
+            .find(|k| k.name == target || k.public_key == target || k.key_id() == target)
+            .ok_or_else(|| SealedError::InvalidInput(format!("No trusted key named '{}'", target)))?;
+        // When revoked twice, the wider of the two revocations stands.
+        key.revoked_effective = match (key.revoked, key.revoked_effective.take(), effective) {
+            (false, _, effective) => effective,
+            (true, Some(a), Some(b)) => Some(earliest(a, b)),
+            (true, _, _) => None,
+        };
+        key.revoked = true;
+        key.revoked_at = Some(Utc::now().to_rfc3339());
+        if let Some(reason) = reason {
+            key.revocation_reason = Some(reason.to_string());
+        }
+        Ok(key)
+    }
+
+    /// Apply a verified key statement. A revocation marks the key revoked from
+    /// its effective time; a key the store does not know is recorded under
+    /// `name` (or its key ID) as revoked outright, since anyone can sign a
+    /// revocation of their own key and it must not buy trust before the date.
+    /// A rotation ends the old key's validity where the new key's begins and
+    /// trusts the new key under `name` (or the old name plus the new key ID);
+    /// the old key must already be trusted. Returns the affected key's name.
+    pub fn apply_statement(&mut self, statement: &KeyStatement, name: Option<&str>) -> SealedResult<String> {
+        match statement {
+            KeyStatement::Revocation { key_id, public_key, effective_at, reason, .. } => {
+                if self.find(public_key).is_none() {
+                    self.add(TrustedKey::new(name.unwrap_or(key_id), public_key))?;
+                    return Ok(self.revoke(public_key, reason.as_deref(), None)?.name.clone());
+                }
+                Ok(self.revoke(public_key, reason.as_deref(), Some(effective_at))?.name.clone())
+            }
+            KeyStatement::Rotation { old_public_key, new_key_id, new_public_key, effective_at, .. } => {
+                let effective = parse_time(effective_at)?;
+                let old = self
+                    .keys
+                    .iter()
+                    .position(|k| k.public_key == *old_public_key)
+                    .ok_or_else(|| SealedError::InvalidInput(format!(
+                        "The rotated key {} is not in the trust store",
+                        key_id_from_base64(old_public_key).unwrap_or_default()
+                    )))?;
+                if !self.assess(old_public_key, effective_at).is_trusted() {
+                    return Err(SealedError::InvalidInput(format!(
+                        "'{}' is not trusted at {}, so it cannot hand over to a new key",
+                        self.keys[old].name, effective_at
+                    )));
+                }
+                if let Some(existing) = self.find(new_public_key) {
+                    return Err(SealedError::InvalidInput(format!(
+                        "Trust store already has the new key as '{}'",
+                        existing.name
+                    )));
+                }
+                let new_name = match name {
+                    Some(name) => name.to_string(),
+                    None => format!("{}-{}", self.keys[old].name, &new_key_id[..8]),
+                };
+                let mut new = TrustedKey::new(&new_name, new_public_key);
+                new.label = self.keys[old].label.clone();
+                new.not_before = Some(effective.to_rfc3339());
+                self.add(new)?;
+
+                let old = &mut self.keys[old];
+                old.not_after = Some(match old.not_after.take() {
+                    Some(end) => earliest(end, effective.to_rfc3339()),
+                    None => effective.to_rfc3339(),
+                });
+                old.replaced_by = Some(new_key_id.clone());
+                Ok(new_name)
+            }
+        }
+    }
+
+    /// The entry for a base64 public key.
+    pub fn find(&self, public_key: &str) -> Option<&TrustedKey> {
+        self.keys.iter().find(|k| k.public_key == public_key)
+    }
+
+    /// Judge `public_key` for a record sealed at `sealed_at` (RFC 3339). An
+    /// unreadable sealing time only passes keys with no validity period.
+    pub fn assess(&self, public_key: &str, sealed_at: &str) -> KeyTrust {
+        let Some(key) = self.find(public_key) else { return KeyTrust::Unknown };
+        let sealed = parse_time(sealed_at).ok();
+        if key.revoked_for(sealed) {
+            return KeyTrust::Revoked { name: key.name.clone(), reason: key.revocation_reason.clone() };
+        }
+        let after_start = key.not_before.as_deref().is_none_or(|t| {
+            matches!((parse_time(t), sealed), (Ok(start), Some(at)) if at >= start)
+        });
+        let before_end = key.not_after.as_deref().is_none_or(|t| {
+            matches!((parse_time(t), sealed), (Ok(end), Some(at)) if at <= end)
+        });
+        if after_start && before_end {
+            KeyTrust::Trusted { name: key.name.clone() }
+        } else {
+            KeyTrust::OutsideValidity { name: key.name.clone() }
+        }
+    }
+
+    /// Judge `public_key` for a seal. `claimed` is the record's own
+    /// `sealed_at`, which the signer wrote and could backdate; `proven` is a
+    /// time from independent evidence (a verified RFC 3161 token or Bitcoin
+    /// block) that the seal existed by then. Without proof, a key with a
+    /// revocation or an end to its validity is not trusted at all.
+    pub fn assess_seal(&self, public_key: &str, claimed: &str, proven: Option<&str>) -> KeyTrust {
+        if let Some(at) = proven {
+            return self.assess(public_key, at);
+        }
+        match (self.assess(public_key, claimed), self.find(public_key)) {
+            (KeyTrust::Trusted { name }, Some(key)) if key.revoked => {
+                KeyTrust::Revoked { name, reason: key.revocation_reason.clone() }
+            }
+            (KeyTrust::Trusted { name }, Some(key)) if key.not_after.is_some() => KeyTrust::OutsideValidity { name },
+            (trust, _) => trust,
+        }
+    }
+}
+
+/// Read a public key given as a file (raw 32 bytes, base64, PEM, OpenSSH or
+/// JWK) or as base64 or `ssh-ed25519` text itself. Returns it base64-encoded.
+pub fn read_public_key(arg: &str) -> SealedResult<String> {
+    let path = Path::new(arg);
+    let key = if path.is_file() {
+        parse_public_key(&std::fs::read(path)?, None)?.0
+    } else {
+        parse_public_key(arg.as_bytes(), None)?.0
+    };
+    Ok(BASE64.encode(key.to_bytes()))
+}
+
+/// Validate a base64 Ed25519 public key, returned in standard base64.
+pub fn normalize_public_key(b64: &str) -> SealedResult<String> {
+    let bytes = BASE64
+        .decode(b64)
+        .map_err(|e| SealedError::KeyError(format!("Public key is not base64: {}", e)))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| SealedError::KeyError("Invalid public key: expected 32 bytes".to_string()))?;
+    ed25519_dalek::VerifyingKey::from_bytes(&bytes)?;
+    Ok(BASE64.encode(bytes))
+}
+
+/// Accept RFC 3339 or a bare `YYYY-MM-DD` date (start of day, or its last
+/// second when `end_of_day`), returned as RFC 3339.
+pub fn normalize_time(value: &str, end_of_day: bool) -> SealedResult<String> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let time = if end_of_day { date.and_hms_opt(23, 59, 59) } else { date.and_hms_opt(0, 0, 0) };
+        return Ok(time.expect("valid time of day").and_utc().to_rfc3339());
+    }
+    Ok(parse_time(value)?.to_rfc3339())
+}
+
+fn parse_time(value: &str) -> SealedResult<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| SealedError::InvalidInput(format!("Invalid time '{}': {}", value, e)))
+}
+
+/// The earlier of two RFC 3339 times; unreadable ones lose.
+fn earliest(a: String, b: String) -> String {
+    match (parse_time(&a), parse_time(&b)) {
+        (Ok(ta), Ok(tb)) if tb < ta => b,
+        (Ok(_), _) => a,
+        _ => b,
+    }
+}
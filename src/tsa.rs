@@ -0,0 +1,507 @@
+use std::path::Path;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use cms::cert::CertificateChoices;
+use cms::content_info::ContentInfo;
+use cms::signed_data::{SignedData, SignerIdentifier, SignerInfo};
+use der::asn1::{Any, ObjectIdentifier, OctetString, Uint};
+use der::oid::db::rfc5280::{ID_CE_BASIC_CONSTRAINTS, ID_CE_EXT_KEY_USAGE, ID_CE_SUBJECT_KEY_IDENTIFIER, ID_KP_TIME_STAMPING};
+use der::{Decode, DecodePem, Encode, Sequence, Tag, Tagged};
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Sha384, Sha512, Digest};
+use x509_cert::ext::pkix::{BasicConstraints, ExtendedKeyUsage, SubjectKeyIdentifier};
+use x509_cert::spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned};
+use x509_cert::Certificate;
+
+use crate::errors::{SealedError, SealedResult};
+
+const ID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+const ID_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.2");
+const ID_SHA512: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.3");
+
+const ID_SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+const ID_CT_TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+const ID_CONTENT_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+const ID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const SHA256_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+const SHA384_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.12");
+const SHA512_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.13");
+const ID_EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+const ECDSA_WITH_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+const ECDSA_WITH_SHA512: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.4");
+
+/// PKIStatus values that carry a token (granted, grantedWithMods).
+const STATUS_GRANTED: u8 = 0;
+const STATUS_GRANTED_WITH_MODS: u8 = 1;
+
+/// How many certificates above the signer we will walk looking for the anchor.
+const MAX_CHAIN_DEPTH: usize = 4;
+
+/// RFC 3161 `MessageImprint`.
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct MessageImprint {
+    pub hash_algorithm: AlgorithmIdentifierOwned,
+    pub hashed_message: OctetString,
+}
+
+/// RFC 3161 `TimeStampReq`.
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct TimeStampReq {
+    pub version: u8,
+    pub message_imprint: MessageImprint,
+    #[asn1(optional = "true")]
+    pub req_policy: Option<ObjectIdentifier>,
+    #[asn1(optional = "true")]
+    pub nonce: Option<Uint>,
+    #[asn1(default = "Default::default")]
+    pub cert_req: bool,
+}
+
+impl TimeStampReq {
+    /// Request over a SHA-256 digest, asking the TSA to include its certificate.
+    pub fn for_sha256(digest: &[u8], nonce: u64) -> SealedResult<Self> {
+        if digest.len() != 32 {
+            return Err(SealedError::InvalidInput("SHA-256 hash must be 32 bytes".to_string()));
+        }
+        Ok(Self {
+            version: 1,
+            message_imprint: MessageImprint {
+                hash_algorithm: AlgorithmIdentifierOwned { oid: ID_SHA256, parameters: None },
+                hashed_message: OctetString::new(digest)?,
+            },
+            req_policy: None,
+            nonce: Some(Uint::new(&nonce.to_be_bytes())?),
+            cert_req: true,
+        })
+    }
+}
+
+/// RFC 3161 `PKIStatusInfo`.
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct PkiStatusInfo {
+    pub status: u8,
+    #[asn1(optional = "true")]
+    pub status_string: Option<Vec<String>>,
+    #[asn1(optional = "true")]
+    pub fail_info: Option<der::asn1::BitString>,
+}
+
+/// RFC 3161 `TimeStampResp`, the content of a `.tsr` file.
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct TimeStampResp {
+    pub status: PkiStatusInfo,
+    #[asn1(optional = "true")]
+    pub time_stamp_token: Option<ContentInfo>,
+}
+
+/// RFC 3161 `Accuracy`.
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct Accuracy {
+    #[asn1(optional = "true")]
+    pub seconds: Option<u32>,
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    pub millis: Option<u16>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    pub micros: Option<u16>,
+}
+
+/// RFC 3161 `TSTInfo`, the signed content of a timestamp token.
+///
+/// `gen_time` is kept raw because TSAs commonly emit fractional seconds,
+/// which DER `GeneralizedTime` decoding rejects.
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct TstInfo {
+    pub version: u8,
+    pub policy: ObjectIdentifier,
+    pub message_imprint: MessageImprint,
+    pub serial_number: Uint,
+    pub gen_time: Any,
+    #[asn1(optional = "true")]
+    pub accuracy: Option<Accuracy>,
+    #[asn1(default = "Default::default")]
+    pub ordering: bool,
+    #[asn1(optional = "true")]
+    pub nonce: Option<Uint>,
+    #[asn1(context_specific = "0", tag_mode = "EXPLICIT", optional = "true")]
+    pub tsa: Option<Any>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", constructed = "true", optional = "true")]
+    pub extensions: Option<Vec<x509_cert::ext::Extension>>,
+}
+
+impl TstInfo {
+    /// `genTime` as UTC (fractional seconds truncated).
+    pub fn gen_time(&self) -> SealedResult<DateTime<Utc>> {
+        if self.gen_time.tag() != Tag::GeneralizedTime {
+            return Err(SealedError::TimestampError("TSTInfo genTime is not a GeneralizedTime".to_string()));
+        }
+        let text = std::str::from_utf8(self.gen_time.value())
+            .map_err(|_| SealedError::TimestampError("TSTInfo genTime is not ASCII".to_string()))?;
+        let naive = NaiveDateTime::parse_from_str(text, "%Y%m%d%H%M%S%.fZ")
+            .map_err(|e| SealedError::TimestampError(format!("Invalid genTime '{}': {}", text, e)))?;
+        Ok(naive.and_utc())
+    }
+
+    /// Whether the token's imprint is SHA-256 over `digest`.
+    pub fn imprint_matches(&self, digest: &[u8]) -> bool {
+        self.message_imprint.hash_algorithm.oid == ID_SHA256
+            && self.message_imprint.hashed_message.as_bytes() == digest
+    }
+}
+
+/// A parsed timestamp token: the CMS envelope plus its decoded `TSTInfo`.
+#[derive(Debug, Clone)]
+pub struct TimeStampToken {
+    pub signed_data: SignedData,
+    pub tst_info: TstInfo,
+    /// DER bytes of `TSTInfo` exactly as signed.
+    econtent: Vec<u8>,
+}
+
+impl TimeStampToken {
+    /// Parse a DER `TimeStampResp`, requiring a granted status and a token.
+    pub fn from_response(der_bytes: &[u8]) -> SealedResult<Self> {
+        let resp = TimeStampResp::from_der(der_bytes)?;
+        let status = resp.status.status;
+        if status != STATUS_GRANTED && status != STATUS_GRANTED_WITH_MODS {
+            let text = resp.status.status_string.unwrap_or_default().join("; ");
+            return Err(SealedError::TimestampError(format!(
+                "TSA rejected the request (status {}): {}",
+                status, text
+            )));
+        }
+        let token = resp.time_stamp_token.ok_or_else(|| {
+            SealedError::TimestampError("TSA response carries no timestamp token".to_string())
+        })?;
+        Self::from_content_info(&token)
+    }
+
+    /// Parse a bare `TimeStampToken` (CMS `ContentInfo`).
+    pub fn from_content_info(token: &ContentInfo) -> SealedResult<Self> {
+        if token.content_type != ID_SIGNED_DATA {
+            return Err(SealedError::TimestampError(format!(
+                "Timestamp token is {}, not SignedData",
+                token.content_type
+            )));
+        }
+        let signed_data: SignedData = token.content.decode_as()?;
+        let eci = &signed_data.encap_content_info;
+        if eci.econtent_type != ID_CT_TST_INFO {
+            return Err(SealedError::TimestampError(format!(
+                "Timestamp token content is {}, not TSTInfo",
+                eci.econtent_type
+            )));
+        }
+        let econtent = eci
+            .econtent
+            .as_ref()
+            .ok_or_else(|| SealedError::TimestampError("Timestamp token has no TSTInfo".to_string()))?
+            .decode_as::<OctetString>()?
+            .into_bytes();
+        let tst_info = TstInfo::from_der(&econtent)?;
+
+        Ok(Self { signed_data, tst_info, econtent })
+    }
+
+    fn certificates(&self) -> Vec<&Certificate> {
+        self.signed_data
+            .certificates
+            .iter()
+            .flat_map(|set| set.0.iter())
+            .filter_map(|choice| match choice {
+                CertificateChoices::Certificate(cert) => Some(cert),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The certificate named by the signer's `sid`, if the TSA included it.
+    fn signer_certificate(&self, signer: &SignerInfo) -> Option<&Certificate> {
+        self.certificates().into_iter().find(|cert| match &signer.sid {
+            SignerIdentifier::IssuerAndSerialNumber(ias) => {
+                cert.tbs_certificate.issuer == ias.issuer
+                    && cert.tbs_certificate.serial_number == ias.serial_number
+            }
+            SignerIdentifier::SubjectKeyIdentifier(ski) => {
+                extension::<SubjectKeyIdentifier>(cert, ID_CE_SUBJECT_KEY_IDENTIFIER)
+                    .is_some_and(|own| own == *ski)
+            }
+        })
+    }
+}
+
+/// Outcome of checking a timestamp token offline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TsaVerification {
+    /// Hex of the hash the token commits to.
+    pub imprint: String,
+    pub imprint_matches: bool,
+    pub gen_time: String,
+    pub serial_number: String,
+    pub policy: String,
+    pub signer: String,
+    /// CMS signature over the signed attributes checks out with the signer certificate.
+    pub signature_valid: bool,
+    /// Signer certificate has the timeStamping EKU and was valid at `gen_time`.
+    pub signer_valid: bool,
+    /// Signer certificate chains to the configured trust anchor.
+    pub chain_trusted: bool,
+}
+
+impl TsaVerification {
+    /// True if every check passed.
+    pub fn is_verified(&self) -> bool {
+        self.imprint_matches && self.signature_valid && self.signer_valid && self.chain_trusted
+    }
+}
+
+/// Load a trust anchor certificate (PEM or DER).
+pub fn load_trust_anchor(path: &Path) -> SealedResult<Certificate> {
+    if !path.exists() {
+        return Err(SealedError::FileNotFound(path.display().to_string()));
+    }
+    let bytes = std::fs::read(path)?;
+    let cert = if bytes.starts_with(b"-----BEGIN") {
+        Certificate::from_pem(&bytes)?
+    } else {
+        Certificate::from_der(&bytes)?
+    };
+    Ok(cert)
+}
+
+/// Check a `.tsr` response against the sealed SHA-256 and a trust anchor.
+pub fn verify_token(
+    tsr: &[u8],
+    expected_sha256_hex: &str,
+    trust_anchor: &Certificate,
+) -> SealedResult<TsaVerification> {
+    let expected = hex::decode(expected_sha256_hex)
+        .map_err(|e| SealedError::InvalidInput(format!("Invalid SHA-256 hex: {}", e)))?;
+
+    let token = TimeStampToken::from_response(tsr)?;
+    let tst = &token.tst_info;
+    let gen_time = tst.gen_time()?;
+
+    let signers = token.signed_data.signer_infos.0.as_slice();
+    let signer = match signers {
+        [signer] => signer,
+        _ => {
+            return Err(SealedError::TimestampError(format!(
+                "Timestamp token must have exactly one signer, found {}",
+                signers.len()
+            )))
+        }
+    };
+    let cert = token.signer_certificate(signer).ok_or_else(|| {
+        SealedError::TimestampError("Timestamp token does not include the signer certificate".to_string())
+    })?;
+
+    let signature_valid = verify_signer_info(signer, &token.econtent, cert)?;
+    let signer_valid = has_time_stamping_eku(cert) && valid_at(cert, gen_time);
+    let chain_trusted = chains_to_anchor(cert, &token.certificates(), trust_anchor, gen_time)?;
+
+    Ok(TsaVerification {
+        imprint: hex::encode(tst.message_imprint.hashed_message.as_bytes()),
+        imprint_matches: tst.imprint_matches(&expected),
+        gen_time: gen_time.to_rfc3339(),
+        serial_number: hex::encode(tst.serial_number.as_bytes()),
+        policy: tst.policy.to_string(),
+        signer: cert.tbs_certificate.subject.to_string(),
+        signature_valid,
+        signer_valid,
+        chain_trusted,
+    })
+}
+
+/// Verify a CMS SignerInfo: the messageDigest attribute must match the content
+/// and the signature must cover the DER-encoded signed attributes.
+fn verify_signer_info(signer: &SignerInfo, econtent: &[u8], cert: &Certificate) -> SealedResult<bool> {
+    let attrs = signer.signed_attrs.as_ref().ok_or_else(|| {
+        SealedError::TimestampError("Timestamp token has no signed attributes".to_string())
+    })?;
+    let attr_value = |oid: ObjectIdentifier| {
+        attrs
+            .iter()
+            .find(|attr| attr.oid == oid)
+            .and_then(|attr| attr.values.iter().next())
+    };
+
+    let content_type = attr_value(ID_CONTENT_TYPE)
+        .map(|v| v.decode_as::<ObjectIdentifier>())
+        .transpose()?;
+    if content_type != Some(ID_CT_TST_INFO) {
+        return Ok(false);
+    }
+
+    let digest_alg = digest_for(&signer.digest_alg.oid)?;
+    let message_digest = attr_value(ID_MESSAGE_DIGEST)
+        .map(|v| v.decode_as::<OctetString>())
+        .transpose()?;
+    match message_digest {
+        Some(md) if md.as_bytes() == digest_alg.hash(econtent) => {}
+        _ => return Ok(false),
+    }
+
+    verify_signature(
+        &cert.tbs_certificate.subject_public_key_info,
+        &signer.signature_algorithm.oid,
+        digest_alg,
+        &attrs.to_der()?,
+        signer.signature.as_bytes(),
+    )
+}
+
+/// Walk from the signer towards `anchor` through certificates carried in the token.
+fn chains_to_anchor(
+    signer: &Certificate,
+    bundled: &[&Certificate],
+    anchor: &Certificate,
+    at: DateTime<Utc>,
+) -> SealedResult<bool> {
+    let mut current = signer;
+    for depth in 0..=MAX_CHAIN_DEPTH {
+        if current == anchor {
+            return Ok(true);
+        }
+        if current.tbs_certificate.issuer == anchor.tbs_certificate.subject {
+            return issued_by(current, anchor);
+        }
+        if depth == MAX_CHAIN_DEPTH {
+            break;
+        }
+        let parent = bundled.iter().find(|c| {
+            c.tbs_certificate.subject == current.tbs_certificate.issuer && **c != current
+        });
+        match parent {
+            Some(parent) if is_ca(parent) && valid_at(parent, at) && issued_by(current, parent)? => {
+                current = parent;
+            }
+            _ => return Ok(false),
+        }
+    }
+    Ok(false)
+}
+
+/// Whether `issuer`'s key signed `cert`.
+fn issued_by(cert: &Certificate, issuer: &Certificate) -> SealedResult<bool> {
+    let sig_alg = &cert.signature_algorithm.oid;
+    let digest_alg = hash_in_signature_alg(sig_alg).ok_or_else(|| {
+        SealedError::UnsupportedFormat(format!("Certificate signature algorithm {}", sig_alg))
+    })?;
+    let signature = cert.signature.as_bytes().ok_or_else(|| {
+        SealedError::TimestampError("Certificate signature has unused bits".to_string())
+    })?;
+    verify_signature(
+        &issuer.tbs_certificate.subject_public_key_info,
+        sig_alg,
+        digest_alg,
+        &cert.tbs_certificate.to_der()?,
+        signature,
+    )
+}
+
+fn has_time_stamping_eku(cert: &Certificate) -> bool {
+    extension::<ExtendedKeyUsage>(cert, ID_CE_EXT_KEY_USAGE)
+        .is_some_and(|eku| eku.0.contains(&ID_KP_TIME_STAMPING))
+}
+
+fn is_ca(cert: &Certificate) -> bool {
+    extension::<BasicConstraints>(cert, ID_CE_BASIC_CONSTRAINTS).is_some_and(|bc| bc.ca)
+}
+
+fn valid_at(cert: &Certificate, at: DateTime<Utc>) -> bool {
+    let validity = &cert.tbs_certificate.validity;
+    let at = at.timestamp();
+    let not_before = validity.not_before.to_unix_duration().as_secs() as i64;
+    let not_after = validity.not_after.to_unix_duration().as_secs() as i64;
+    not_before <= at && at <= not_after
+}
+
+/// Decode the extension with `oid`, if present and well-formed.
+fn extension<'a, T: Decode<'a>>(cert: &'a Certificate, oid: ObjectIdentifier) -> Option<T> {
+    cert.tbs_certificate
+        .extensions
+        .as_ref()?
+        .iter()
+        .find(|ext| ext.extn_id == oid)
+        .and_then(|ext| T::from_der(ext.extn_value.as_bytes()).ok())
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DigestAlg {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlg {
+    fn hash(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            DigestAlg::Sha256 => Sha256::digest(data).to_vec(),
+            DigestAlg::Sha384 => Sha384::digest(data).to_vec(),
+            DigestAlg::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+}
+
+fn digest_for(oid: &ObjectIdentifier) -> SealedResult<DigestAlg> {
+    match *oid {
+        ID_SHA256 => Ok(DigestAlg::Sha256),
+        ID_SHA384 => Ok(DigestAlg::Sha384),
+        ID_SHA512 => Ok(DigestAlg::Sha512),
+        _ => Err(SealedError::UnsupportedFormat(format!("Digest algorithm {}", oid))),
+    }
+}
+
+fn hash_in_signature_alg(oid: &ObjectIdentifier) -> Option<DigestAlg> {
+    match *oid {
+        SHA256_WITH_RSA | ECDSA_WITH_SHA256 => Some(DigestAlg::Sha256),
+        SHA384_WITH_RSA | ECDSA_WITH_SHA384 => Some(DigestAlg::Sha384),
+        SHA512_WITH_RSA | ECDSA_WITH_SHA512 => Some(DigestAlg::Sha512),
+        _ => None,
+    }
+}
+
+/// Verify `signature` over `message` with an RSA (PKCS#1 v1.5) or P-256 ECDSA key.
+/// The hash is taken from `sig_alg` when it names one, otherwise `digest_alg`.
+fn verify_signature(
+    spki: &SubjectPublicKeyInfoOwned,
+    sig_alg: &ObjectIdentifier,
+    digest_alg: DigestAlg,
+    message: &[u8],
+    signature: &[u8],
+) -> SealedResult<bool> {
+    let digest_alg = hash_in_signature_alg(sig_alg).unwrap_or(digest_alg);
+    let hashed = digest_alg.hash(message);
+    let spki_der = spki.to_der()?;
+
+    match spki.algorithm.oid {
+        RSA_ENCRYPTION => {
+            use rsa::pkcs8::DecodePublicKey;
+            use rsa::{Pkcs1v15Sign, RsaPublicKey};
+
+            let key = RsaPublicKey::from_public_key_der(&spki_der)
+                .map_err(|e| SealedError::KeyError(format!("Invalid RSA public key: {}", e)))?;
+            let scheme = match digest_alg {
+                DigestAlg::Sha256 => Pkcs1v15Sign::new::<Sha256>(),
+                DigestAlg::Sha384 => Pkcs1v15Sign::new::<Sha384>(),
+                DigestAlg::Sha512 => Pkcs1v15Sign::new::<Sha512>(),
+            };
+            Ok(key.verify(scheme, &hashed, signature).is_ok())
+        }
+        ID_EC_PUBLIC_KEY => {
+            use p256::ecdsa::signature::hazmat::PrehashVerifier;
+            use p256::ecdsa::{Signature, VerifyingKey};
+            use p256::pkcs8::DecodePublicKey;
+
+            let key = VerifyingKey::from_public_key_der(&spki_der)
+                .map_err(|e| SealedError::KeyError(format!("Unsupported EC public key: {}", e)))?;
+            let Ok(sig) = Signature::from_der(signature) else {
+                return Ok(false);
+            };
+            Ok(key.verify_prehash(&hashed, &sig).is_ok())
+        }
+        other => Err(SealedError::UnsupportedFormat(format!("Public key algorithm {}", other))),
+    }
+}
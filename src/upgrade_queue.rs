@@ -0,0 +1,368 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use tracing::info;
+
+use crate::errors::{SealedError, SealedResult};
+use crate::ipfs::{IpfsConfig, IpfsProvider};
+use crate::json_file::{load_or_default, save_pretty};
+use crate::pinning::{default_profiles_path, resolve_config};
+use crate::timestamp::{mark_confirmed, repin_with_ots, try_upgrade_ots, CalendarConfig};
+
+/// File name of the pending-upgrade queue inside the sealed home directory.
+pub const QUEUE_FILE: &str = "ots_queue.json";
+
+/// Retry delays in seconds, indexed by attempt; the last one repeats forever.
+const BACKOFF_SECS: &[i64] = &[30, 60, 120, 300, 600, 900, 1800, 3600];
+
+/// A lock file untouched for this long, whose worker is gone, was left behind.
+const STALE_LOCK: Duration = Duration::from_secs(600);
+
+/// Upper bound on how long a worker sleeps between queue passes.
+const MAX_IDLE: Duration = Duration::from_secs(60);
+
+/// Default queue location: `<sealed home>/ots_queue.json`.
+pub fn default_queue_path() -> PathBuf {
+    crate::paths::sealed_home().join(QUEUE_FILE)
+}
+
+/// One sealed directory whose .ots proof still awaits a Bitcoin attestation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueEntry {
+    pub hash: String,
+    /// Absolute path of the sealed directory holding `timestamp.ots`.
+    pub dir: PathBuf,
+    /// Calendars the proof was submitted to; upgrades may contact them.
+    pub calendars: Vec<String>,
+    pub attempts: u32,
+    pub next_retry: DateTime<Utc>,
+    pub added_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// IPFS API to re-pin the confirmed proof to. The API key is never stored here.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipfs_url: Option<String>,
+    /// API that `ipfs_url` speaks; absent in older entries, which meant Kubo.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipfs_provider: Option<IpfsProvider>,
+    /// Named IPFS profile to re-pin with; takes precedence over `ipfs_url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipfs_profile: Option<String>,
+}
+
+impl QueueEntry {
+    /// A new entry; `ipfs` records where to re-pin, but not its credentials.
+    pub fn new(hash: &str, dir: &Path, calendars: Vec<String>, ipfs: Option<&IpfsConfig>) -> Self {
+        let now = Utc::now();
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        Self {
+            hash: hash.to_string(),
+            dir,
+            calendars,
+            attempts: 0,
+            next_retry: now + chrono::Duration::seconds(BACKOFF_SECS[0]),
+            added_at: now,
+            last_error: None,
+            ipfs_url: ipfs.map(|c| c.api_url.clone()),
+            ipfs_provider: ipfs.map(|c| c.provider),
+            ipfs_profile: None,
+        }
+    }
+
+    /// Calendar settings for upgrading this entry: `base`, plus the calendars
+    /// the proof was actually submitted to.
+    pub fn calendar_config(&self, base: &CalendarConfig) -> CalendarConfig {
+        let mut config = base.clone();
+        for calendar in &self.calendars {
+            if !config.calendars.contains(calendar) {
+                config.calendars.push(calendar.clone());
+            }
+        }
+        config
+    }
+
+    fn schedule_retry(&mut self, error: Option<String>) {
+        let delay = BACKOFF_SECS[(self.attempts as usize).min(BACKOFF_SECS.len() - 1)];
+        self.attempts += 1;
+        self.next_retry = Utc::now() + chrono::Duration::seconds(delay);
+        self.last_error = error;
+    }
+}
+
+/// Durable list of pending OTS upgrades.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpgradeQueue {
+    pub entries: Vec<QueueEntry>,
+}
+
+impl UpgradeQueue {
+    /// Load the queue, treating a missing file as empty.
+    pub fn load(path: &Path) -> SealedResult<Self> {
+        load_or_default(path)
+    }
+
+    /// Write the queue atomically, creating the parent directory.
+    pub fn save(&self, path: &Path) -> SealedResult<()> {
+        save_pretty(path, self)
+    }
+
+    /// Add an entry, replacing any existing one for the same directory.
+    pub fn push(&mut self, entry: QueueEntry) {
+        self.entries.retain(|e| e.dir != entry.dir);
+        self.entries.push(entry);
+    }
+
+    /// Earliest scheduled retry, if anything is pending.
+    pub fn next_due(&self) -> Option<DateTime<Utc>> {
+        self.entries.iter().map(|e| e.next_retry).min()
+    }
+}
+
+/// Append one entry to the queue file.
+pub fn enqueue(queue_path: &Path, entry: QueueEntry) -> SealedResult<()> {
+    let mut queue = UpgradeQueue::load(queue_path)?;
+    queue.push(entry);
+    queue.save(queue_path)
+}
+
+/// Outcome of one pass over the queue.
+#[derive(Debug, Clone, Default)]
+pub struct PassSummary {
+    pub confirmed: Vec<PathBuf>,
+    pub dropped: Vec<PathBuf>,
+    pub still_pending: usize,
+}
+
+/// Pinning settings for re-pinning a confirmed entry, if it asked for one.
+///
+/// Only a profile carries credentials; the worker draining the queue may not
+/// be the process that queued the entry, so it never has a key of its own.
+fn repin_config(entry: &QueueEntry) -> Option<SealedResult<IpfsConfig>> {
+    match (&entry.ipfs_profile, &entry.ipfs_url) {
+        (Some(name), _) => Some(resolve_config(&default_profiles_path(), Some(name), None, None)),
+        (None, Some(url)) => Some(match entry.ipfs_provider.unwrap_or_default() {
+            IpfsProvider::Kubo => Ok(IpfsConfig::kubo(url)),
+            provider => Err(SealedError::IpfsError(format!(
+                "{} at {} needs credentials; seal with --ipfs-profile to re-pin automatically",
+                provider.as_str(), url
+            ))),
+        }),
+        (None, None) => None,
+    }
+}
+
+/// Try every entry whose retry time has come (or every entry if `force`).
+///
+/// Confirmed proofs are marked in `timestamp_record.json`, re-pinned if the
+/// entry asks for it, and removed. Entries whose directory is gone are dropped.
+/// A held `lock` is refreshed before each entry, however long the pass takes.
+pub fn process_queue(
+    queue_path: &Path,
+    config: &CalendarConfig,
+    lock: Option<&QueueLock>,
+    force: bool,
+) -> SealedResult<PassSummary> {
+    let snapshot = UpgradeQueue::load(queue_path)?;
+    let now = Utc::now();
+    let mut summary = PassSummary::default();
+    let mut updated = Vec::new();
+
+    for mut entry in snapshot.entries {
+        if !force && entry.next_retry > now {
+            continue;
+        }
+        if let Some(lock) = lock {
+            lock.touch();
+        }
+        let ots_path = entry.dir.join("timestamp.ots");
+        if !ots_path.exists() {
+            info!("Dropping queue entry, proof is gone: {}", ots_path.display());
+            summary.dropped.push(entry.dir.clone());
+            updated.push((entry.dir, None));
+            continue;
+        }
+
+        match try_upgrade_ots(&ots_path, &entry.calendar_config(config)) {
+            Ok(true) => {
+                mark_confirmed(&entry.dir);
+                match repin_config(&entry) {
+                    Some(Ok(ipfs)) => repin_with_ots(&entry.dir, &ipfs),
+                    Some(Err(e)) => info!("Skipping IPFS re-pin for {}: {}", entry.dir.display(), e),
+                    None => {}
+                }
+                info!("OTS proof confirmed: {}", entry.dir.display());
+                summary.confirmed.push(entry.dir.clone());
+                updated.push((entry.dir, None));
+            }
+            Ok(false) => {
+                entry.schedule_retry(None);
+                updated.push((entry.dir.clone(), Some(entry)));
+            }
+            Err(e) => {
+                entry.schedule_retry(Some(e.to_string()));
+                updated.push((entry.dir.clone(), Some(entry)));
+            }
+        }
+    }
+
+    // Re-read before writing so entries enqueued during the pass are kept.
+    let mut queue = UpgradeQueue::load(queue_path)?;
+    for (dir, outcome) in updated {
+        match outcome {
+            Some(entry) => {
+                if let Some(slot) = queue.entries.iter_mut().find(|e| e.dir == dir) {
+                    *slot = entry;
+                }
+            }
+            None => queue.entries.retain(|e| e.dir != dir),
+        }
+    }
+    queue.save(queue_path)?;
+
+    summary.still_pending = queue.entries.len();
+    Ok(summary)
+}
+
+/// Exclusive right to drain a queue, held via a `.lock` file next to it.
+pub struct QueueLock {
+    path: PathBuf,
+}
+
+impl QueueLock {
+    /// Take the lock, or return `None` if a live worker holds it.
+    pub fn acquire(queue_path: &Path) -> SealedResult<Option<Self>> {
+        let path = queue_path.with_extension("lock");
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let untouched = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > STALE_LOCK);
+        if untouched && !holder_alive(&path) {
+            let _ = std::fs::remove_file(&path);
+        }
+
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                use std::io::Write;
+                let _ = write!(file, "{}", std::process::id());
+                Ok(Some(Self { path }))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Refresh the lock's mtime so other workers don't consider it stale.
+    pub fn touch(&self) {
+        let _ = std::fs::write(&self.path, std::process::id().to_string());
+    }
+}
+
+/// Whether the worker whose PID is in the lock file is still running.
+/// Only Linux can tell; elsewhere an untouched lock counts as abandoned.
+fn holder_alive(path: &Path) -> bool {
+    let pid = match std::fs::read_to_string(path).ok().and_then(|s| s.trim().parse::<u32>().ok()) {
+        Some(pid) => pid,
+        None => return false,
+    };
+    cfg!(target_os = "linux") && Path::new(&format!("/proc/{}", pid)).exists()
+}
+
+impl Drop for QueueLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Drain the queue, sleeping until the next retry between passes.
+///
+/// With `until_empty` the worker returns once nothing is pending (or another
+/// worker holds the lock); otherwise it runs forever, as `serve` does.
+pub fn run_queue_worker(
+    queue_path: &Path,
+    config: &CalendarConfig,
+    until_empty: bool,
+) -> SealedResult<()> {
+    let lock = loop {
+        match QueueLock::acquire(queue_path)? {
+            Some(lock) => break lock,
+            None if until_empty => {
+                info!("Another OTS upgrade worker is draining {}", queue_path.display());
+                return Ok(());
+            }
+            None => std::thread::sleep(MAX_IDLE),
+        }
+    };
+
+    loop {
+        lock.touch();
+        match process_queue(queue_path, config, Some(&lock), false) {
+            Ok(summary) => {
+                if summary.still_pending == 0 && until_empty {
+                    return Ok(());
+                }
+            }
+            Err(e) => info!("OTS queue pass failed: {}", e),
+        }
+
+        let wait = UpgradeQueue::load(queue_path)
+            .ok()
+            .and_then(|q| q.next_due())
+            .and_then(|due| (due - Utc::now()).to_std().ok())
+            .unwrap_or(Duration::ZERO)
+            .clamp(Duration::from_secs(1), MAX_IDLE);
+        std::thread::sleep(wait);
+    }
+}
+
+/// Spawn a detached `ots-upgrade --all` worker so a fresh seal gets upgraded
+/// without further action. The queue survives if the worker dies.
+pub fn spawn_queue_worker(queue_path: &Path) {
+    let exe = match std::env::current_exe() {
+        Ok(p) => p,
+        Err(e) => {
+            info!("OTS upgrade: could not determine executable path: {}", e);
+            return;
+        }
+    };
+
+    let log_path = queue_path.with_file_name("ots_upgrade.log");
+    let log_file = match std::fs::OpenOptions::new().create(true).append(true).open(&log_path) {
+        Ok(f) => f,
+        Err(e) => {
+            info!("OTS upgrade: could not open log file: {}", e);
+            return;
+        }
+    };
+
+    let mut cmd = std::process::Command::new(exe);
+    cmd.arg("ots-upgrade").arg("--all").arg("--queue").arg(queue_path);
+
+    let stdout = match log_file.try_clone() {
+        Ok(f) => std::process::Stdio::from(f),
+        Err(_) => std::process::Stdio::null(),
+    };
+    cmd.stdin(std::process::Stdio::null())
+        .stdout(stdout)
+        .stderr(std::process::Stdio::from(log_file));
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
+        const DETACHED_PROCESS: u32 = 0x00000008;
+        cmd.creation_flags(CREATE_NEW_PROCESS_GROUP | DETACHED_PROCESS);
+    }
+
+    match cmd.spawn() {
+        Ok(child) => {
+            info!("OTS upgrade worker spawned (pid {})", child.id());
+            drop(child);
+        }
+        Err(e) => info!("OTS upgrade: failed to spawn background process: {}", e),
+    }
+}
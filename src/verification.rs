@@ -5,19 +5,433 @@ use tracing::info;
 use crate::errors::{SealedError, SealedResult};
 use crate::hashing::{compute_hash_record, compare_hashes, HashRecord, SimilarityReport, SimilarityConfidence};
 use crate::image_processing::open_image_by_content;
-use crate::signing::SignedEnvelope;
+use crate::integrity::{check_artifacts, IntegrityReport};
+use crate::ots::{verify_proof, BlockHeader, DetachedTimestampFile};
+use crate::signing::{key_id_from_base64, SignedEnvelope};
+use crate::trust::{KeyTrust, TrustStore};
 use crate::tile_hashing::{TileHashIndex, TileMatchResult, compare_against_tiles};
+use crate::timestamp::TSR_FILE;
+use crate::tsa::verify_token;
+use x509_cert::Certificate;
 
 /// Verification result for a suspect image against a sealed record.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VerificationResult {
     pub signature_valid: bool,
+    pub signature: SignatureStatus,
     pub vs_original: SimilarityReport,
     pub vs_cropped: SimilarityReport,
     pub tile_match: Option<TileMatchResult>,
     pub sealed_record: SealedRecord,
     pub suspect_hashes: HashRecord,
-    pub verdict: String,
+    pub verdict: Verdict,
+    /// The key that made a valid signature, and whether the trust store
+    /// knows it. `None` when unsigned or invalid.
+    #[serde(default)]
+    pub signer: Option<Signer>,
+    /// Keys behind valid co-signatures, judged separately.
+    #[serde(default)]
+    pub cosigners: Vec<Signer>,
+    /// Key IDs of co-signatures that do not verify.
+    #[serde(default)]
+    pub invalid_cosignatures: Vec<String>,
+    /// Outcome of an "M of N" signer policy, when one was applied.
+    #[serde(default)]
+    pub policy: Option<PolicyOutcome>,
+    /// When a timestamp independently proves the record existed. Key
+    /// revocations and validity periods are judged against this, not
+    /// against `sealed_at`, which the signer writes.
+    #[serde(default)]
+    pub proven_time: Option<ProvenTime>,
+    /// Whether the sealed directory's artifacts match its signed record.
+    /// `None` if it holds no artifacts or the check could not run.
+    #[serde(default)]
+    pub integrity: Option<IntegrityReport>,
+}
+
+/// A time by which a record provably existed, from evidence the signer
+/// cannot forge.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvenTime {
+    /// RFC 3339.
+    pub time: String,
+    /// What vouches for it, e.g. "RFC 3161 token from CN=..." or "Bitcoin block 800000".
+    pub source: String,
+}
+
+/// What `verify` checks a sealed directory's timestamps against.
+#[derive(Debug, Default)]
+pub struct TimeAnchors {
+    /// Trust anchor for the RFC 3161 token in `timestamp.tsr`.
+    pub tsa_anchor: Option<Certificate>,
+    /// Header of the block an OpenTimestamps proof is anchored in.
+    pub block_header: Option<BlockHeader>,
+}
+
+/// The key behind a valid signature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Signer {
+    /// Ed25519 public key, base64.
+    pub public_key: String,
+    /// See [`crate::signing::key_id`].
+    #[serde(default)]
+    pub key_id: String,
+    pub trust: KeyTrust,
+}
+
+/// Co-signing requirement: at least `required` distinct signers the trust
+/// store trusts, drawn from `signers` (trust store names or key IDs) when
+/// that list is given.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignerPolicy {
+    pub required: usize,
+    #[serde(default)]
+    pub signers: Vec<String>,
+}
+
+impl SignerPolicy {
+    pub fn new(required: usize, signers: Vec<String>) -> SealedResult<Self> {
+        if required == 0 {
+            return Err(SealedError::InvalidInput("A signer policy needs at least one required signer".to_string()));
+        }
+        if !signers.is_empty() && required > signers.len() {
+            return Err(SealedError::InvalidInput(format!(
+                "Cannot require {} signers out of {} named",
+                required,
+                signers.len()
+            )));
+        }
+        Ok(Self { required, signers })
+    }
+}
+
+/// How a record fared against a [`SignerPolicy`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyOutcome {
+    pub required: usize,
+    /// Number of named signers, or `None` when any trusted key counts.
+    pub of: Option<usize>,
+    /// Names of the trusted signers that counted.
+    pub satisfied_by: Vec<String>,
+    pub satisfied: bool,
+}
+
+impl std::fmt::Display for PolicyOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let policy = match self.of {
+            Some(n) => format!("{} of {} named signers", self.required, n),
+            None => format!("{} trusted signers", self.required),
+        };
+        let by = if self.satisfied_by.is_empty() { "none".to_string() } else { self.satisfied_by.join(", ") };
+        if self.satisfied {
+            write!(f, "Signer policy ({}) met by {}.", policy, by)
+        } else {
+            write!(f, "Signer policy ({}) NOT met; counted: {}.", policy, by)
+        }
+    }
+}
+
+impl VerificationResult {
+    /// Judge the signing keys against `store`. A primary key the store
+    /// trusts makes the signature trusted; a revoked or out-of-period key
+    /// makes it merely valid, even if it was also pinned with `--public-key`.
+    /// Co-signers are judged on their own and leave the status alone.
+    /// Revocations and validity periods are judged by [`Self::proven_time`];
+    /// without one, a key with either is not trusted.
+    pub fn apply_trust(&mut self, store: &TrustStore) {
+        let sealed_at = &self.sealed_record.sealed_at;
+        let proven = self.proven_time.as_ref().map(|p| p.time.as_str());
+        for cosigner in &mut self.cosigners {
+            cosigner.trust = store.assess_seal(&cosigner.public_key, sealed_at, proven);
+        }
+        let Some(signer) = self.signer.as_mut() else { return };
+        signer.trust = store.assess_seal(&signer.public_key, sealed_at, proven);
+        self.signature = match signer.trust {
+            KeyTrust::Trusted { .. } => SignatureStatus::Trusted,
+            KeyTrust::Revoked { .. } | KeyTrust::OutsideValidity { .. } => SignatureStatus::Valid,
+            KeyTrust::Unknown => self.signature,
+        };
+    }
+
+    /// Count trusted signers against `policy`. Call after [`Self::apply_trust`].
+    pub fn apply_policy(&mut self, policy: &SignerPolicy) {
+        let satisfied_by: Vec<String> = self
+            .signers()
+            .filter_map(|s| match &s.trust {
+                KeyTrust::Trusted { name }
+                    if policy.signers.is_empty() || policy.signers.iter().any(|p| *p == *name || *p == s.key_id) =>
+                {
+                    Some(name.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        self.policy = Some(PolicyOutcome {
+            required: policy.required,
+            of: (!policy.signers.is_empty()).then_some(policy.signers.len()),
+            satisfied: satisfied_by.len() >= policy.required,
+            satisfied_by,
+        });
+    }
+
+    /// The primary signer, then the co-signers.
+    pub fn signers(&self) -> impl Iterator<Item = &Signer> {
+        self.signer.iter().chain(&self.cosigners)
+    }
+
+    /// Whether the trust store has revoked any signing key, or the record
+    /// falls outside one's validity period.
+    pub fn signer_distrusted(&self) -> bool {
+        self.signers().any(|s| matches!(s.trust, KeyTrust::Revoked { .. } | KeyTrust::OutsideValidity { .. }))
+    }
+
+    /// Whether a co-signature fails to verify or the signer policy is not met.
+    pub fn cosigning_failed(&self) -> bool {
+        !self.invalid_cosignatures.is_empty() || self.policy.as_ref().is_some_and(|p| !p.satisfied)
+    }
+
+    fn cosigner_note(&self) -> Option<String> {
+        if self.cosigners.is_empty() && self.invalid_cosignatures.is_empty() {
+            return None;
+        }
+        let mut parts: Vec<String> = self
+            .cosigners
+            .iter()
+            .map(|c| match &c.trust {
+                KeyTrust::Trusted { name } => format!("{} (trusted)", name),
+                KeyTrust::Revoked { name, .. } => format!("{} (REVOKED)", name),
+                KeyTrust::OutsideValidity { name } => format!("{} (outside validity period)", name),
+                KeyTrust::Unknown => format!("unknown key {}", c.key_id),
+            })
+            .collect();
+        parts.extend(self.invalid_cosignatures.iter().map(|id| format!("key {} (INVALID co-signature)", id)));
+        Some(format!("Co-signed by {}.", parts.join(", ")))
+    }
+
+    fn signature_note(&self) -> String {
+        let note = self.signer_note();
+        if self.proven_time.is_none() && self.signer_distrusted() {
+            format!("{} No verified timestamp shows when it was sealed.", note)
+        } else {
+            note
+        }
+    }
+
+    fn signer_note(&self) -> String {
+        match (&self.signer, self.signature) {
+            (Some(Signer { trust: KeyTrust::Trusted { name }, .. }), _) => format!("Signed by {} (trusted).", name),
+            (Some(Signer { trust: KeyTrust::Revoked { name, .. }, .. }), _) => {
+                format!("Valid signature by {}, whose key is REVOKED.", name)
+            }
+            (Some(Signer { trust: KeyTrust::OutsideValidity { name }, .. }), _) => {
+                format!("Valid signature by {}, made outside the key's validity period.", name)
+            }
+            (Some(_), SignatureStatus::Valid) => "Valid signature by unknown key.".to_string(),
+            (_, status) => status.note().to_string(),
+        }
+    }
+
+    /// The verdict, signature status and artifact check as English sentences.
+    pub fn summary(&self) -> String {
+        let mut summary = format!("{} {}", self.verdict, self.signature_note());
+        if let Some(note) = self.cosigner_note() {
+            summary.push_str(&format!(" {}", note));
+        }
+        if let Some(policy) = &self.policy {
+            summary.push_str(&format!(" {}", policy));
+        }
+        if self.integrity.as_ref().is_some_and(|i| !i.is_intact()) {
+            summary.push_str(" Sealed artifacts do NOT match the record.");
+        }
+        summary
+    }
+}
+
+/// What the suspect was found to be, with the evidence for it. Says nothing
+/// about who sealed the record; see [`SignatureStatus`] for that.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Verdict {
+    /// Pixel-identical to the sealed original.
+    ExactOriginal { sha256: String },
+    /// Pixel-identical to the sealed share (or cropped) version.
+    ExactShare { sha256: String },
+    /// A region of the sealed original, found by tile hashing.
+    CropDetected {
+        tiles_checked: usize,
+        consistent_votes: usize,
+        total_matches: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        offset: Option<(i32, i32)>,
+        /// Whole-image comparison against the matching region, if it was refined.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        refined: Option<SimilarityReport>,
+    },
+    /// Perceptually similar; distances are the best over original and share.
+    Perceptual {
+        level: SimilarityConfidence,
+        ahash_hamming: u32,
+        dhash_hamming: u32,
+        phash_hamming: u32,
+    },
+    /// Nothing matched; distances are the best over original and share.
+    NoMatch {
+        ahash_hamming: u32,
+        dhash_hamming: u32,
+        phash_hamming: u32,
+    },
+}
+
+impl Verdict {
+    /// Classify comparisons against the original, the share and the tile
+    /// index. Exact matches win, then crops, then perceptual similarity.
+    pub fn from_comparisons(
+        vs_original: &SimilarityReport,
+        vs_share: &SimilarityReport,
+        tile_match: Option<&TileMatchResult>,
+        suspect: &HashRecord,
+    ) -> Verdict {
+        if vs_original.exact_match {
+            return Verdict::ExactOriginal { sha256: suspect.sha256.clone() };
+        }
+        if vs_share.exact_match {
+            return Verdict::ExactShare { sha256: suspect.sha256.clone() };
+        }
+        if let Some(tm) = tile_match.filter(|tm| tm.crop_detected) {
+            return Verdict::CropDetected {
+                tiles_checked: tm.tiles_checked,
+                consistent_votes: tm.consistent_votes,
+                total_matches: tm.total_matches,
+                offset: tm.estimated_offset,
+                refined: tm.refined_similarity.clone(),
+            };
+        }
+
+        let ahash_hamming = vs_original.ahash_hamming.min(vs_share.ahash_hamming);
+        let dhash_hamming = vs_original.dhash_hamming.min(vs_share.dhash_hamming);
+        let phash_hamming = vs_original.phash_hamming.min(vs_share.phash_hamming);
+        let level = if vs_original.confidence.rank() >= vs_share.confidence.rank() {
+            vs_original.confidence
+        } else {
+            vs_share.confidence
+        };
+        match level {
+            SimilarityConfidence::None => Verdict::NoMatch { ahash_hamming, dhash_hamming, phash_hamming },
+            level => Verdict::Perceptual { level, ahash_hamming, dhash_hamming, phash_hamming },
+        }
+    }
+
+    /// Short name, as used in the JSON `kind` field.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Verdict::ExactOriginal { .. } => "exact-original",
+            Verdict::ExactShare { .. } => "exact-share",
+            Verdict::CropDetected { .. } => "crop-detected",
+            Verdict::Perceptual { .. } => "perceptual",
+            Verdict::NoMatch { .. } => "no-match",
+        }
+    }
+
+    /// Whether the suspect matched the sealed content in any way.
+    pub fn is_match(&self) -> bool {
+        !matches!(self, Verdict::NoMatch { .. })
+    }
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::ExactOriginal { .. } => {
+                write!(f, "EXACT MATCH: Suspect image is byte-identical to the sealed original.")
+            }
+            Verdict::ExactShare { .. } => write!(
+                f,
+                "EXACT MATCH (CROPPED): Suspect image matches the sealed cropped/share version."
+            ),
+            Verdict::CropDetected { tiles_checked, consistent_votes, total_matches, offset, refined } => {
+                write!(
+                    f,
+                    "SUB-REGION CROP DETECTED: Suspect image matches a region of the sealed original \
+                     ({} blocks checked, {} consistent votes).",
+                    tiles_checked, consistent_votes
+                )?;
+                if let Some((dx, dy)) = offset {
+                    write!(f, " Estimated offset: ({}, {}).", dx, dy)?;
+                }
+                match refined {
+                    Some(r) => write!(
+                        f,
+                        " Refined comparison: {} confidence (aHash={}, dHash={}, pHash={}).",
+                        r.confidence, r.ahash_hamming, r.dhash_hamming, r.phash_hamming
+                    ),
+                    None => write!(f, " {} consistent block votes, {} total matches.", consistent_votes, total_matches),
+                }
+            }
+            Verdict::Perceptual { level, ahash_hamming, dhash_hamming, phash_hamming } => {
+                let meaning = match level {
+                    SimilarityConfidence::High => "is very likely derived from the sealed content",
+                    SimilarityConfidence::Medium => "appears visually similar to sealed content",
+                    _ => "has loose visual similarity to sealed content",
+                };
+                write!(
+                    f,
+                    "PERCEPTUALLY SIMILAR ({} confidence): Suspect image {} \
+                     (best hamming: aHash={}, dHash={}, pHash={}).",
+                    level, meaning, ahash_hamming, dhash_hamming, phash_hamming
+                )?;
+                if *level == SimilarityConfidence::Low {
+                    write!(f, " May be coincidental.")?;
+                }
+                Ok(())
+            }
+            Verdict::NoMatch { ahash_hamming, dhash_hamming, phash_hamming } => write!(
+                f,
+                "NO MATCH: Suspect image does not appear to match the sealed content \
+                 (best hamming: aHash={}, dHash={}, pHash={}).",
+                ahash_hamming, dhash_hamming, phash_hamming
+            ),
+        }
+    }
+}
+
+/// State of the signature on a sealed record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureStatus {
+    /// Valid, and made by the public key the verifier supplied.
+    Trusted,
+    /// Valid against the key carried in the record itself. Proves the record
+    /// is intact, not who signed it.
+    Valid,
+    /// Present but does not verify, or signs a different record.
+    Invalid,
+    Unsigned,
+}
+
+impl SignatureStatus {
+    pub fn is_valid(self) -> bool {
+        matches!(self, SignatureStatus::Trusted | SignatureStatus::Valid)
+    }
+
+    fn note(self) -> &'static str {
+        match self {
+            SignatureStatus::Trusted => "Sealed record signature is VALID (trusted key).",
+            SignatureStatus::Valid => "Sealed record signature is VALID (key not pinned).",
+            SignatureStatus::Invalid => "Sealed record signature is INVALID.",
+            SignatureStatus::Unsigned => "No valid signature found on sealed record.",
+        }
+    }
+}
+
+impl std::fmt::Display for SignatureStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureStatus::Trusted => write!(f, "trusted"),
+            SignatureStatus::Valid => write!(f, "valid"),
+            SignatureStatus::Invalid => write!(f, "invalid"),
+            SignatureStatus::Unsigned => write!(f, "unsigned"),
+        }
+    }
 }
 
 /// Sealed record as stored in hashes.json.
@@ -37,11 +451,57 @@ pub struct SealedRecord {
     pub sealed_version: String,
 }
 
+/// The earliest time the timestamps in `sealed_dir` prove `sha256_hex`
+/// existed: an RFC 3161 token that verifies against `anchors.tsa_anchor`,
+/// or an OpenTimestamps attestation confirmed by `anchors.block_header`.
+/// Proofs that are missing or do not verify are skipped.
+pub fn proven_sealing_time(sealed_dir: &Path, sha256_hex: &str, anchors: &TimeAnchors) -> Option<ProvenTime> {
+    let mut proofs = Vec::new();
+    let tsr_path = sealed_dir.join(TSR_FILE);
+    if let (Some(anchor), true) = (&anchors.tsa_anchor, tsr_path.exists()) {
+        match std::fs::read(&tsr_path).map_err(SealedError::from).and_then(|tsr| verify_token(&tsr, sha256_hex, anchor)) {
+            Ok(token) if token.is_verified() => {
+                proofs.push(ProvenTime { time: token.gen_time, source: format!("RFC 3161 token from {}", token.signer) });
+            }
+            Ok(_) => info!("{} does not verify against the TSA trust anchor", tsr_path.display()),
+            Err(e) => info!("Could not check {}: {}", tsr_path.display(), e),
+        }
+    }
+    let ots_path = sealed_dir.join("timestamp.ots");
+    if let (Some(header), true) = (&anchors.block_header, ots_path.exists()) {
+        match DetachedTimestampFile::load(&ots_path).and_then(|proof| verify_proof(&proof, sha256_hex, Some(header))) {
+            Ok(result) if result.digest_matches => {
+                proofs.extend(result.bitcoin.into_iter().filter_map(|check| {
+                    check.block_time.map(|time| ProvenTime { time, source: format!("Bitcoin block {}", check.height) })
+                }));
+            }
+            Ok(_) => info!("{} does not commit to the sealed hash", ots_path.display()),
+            Err(e) => info!("Could not check {}: {}", ots_path.display(), e),
+        }
+    }
+    proofs
+        .into_iter()
+        .filter_map(|p| chrono::DateTime::parse_from_rfc3339(&p.time).ok().map(|t| (t, p)))
+        .min_by_key(|(t, _)| *t)
+        .map(|(_, p)| p)
+}
+
 /// Verify a suspect image against a sealed record directory.
 pub fn verify_image(
     suspect_path: &Path,
     sealed_dir: &Path,
     public_key_path: Option<&Path>,
+) -> SealedResult<VerificationResult> {
+    verify_image_with(suspect_path, sealed_dir, public_key_path, true)
+}
+
+/// `verify_image`, optionally without checking the sealed artifacts. Only a
+/// record fetched by CID, which never carries them all, skips that check.
+pub fn verify_image_with(
+    suspect_path: &Path,
+    sealed_dir: &Path,
+    public_key_path: Option<&Path>,
+    check_integrity: bool,
 ) -> SealedResult<VerificationResult> {
     if !suspect_path.exists() {
         return Err(SealedError::FileNotFound(suspect_path.display().to_string()));
@@ -60,50 +520,7 @@ pub fn verify_image(
     let record_json = std::fs::read_to_string(&record_path)?;
     let sealed_record: SealedRecord = serde_json::from_str(&record_json)?;
 
-    let signed_path = sealed_dir.join("signed_record.json");
-    let signature_valid = if signed_path.exists() {
-        let signed_json = std::fs::read_to_string(&signed_path)?;
-        let envelope: SignedEnvelope = serde_json::from_str(&signed_json)?;
-        let result = match public_key_path {
-            Some(pk_path) => {
-                info!("Verifying signature against trusted public key: {}", pk_path.display());
-                envelope.verify_with_key(pk_path)
-            }
-            None => envelope.verify(),
-        };
-        match result {
-            Ok(()) => {
-                // Verify that the signed payload actually matches the hashes.json content.
-                // Without this check, someone could have a valid signature over a different
-                // hash record than the one stored in the sealed directory.
-                let payload_record: Result<SealedRecord, _> = serde_json::from_str(&envelope.payload);
-                match payload_record {
-                    Ok(signed_record) => {
-                        if signed_record.original.sha256 != sealed_record.original.sha256
-                            || signed_record.original.blake3 != sealed_record.original.blake3
-                        {
-                            info!("Signature valid but payload doesn't match hashes.json");
-                            false
-                        } else {
-                            info!("Signature verified (payload matches hashes.json)");
-                            true
-                        }
-                    }
-                    Err(e) => {
-                        info!("Digital signature valid but signed payload could not be parsed: {}", e);
-                        false
-                    }
-                }
-            }
-            Err(e) => {
-                info!("Digital signature verification FAILED: {}", e);
-                false
-            }
-        }
-    } else {
-        info!("No digital signature found in sealed directory");
-        false
-    };
+    let signature = record_signature_status(sealed_dir, &sealed_record, public_key_path)?;
 
     let suspect_img = open_image_by_content(suspect_path)?;
     let suspect_hashes = compute_hash_record(&suspect_img)?;
@@ -144,21 +561,127 @@ pub fn verify_image(
         None
     };
 
-    let verdict = generate_verdict(&vs_original, &vs_cropped, tile_match.as_ref(), signature_valid);
-
-    info!("Verification complete: {}", verdict);
+    let verdict = Verdict::from_comparisons(&vs_original, &vs_cropped, tile_match.as_ref(), &suspect_hashes);
+    // Deleted artifacts are reported as missing, however many are gone.
+    let integrity = if check_integrity {
+        match check_artifacts(sealed_dir) {
+            Ok(report) => Some(report),
+            Err(e) => {
+                info!("Could not check sealed artifacts: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
 
-    Ok(VerificationResult {
-        signature_valid,
+    let (signer, cosigners, invalid_cosignatures) = if signature.is_valid() {
+        record_signers(sealed_dir)
+    } else {
+        (None, Vec::new(), Vec::new())
+    };
+    let result = VerificationResult {
+        signature_valid: signature.is_valid(),
+        signature,
         vs_original,
         vs_cropped,
         tile_match,
         sealed_record,
         suspect_hashes,
         verdict,
+        signer,
+        cosigners,
+        invalid_cosignatures,
+        policy: None,
+        proven_time: None,
+        integrity,
+    };
+    info!("Verification complete: {}", result.summary());
+    Ok(result)
+}
+
+/// Check `signed_record.json` in `sealed_dir` against `record`, by
+/// `public_key_path` if given.
+pub fn record_signature_status(
+    sealed_dir: &Path,
+    record: &SealedRecord,
+    public_key_path: Option<&Path>,
+) -> SealedResult<SignatureStatus> {
+    let signed_path = sealed_dir.join("signed_record.json");
+    let valid = if public_key_path.is_some() { SignatureStatus::Trusted } else { SignatureStatus::Valid };
+    Ok(if signed_path.exists() {
+        let signed_json = std::fs::read_to_string(&signed_path)?;
+        let envelope: SignedEnvelope = serde_json::from_str(&signed_json)?;
+        // The primary signature must hold whoever else signed; the pinned key
+        // may then be the primary signer or a co-signer.
+        let result = envelope.verify().and_then(|()| match public_key_path {
+            Some(pk_path) => {
+                info!("Verifying signature against trusted public key: {}", pk_path.display());
+                envelope.verify_with_key(pk_path).or_else(|e| envelope.verify_cosignature_with_key(pk_path).map_err(|_| e))
+            }
+            None => Ok(()),
+        });
+        match result {
+            Ok(()) => {
+                // Verify that the signed payload actually matches the hashes.json content,
+                // field for field. Without this check, someone could have a valid signature
+                // over a different hash record than the one stored in the sealed directory.
+                let payload_record: Result<SealedRecord, _> = serde_json::from_str(&envelope.payload);
+                match payload_record {
+                    Ok(signed_record) => {
+                        if serde_json::to_value(&signed_record)? != serde_json::to_value(record)? {
+                            info!("Signature valid but payload doesn't match hashes.json");
+                            SignatureStatus::Invalid
+                        } else {
+                            info!("Signature verified (payload matches hashes.json)");
+                            valid
+                        }
+                    }
+                    Err(e) => {
+                        info!("Digital signature valid but signed payload could not be parsed: {}", e);
+                        SignatureStatus::Invalid
+                    }
+                }
+            }
+            Err(e) => {
+                info!("Digital signature verification FAILED: {}", e);
+                SignatureStatus::Invalid
+            }
+        }
+    } else {
+        info!("No digital signature found in sealed directory");
+        SignatureStatus::Unsigned
     })
 }
 
+/// The keys behind the signatures in `signed_record.json`: the primary
+/// signer if its signature verifies, co-signers whose signatures verify, and
+/// the key IDs of co-signatures that do not.
+pub fn record_signers(sealed_dir: &Path) -> (Option<Signer>, Vec<Signer>, Vec<String>) {
+    let envelope = std::fs::read_to_string(sealed_dir.join("signed_record.json"))
+        .ok()
+        .and_then(|json| serde_json::from_str::<SignedEnvelope>(&json).ok());
+    let Some(envelope) = envelope else { return (None, Vec::new(), Vec::new()) };
+    let signer = |public_key: &str| Signer {
+        public_key: public_key.to_string(),
+        key_id: key_id_from_base64(public_key).unwrap_or_default(),
+        trust: KeyTrust::Unknown,
+    };
+
+    let primary = envelope.verify().is_ok().then(|| signer(&envelope.public_key));
+    let (mut cosigners, mut invalid) = (Vec::new(), Vec::new());
+    for (cosignature, result) in envelope.cosignatures.iter().zip(envelope.verify_cosignatures()) {
+        match result {
+            Ok(()) => cosigners.push(signer(&cosignature.public_key)),
+            Err(e) => {
+                info!("Co-signature by {} does not verify: {}", cosignature.key_id, e);
+                invalid.push(cosignature.key_id.clone());
+            }
+        }
+    }
+    (primary, cosigners, invalid)
+}
+
 /// Verify a suspect image directly against a HashRecord.
 pub fn verify_against_record(
     suspect_path: &Path,
@@ -175,100 +698,3 @@ pub fn verify_against_record(
 
     Ok((vs_original, vs_cropped))
 }
-
-fn generate_verdict(
-    vs_original: &SimilarityReport,
-    vs_cropped: &SimilarityReport,
-    tile_match: Option<&TileMatchResult>,
-    signature_valid: bool,
-) -> String {
-    if vs_original.exact_match {
-        return format!(
-            "EXACT MATCH: Suspect image is byte-identical to the sealed original. {}",
-            sig_note(signature_valid)
-        );
-    }
-
-    if vs_cropped.exact_match {
-        return format!(
-            "EXACT MATCH (CROPPED): Suspect image matches the sealed cropped/share version. {}",
-            sig_note(signature_valid)
-        );
-    }
-
-    let best_confidence = best_of(vs_original.confidence, vs_cropped.confidence);
-    let best_ahash = vs_original.ahash_hamming.min(vs_cropped.ahash_hamming);
-    let best_dhash = vs_original.dhash_hamming.min(vs_cropped.dhash_hamming);
-    let best_phash = vs_original.phash_hamming.min(vs_cropped.phash_hamming);
-
-    if let Some(tm) = tile_match {
-        if tm.crop_detected {
-            let offset_info = match tm.estimated_offset {
-                Some((dx, dy)) => format!("estimated offset: ({}, {})", dx, dy),
-                None => String::new(),
-            };
-            let refined_info = match &tm.refined_similarity {
-                Some(r) => format!(
-                    "Refined comparison: {:?} confidence (aHash={}, dHash={}, pHash={})",
-                    r.confidence, r.ahash_hamming, r.dhash_hamming, r.phash_hamming
-                ),
-                None => format!(
-                    "{} consistent block votes, {} total matches",
-                    tm.consistent_votes, tm.total_matches
-                ),
-            };
-            return format!(
-                "SUB-REGION CROP DETECTED: Suspect image matches a region of the sealed original \
-                 ({} blocks checked, {} consistent votes). {} — {} {}",
-                tm.tiles_checked, tm.consistent_votes, offset_info, refined_info, sig_note(signature_valid)
-            );
-        }
-    }
-
-    match best_confidence {
-        SimilarityConfidence::Exact => unreachable!(),
-        SimilarityConfidence::High => format!(
-            "PERCEPTUALLY SIMILAR (HIGH confidence): Suspect image is very likely derived from \
-             the sealed content (best hamming: aHash={}, dHash={}, pHash={}). {}",
-            best_ahash, best_dhash, best_phash, sig_note(signature_valid)
-        ),
-        SimilarityConfidence::Medium => format!(
-            "PERCEPTUALLY SIMILAR (MEDIUM confidence): Suspect image appears visually similar to \
-             sealed content (best hamming: aHash={}, dHash={}, pHash={}). {}",
-            best_ahash, best_dhash, best_phash, sig_note(signature_valid)
-        ),
-        SimilarityConfidence::Low => format!(
-            "PERCEPTUALLY SIMILAR (LOW confidence): Suspect image has loose visual similarity to \
-             sealed content (best hamming: aHash={}, dHash={}, pHash={}). May be coincidental. {}",
-            best_ahash, best_dhash, best_phash, sig_note(signature_valid)
-        ),
-        SimilarityConfidence::None => format!(
-            "NO MATCH: Suspect image does not appear to match the sealed content. \
-             aHash distance: {}/{}, dHash distance: {}/{}, pHash distance: {}/{}. {}",
-            vs_original.ahash_hamming, vs_cropped.ahash_hamming,
-            vs_original.dhash_hamming, vs_cropped.dhash_hamming,
-            vs_original.phash_hamming, vs_cropped.phash_hamming,
-            sig_note(signature_valid)
-        ),
-    }
-}
-
-/// Higher of two confidence levels.
-fn best_of(a: SimilarityConfidence, b: SimilarityConfidence) -> SimilarityConfidence {
-    let rank = |c: SimilarityConfidence| match c {
-        SimilarityConfidence::Exact => 4,
-        SimilarityConfidence::High => 3,
-        SimilarityConfidence::Medium => 2,
-        SimilarityConfidence::Low => 1,
-        SimilarityConfidence::None => 0,
-    };
-    if rank(a) >= rank(b) { a } else { b }
-}
-
-fn sig_note(valid: bool) -> &'static str {
-    if valid {
-        "Sealed record signature is VALID."
-    } else {
-        "No valid signature found on sealed record."
-    }
-}
@@ -52,7 +52,7 @@ pub fn process_video(
     let mut frame_files: Vec<_> = std::fs::read_dir(&frames_dir)?
         .filter_map(|e| e.ok())
         .map(|e| e.path())
-        .filter(|p| p.extension().map_or(false, |ext| ext == "png"))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "png"))
         .collect();
 
     frame_files.sort();
@@ -82,7 +82,7 @@ pub fn process_video(
     let mut xor_image: Option<DynamicImage> = None;
     for path in &frame_files {
         let img = ImageReader::open(path)
-            .map_err(|e| SealedError::Io(e))?
+            .map_err(SealedError::Io)?
             .decode()?;
 
         match xor_image {
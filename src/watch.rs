@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+
+use crate::errors::SealedResult;
+use crate::json_file::{load_or_default, save_pretty};
+
+/// Default state file name, kept inside the watched folder.
+pub const WATCH_STATE_FILE: &str = ".sealed-watch.json";
+
+/// What the watcher last did with one file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedFile {
+    pub size: u64,
+    /// Modification time in nanoseconds since the Unix epoch.
+    pub modified: u128,
+    /// SHA-256 of the file bytes, so a touched but unchanged file is not resealed.
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sealed_dir: Option<PathBuf>,
+    /// Why sealing failed; the file is retried only once it changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub processed_at: String,
+}
+
+/// Files already handled, keyed by file name. Persisted so a restart does
+/// not reseal anything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatchState {
+    pub files: BTreeMap<String, WatchedFile>,
+}
+
+impl WatchState {
+    /// Load the state; a missing file is an empty state.
+    pub fn load(path: &Path) -> SealedResult<Self> {
+        load_or_default(path)
+    }
+
+    /// Write atomically, creating the parent directory.
+    pub fn save(&self, path: &Path) -> SealedResult<()> {
+        save_pretty(path, self)
+    }
+}
+
+/// A file that has stopped changing and needs sealing.
+#[derive(Debug, Clone)]
+pub struct ReadyFile {
+    pub path: PathBuf,
+    pub name: String,
+    size: u64,
+    modified: u128,
+    sha256: String,
+}
+
+/// Size and mtime seen on the previous poll, and since when.
+#[derive(Debug, Clone, Copy)]
+struct Observation {
+    size: u64,
+    modified: u128,
+    since: Instant,
+}
+
+/// Polls one folder (not its subfolders) for new or changed files. Uses only
+/// `read_dir` and metadata, so it works the same on every platform.
+pub struct FolderWatcher {
+    dir: PathBuf,
+    state_path: PathBuf,
+    state: WatchState,
+    /// How long a file's size and mtime must hold still before it is ready.
+    settle: Duration,
+    filter: fn(&Path) -> bool,
+    pending: BTreeMap<String, Observation>,
+}
+
+impl FolderWatcher {
+    /// Watch `dir` for files accepted by `filter`, remembering progress in
+    /// `state_path`.
+    pub fn new(dir: &Path, state_path: &Path, settle: Duration, filter: fn(&Path) -> bool) -> SealedResult<Self> {
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            state_path: state_path.to_path_buf(),
+            state: WatchState::load(state_path)?,
+            settle,
+            filter,
+            pending: BTreeMap::new(),
+        })
+    }
+
+    pub fn state(&self) -> &WatchState {
+        &self.state
+    }
+
+    /// Whether some file was seen changing and is waiting to settle.
+    pub fn is_settling(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Look at the folder once. Returns files that are new or changed and
+    /// have held the same size and mtime since an earlier poll for at least
+    /// the settle time.
+    pub fn poll(&mut self) -> SealedResult<Vec<ReadyFile>> {
+        let now = Instant::now();
+        let mut seen = Vec::new();
+        let mut ready = Vec::new();
+
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&self.dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else { continue };
+            if name.starts_with('.') || !(self.filter)(&path) {
+                continue;
+            }
+            // The file may vanish or still be locked by the writer; try next poll.
+            let Ok(meta) = std::fs::metadata(&path) else { continue };
+            if !meta.is_file() {
+                continue;
+            }
+            let size = meta.len();
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos())
+                .unwrap_or(0);
+            seen.push(name.clone());
+
+            if self.state.files.get(&name).is_some_and(|f| f.size == size && f.modified == modified) {
+                self.pending.remove(&name);
+                continue;
+            }
+
+            let stable = match self.pending.get(&name) {
+                Some(o) if o.size == size && o.modified == modified => now.duration_since(o.since) >= self.settle,
+                _ => {
+                    self.pending.insert(name.clone(), Observation { size, modified, since: now });
+                    false
+                }
+            };
+            if !stable {
+                continue;
+            }
+            self.pending.remove(&name);
+
+            let Ok(bytes) = std::fs::read(&path) else { continue };
+            let sha256 = hex::encode(Sha256::digest(&bytes));
+            if let Some(previous) = self.state.files.get_mut(&name).filter(|f| f.sha256 == sha256) {
+                // Touched but identical: remember the new mtime and move on.
+                previous.size = size;
+                previous.modified = modified;
+                self.state.save(&self.state_path)?;
+                continue;
+            }
+            ready.push(ReadyFile { path, name, size, modified, sha256 });
+        }
+
+        self.pending.retain(|name, _| seen.contains(name));
+        Ok(ready)
+    }
+
+    /// Record the outcome of sealing `file` and persist the state.
+    pub fn record(&mut self, file: &ReadyFile, outcome: Result<PathBuf, String>) -> SealedResult<()> {
+        let (sealed_dir, error) = match outcome {
+            Ok(dir) => (Some(dir), None),
+            Err(e) => (None, Some(e)),
+        };
+        self.state.files.insert(
+            file.name.clone(),
+            WatchedFile {
+                size: file.size,
+                modified: file.modified,
+                sha256: file.sha256.clone(),
+                sealed_dir,
+                error,
+                processed_at: chrono::Utc::now().to_rfc3339(),
+            },
+        );
+        self.state.save(&self.state_path)
+    }
+}
@@ -3,24 +3,31 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use tiny_http::{Server, Request, Response, Header, Method, StatusCode};
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::image_processing::{seal_image, save_artifacts, open_image_by_content, SealConfig};
 use crate::hashing::HashRecord;
 use crate::tile_hashing::generate_tile_index;
 use crate::archive::create_archive;
 use crate::signing::SealedKeyPair;
+use crate::trust::{default_trust_store_path, TrustStore};
 use crate::verification::verify_image;
-use crate::ipfs::{pin_to_ipfs, IpfsConfig};
+use crate::errors::{SealedError, SealedResult};
+use crate::ipfs::{pin_to_ipfs, IpfsConfig, IpfsProvider};
 use crate::video::process_video;
 use crate::pdf::process_pdf;
+use crate::timestamp::CalendarConfig;
+use crate::upgrade_queue::run_queue_worker;
 
 /// Web server config.
 pub struct ServeConfig {
     pub port: u16,
     pub static_dir: PathBuf,
     pub uploads_dir: PathBuf,
-    pub key_path: Option<PathBuf>,
+    /// Key that signs each seal, loaded once at startup.
+    pub keypair: Option<SealedKeyPair>,
+    /// OTS upgrade queue to drain in the background, if any.
+    pub ots_queue: Option<PathBuf>,
 }
 
 /// Start the HTTP server.
@@ -36,6 +43,15 @@ pub fn run_server(config: ServeConfig) -> anyhow::Result<()> {
     fs::create_dir_all(&config.uploads_dir)?;
     fs::create_dir_all(&config.static_dir)?;
 
+    if let Some(queue) = config.ots_queue.clone() {
+        info!("OTS upgrade worker draining {}", queue.display());
+        std::thread::spawn(move || {
+            if let Err(e) = run_queue_worker(&queue, &CalendarConfig::default(), false) {
+                info!("OTS upgrade worker stopped: {}", e);
+            }
+        });
+    }
+
     let config = Arc::new(config);
 
     for request in server.incoming_requests() {
@@ -74,12 +90,13 @@ fn handle_request(request: Request, config: &ServeConfig) -> anyhow::Result<()>
 
 /// Handle POST /image, /video, /pdf.
 fn handle_seal(mut request: Request, config: &ServeConfig, file_type: &str) -> anyhow::Result<()> {
-    let ipfs_url = request.headers().iter()
-        .find(|h| h.field.equiv("X-IPFS-Url"))
-        .map(|h| h.value.as_str().to_string());
-    let ipfs_key = request.headers().iter()
-        .find(|h| h.field.equiv("X-IPFS-Key"))
-        .map(|h| h.value.as_str().to_string());
+    let ipfs_config = match ipfs_from_headers(&request) {
+        Ok(config) => config,
+        Err(e) => {
+            let body = serde_json::json!({ "error": e.to_string() }).to_string();
+            return respond_json(request, 400, &body);
+        }
+    };
 
     let mut body = Vec::new();
     request.as_reader().read_to_end(&mut body)?;
@@ -102,7 +119,7 @@ fn handle_seal(mut request: Request, config: &ServeConfig, file_type: &str) -> a
 
     info!("Upload saved: {} ({} bytes, type={})", input_path.display(), body.len(), file_type);
 
-    match seal_uploaded_file(&input_path, &upload_dir, config, file_type, ipfs_url, ipfs_key) {
+    match seal_uploaded_file(&input_path, &upload_dir, config, file_type, ipfs_config) {
         Ok(json) => respond_json(request, 200, &json),
         Err(e) => {
             info!("Seal error: {}", e);
@@ -111,8 +128,29 @@ fn handle_seal(mut request: Request, config: &ServeConfig, file_type: &str) -> a
     }
 }
 
+/// Pinning settings from the `X-IPFS-Url`, `X-IPFS-Provider` and `X-IPFS-Key`
+/// headers. Without a provider the URL is a Kubo node; a key never picks one.
+fn ipfs_from_headers(request: &Request) -> SealedResult<Option<IpfsConfig>> {
+    let header = |name: &'static str| {
+        request.headers().iter()
+            .find(|h| h.field.equiv(name))
+            .map(|h| h.value.as_str().to_string())
+    };
+    let (url, key) = (header("X-IPFS-Url"), header("X-IPFS-Key"));
+    let provider = match header("X-IPFS-Provider") {
+        Some(name) => <IpfsProvider as clap::ValueEnum>::from_str(&name, true)
+            .map_err(|_| SealedError::IpfsError(format!("Unknown IPFS provider '{}'", name)))?,
+        None => IpfsProvider::Kubo,
+    };
+    match url {
+        Some(url) => IpfsConfig::for_provider(provider, &url, key.as_deref()).map(Some),
+        None if key.is_some() => Err(SealedError::IpfsError("X-IPFS-Key needs an X-IPFS-Url".to_string())),
+        None => Ok(None),
+    }
+}
+
 /// Seal an uploaded file and return JSON response.
-fn seal_uploaded_file(input_path: &Path, upload_dir: &Path, config: &ServeConfig, file_type: &str, ipfs_url: Option<String>, ipfs_key: Option<String>) -> anyhow::Result<String> {
+fn seal_uploaded_file(input_path: &Path, upload_dir: &Path, config: &ServeConfig, file_type: &str, ipfs_config: Option<IpfsConfig>) -> anyhow::Result<String> {
     let seal_config = SealConfig::default();
 
     let artifacts = match file_type {
@@ -133,8 +171,8 @@ fn seal_uploaded_file(input_path: &Path, upload_dir: &Path, config: &ServeConfig
 
     let url_prefix = format!("/uploads/{}", upload_dir.file_name().unwrap().to_string_lossy());
 
-    let signed_by = if let Some(ref key_path) = config.key_path {
-        match sign_record(upload_dir, &artifacts, key_path) {
+    let signed_by = if let Some(ref keypair) = config.keypair {
+        match sign_record(upload_dir, &artifacts, keypair) {
             Ok(pub_key) => Some(pub_key),
             Err(e) => {
                 info!("Signing skipped: {}", e);
@@ -161,17 +199,7 @@ fn seal_uploaded_file(input_path: &Path, upload_dir: &Path, config: &ServeConfig
         response["signedBy"] = serde_json::json!(pub_key);
     }
 
-    if let Some(url) = ipfs_url {
-        let ipfs_config = IpfsConfig {
-            api_url: url.clone(),
-            api_key: ipfs_key,
-            gateway_url: if url.contains("pinata") {
-                "https://gateway.pinata.cloud/ipfs".to_string()
-            } else {
-                "https://ipfs.io/ipfs".to_string()
-            },
-        };
-
+    if let Some(ipfs_config) = ipfs_config {
         match pin_to_ipfs(&upload_dir.join("hashes.json"), &ipfs_config) {
             Ok(record) => {
                 info!("IPFS pinned: {}", record.cid);
@@ -222,11 +250,10 @@ fn build_hashes_json(
 fn sign_record(
     upload_dir: &Path,
     artifacts: &crate::image_processing::SealedArtifacts,
-    key_path: &Path,
+    keypair: &SealedKeyPair,
 ) -> anyhow::Result<String> {
     let hashes_json = build_hashes_json(artifacts)?;
-    let keypair = SealedKeyPair::load(key_path)?;
-    let envelope = keypair.sign(&hashes_json);
+    let envelope = keypair.sign_canonical(&serde_json::from_str::<serde_json::Value>(&hashes_json)?)?;
     let signed_json = serde_json::to_string_pretty(&envelope)?;
     fs::write(upload_dir.join("signed_record.json"), &signed_json)?;
     Ok(envelope.public_key)
@@ -309,14 +336,24 @@ fn do_verify(body: &[u8], boundary: &str, config: &ServeConfig) -> anyhow::Resul
         }
     }
 
-    let result = verify_image(&verify_dir.join("suspect.png"), &sealed_dir, None)?;
+    let mut result = verify_image(&verify_dir.join("suspect.png"), &sealed_dir, None)?;
+    match TrustStore::load(&default_trust_store_path()) {
+        Ok(store) => result.apply_trust(&store),
+        Err(e) => warn!("Ignoring unreadable trust store: {}", e),
+    }
 
     let response = serde_json::json!({
-        "verdict": result.verdict,
+        "verdict": result.summary(),
+        "outcome": result.verdict,
+        "signature": result.signature,
         "signature_valid": result.signature_valid,
+        "signer": result.signer,
+        "cosigners": result.cosigners,
+        "invalid_cosignatures": result.invalid_cosignatures,
         "vs_original": result.vs_original,
         "vs_cropped": result.vs_cropped,
         "tile_match": result.tile_match,
+        "integrity": result.integrity,
         "suspect_hashes": result.suspect_hashes,
     });
 
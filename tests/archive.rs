@@ -0,0 +1,62 @@
+use sealed::archive::create_archive;
+use sealed::batch::{verify_inclusion, BatchItem, BatchManifest};
+
+#[test]
+fn archive_includes_nested_subdirectories() {
+    let tmp = std::env::temp_dir().join("sealed_test_archive_nested");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(tmp.join("0")).unwrap();
+    std::fs::create_dir_all(tmp.join("1")).unwrap();
+    std::fs::create_dir_all(tmp.join("frames")).unwrap();
+
+    std::fs::write(tmp.join("0/hashes.json"), "{}").unwrap();
+    std::fs::write(tmp.join("1/hashes.json"), "{}").unwrap();
+    std::fs::write(tmp.join("1/tile_index.json"), "{}").unwrap();
+    std::fs::write(tmp.join("frames/frame-0001.png"), "x").unwrap();
+
+    let mut manifest = BatchManifest::new();
+    manifest.items.push(BatchItem::new("0", "a.png", &"00".repeat(32), &serde_json::json!({})).unwrap());
+    manifest.save(&tmp).unwrap();
+
+    let archive_path = create_archive(&tmp, "batch").unwrap();
+    let zip = zip::ZipArchive::new(std::fs::File::open(&archive_path).unwrap()).unwrap();
+    let names: Vec<&str> = zip.file_names().collect();
+
+    assert!(names.contains(&"0/hashes.json"));
+    assert!(names.contains(&"1/hashes.json"));
+    assert!(names.contains(&"batch.json"));
+    assert!(!names.iter().any(|n| n.ends_with("tile_index.json")));
+    assert!(!names.iter().any(|n| n.starts_with("frames/")));
+
+    let loaded = BatchManifest::load(&tmp).unwrap();
+    assert_eq!(loaded.items.len(), 1);
+    assert_eq!(loaded.items[0].source, "a.png");
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn batch_merkle_root_and_inclusion_proofs() {
+    let mut manifest = BatchManifest::new();
+    for i in 0..5 {
+        let record = serde_json::json!({ "item": i });
+        let item = BatchItem::new(&i.to_string(), &format!("img{}.png", i), &"ab".repeat(32), &record).unwrap();
+        manifest.items.push(item);
+    }
+    let root = manifest.compute_root().unwrap();
+    assert_eq!(root.len(), 64);
+
+    for i in 0..5 {
+        let proof = manifest.inclusion_proof(i).unwrap();
+        assert_eq!(proof.merkle_root, root);
+        assert!(verify_inclusion(&proof).unwrap(), "proof for item {} should verify", i);
+    }
+
+    let mut forged = manifest.inclusion_proof(2).unwrap();
+    forged.leaf = manifest.items[3].leaf.clone();
+    assert!(!verify_inclusion(&forged).unwrap());
+
+    // Changing any record changes the root.
+    manifest.items[4] = BatchItem::new("4", "img4.png", &"ab".repeat(32), &serde_json::json!({ "item": 99 })).unwrap();
+    assert_ne!(manifest.compute_root().unwrap(), root);
+}
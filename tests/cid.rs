@@ -0,0 +1,198 @@
+use sha2::{Digest, Sha256};
+
+use sealed::cid::{cid_for_bytes, import_file, verify_cid, write_varint, Block, BlockStore, Cid, CidVersion, CHUNK_SIZE};
+
+/// Helper: a dag-pb UnixFS file node over `links`, built by hand as a hostile
+/// CAR could carry it.
+fn file_node(links: &[Cid]) -> Block {
+    let mut node = Vec::new();
+    for cid in links {
+        let hash = cid.to_bytes();
+        node.extend_from_slice(&[0x12, hash.len() as u8 + 2, 0x0a, hash.len() as u8]);
+        node.extend_from_slice(&hash);
+    }
+    node.extend_from_slice(&[0x0a, 0x02, 0x08, 0x02]);
+    let cid = Cid { version: CidVersion::V0, codec: 0x70, digest: Sha256::digest(&node).into() };
+    Block { cid, data: node }
+}
+
+fn dag_pb(version: CidVersion, data: Vec<u8>) -> Block {
+    let cid = Cid { version, codec: 0x70, digest: Sha256::digest(&data).into() };
+    Block { cid, data }
+}
+
+fn raw_leaf(data: &[u8]) -> Block {
+    let cid = Cid { version: CidVersion::V1, codec: 0x55, digest: Sha256::digest(data).into() };
+    Block { cid, data: data.to_vec() }
+}
+
+#[test]
+fn cid_matches_kubo_for_small_files() {
+    assert_eq!(
+        cid_for_bytes(b"", CidVersion::V0).to_string(),
+        "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH"
+    );
+    assert_eq!(
+        cid_for_bytes(b"hello world\n", CidVersion::V0).to_string(),
+        "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o"
+    );
+    assert_eq!(
+        cid_for_bytes(b"hello world", CidVersion::V1).to_string(),
+        "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e"
+    );
+}
+
+#[test]
+fn cid_parse_roundtrip_and_verify() {
+    for s in [
+        "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o",
+        "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e",
+    ] {
+        let cid: Cid = s.parse().unwrap();
+        assert_eq!(cid.to_string(), s);
+        assert_eq!(Cid::from_bytes(&cid.to_bytes()).unwrap(), cid);
+    }
+
+    assert!(verify_cid(b"hello world\n", "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o").unwrap());
+    assert!(!verify_cid(b"hello world", "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o").unwrap());
+    assert!(verify_cid(b"hello world", "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e").unwrap());
+    assert!("zNotACid".parse::<Cid>().is_err());
+}
+
+#[test]
+fn cid_chunks_large_files_into_a_dag() {
+    let data = vec![7u8; CHUNK_SIZE * 2 + 10];
+    let blocks = import_file(&data, CidVersion::V1);
+    // Three raw leaves plus one dag-pb root.
+    assert_eq!(blocks.len(), 4);
+    let root = blocks.last().unwrap().cid;
+    assert_eq!(root.codec, 0x70);
+    assert!(root.to_string().starts_with("bafybei"));
+    // Identical chunks hash to the same leaf.
+    assert_eq!(blocks[0].cid, blocks[1].cid);
+    assert!(verify_cid(&data, &root.to_string()).unwrap());
+}
+
+/// Helper: a protobuf length-delimited field.
+fn pb_bytes(out: &mut Vec<u8>, key: u8, bytes: &[u8]) {
+    out.push(key);
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Helper: a protobuf varint field.
+fn pb_varint(out: &mut Vec<u8>, key: u8, value: u64) {
+    out.push(key);
+    write_varint(out, value);
+}
+
+#[test]
+fn large_files_use_the_kubo_balanced_layout() {
+    // 600 KiB: two full 256 KiB chunks and an 88 KiB tail. The root CIDs
+    // below are what this layout hashes to; to compare with Kubo, run
+    // python3 -c 'import sys; sys.stdout.buffer.write(bytes(i % 251 for i in range(614400)))' \
+    //   | ipfs add -Q [--cid-version=1]
+    let data: Vec<u8> = (0..600 * 1024).map(|i| (i % 251) as u8).collect();
+    let chunks: Vec<&[u8]> = data.chunks(CHUNK_SIZE).collect();
+    assert_eq!(chunks.iter().map(|c| c.len()).collect::<Vec<_>>(), [262144, 262144, 90112]);
+
+    // The nodes `ipfs add` writes, spelled out without the crate's encoder:
+    // leaves first, then one root linking them with an empty name and the
+    // subtree size, and a UnixFS file header listing each leaf's length.
+    let root_node = |links: &[(Cid, u64)]| {
+        let mut node = Vec::new();
+        for (cid, tsize) in links {
+            let mut link = Vec::new();
+            pb_bytes(&mut link, 0x0a, &cid.to_bytes());
+            pb_bytes(&mut link, 0x12, b"");
+            pb_varint(&mut link, 0x18, *tsize);
+            pb_bytes(&mut node, 0x12, &link);
+        }
+        let mut unixfs = Vec::new();
+        pb_varint(&mut unixfs, 0x08, 2);
+        pb_varint(&mut unixfs, 0x18, data.len() as u64);
+        for chunk in &chunks {
+            pb_varint(&mut unixfs, 0x20, chunk.len() as u64);
+        }
+        pb_bytes(&mut node, 0x0a, &unixfs);
+        node
+    };
+
+    // CIDv0: each leaf is a dag-pb node holding its chunk.
+    let mut expected = Vec::new();
+    for chunk in &chunks {
+        let mut unixfs = Vec::new();
+        pb_varint(&mut unixfs, 0x08, 2);
+        pb_bytes(&mut unixfs, 0x12, chunk);
+        pb_varint(&mut unixfs, 0x18, chunk.len() as u64);
+        let mut leaf = Vec::new();
+        pb_bytes(&mut leaf, 0x0a, &unixfs);
+        expected.push(dag_pb(CidVersion::V0, leaf));
+    }
+    let links: Vec<(Cid, u64)> = expected.iter().map(|b| (b.cid, b.data.len() as u64)).collect();
+    let root = root_node(&links);
+    expected.push(dag_pb(CidVersion::V0, root));
+    assert_blocks_eq(&import_file(&data, CidVersion::V0), &expected);
+    assert_eq!(expected[3].cid.to_string(), "QmeqHWd7YuJc1yAQiampjeVfMVcK5zS63zD6vHG8qHN9xz");
+
+    // CIDv1: raw leaves, so each link's size is just the chunk length.
+    let mut expected: Vec<Block> = chunks.iter().map(|c| raw_leaf(c)).collect();
+    let links: Vec<(Cid, u64)> = expected.iter().map(|b| (b.cid, b.data.len() as u64)).collect();
+    let root = root_node(&links);
+    expected.push(dag_pb(CidVersion::V1, root));
+    assert_blocks_eq(&import_file(&data, CidVersion::V1), &expected);
+    assert_eq!(expected[3].cid.to_string(), "bafybeib7rtiuapefdrbklxz5ysik3bixpon5h6cdrim7jhclqfyaz2t5cu");
+}
+
+fn assert_blocks_eq(actual: &[Block], expected: &[Block]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert_eq!(a.cid, e.cid);
+        assert!(a.data == e.data, "block {} differs", e.cid);
+    }
+}
+
+#[test]
+fn fetched_dags_are_bounded() {
+    // Repeated chunks in a real import still read back.
+    let data = [vec![7u8; CHUNK_SIZE * 3], b"tail".to_vec()].concat();
+    let blocks = import_file(&data, CidVersion::V0);
+    let root = blocks.last().unwrap().cid;
+    assert_eq!(BlockStore::new(blocks).read_file(&root).unwrap(), data);
+
+    // Five levels each linking 174 times to the one below: a few hundred
+    // bytes of CAR that would expand to 174^5 bytes.
+    let mut blocks = vec![raw_leaf(b"x")];
+    for _ in 0..5 {
+        let below = blocks.last().unwrap().cid;
+        blocks.push(file_node(&[below; 174]));
+    }
+    let root = blocks.last().unwrap().cid;
+    assert!(BlockStore::new(blocks).read_file(&root).is_err());
+
+    // A long chain is refused before it can exhaust the stack.
+    let chain = |depth: usize| {
+        let mut blocks = vec![raw_leaf(b"x")];
+        for _ in 0..depth {
+            let below = blocks.last().unwrap().cid;
+            blocks.push(file_node(&[below]));
+        }
+        let root = blocks.last().unwrap().cid;
+        BlockStore::new(blocks).read_file(&root)
+    };
+    assert_eq!(chain(20).unwrap(), b"x");
+    assert!(chain(1000).is_err());
+
+    // An empty block linked over and over adds nothing but work.
+    let empty = raw_leaf(b"");
+    let node = file_node(&[empty.cid, empty.cid]);
+    let root = node.cid;
+    assert!(BlockStore::new(vec![empty, node]).read_file(&root).is_err());
+
+    // A field length near usize::MAX is a truncation error, not an overflow.
+    let mut data = vec![0x0a];
+    write_varint(&mut data, u64::MAX);
+    let huge = dag_pb(CidVersion::V0, data);
+    let root = huge.cid;
+    assert!(BlockStore::new(vec![huge]).read_file(&root).is_err());
+}
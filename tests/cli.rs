@@ -0,0 +1,108 @@
+use std::path::Path;
+use std::process::Command;
+
+use clap::Parser;
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use sealed::cli::{Cli, Commands, OutputFormat};
+use sealed::signing::SealedKeyPair;
+
+#[test]
+fn format_flag_is_accepted_anywhere() {
+    let cli = Cli::try_parse_from(["sealed-ch", "verify", "s.png", "sealed", "--format", "json", "--report", "r.json"]).unwrap();
+    assert_eq!(cli.format, OutputFormat::Json);
+    match cli.command {
+        Commands::Verify { report, .. } => assert_eq!(report.unwrap().to_str(), Some("r.json")),
+        _ => panic!("expected verify"),
+    }
+    let cli = Cli::try_parse_from(["sealed-ch", "keygen"]).unwrap();
+    assert_eq!(cli.format, OutputFormat::Text);
+    assert!(Cli::try_parse_from(["sealed-ch", "--format", "yaml", "keygen"]).is_err());
+}
+
+/// Helper: run the built binary with a private home and no config files.
+fn run(home: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_sealed-ch"))
+        .arg("--no-config")
+        .args(args)
+        .env("SEALED_HOME", home)
+        .env("RUST_LOG", "error")
+        .output()
+        .expect("run sealed-ch")
+}
+
+fn patterned(w: u32, h: u32) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(w, h, |x, y| {
+        Rgba([
+            ((x * 7 + y * 3) % 256) as u8,
+            ((x * 11 + y * 5) % 256) as u8,
+            ((x * 13 + y * 7) % 256) as u8,
+            255,
+        ])
+    }))
+}
+
+fn gradient(w: u32, h: u32, brighten: u8) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(w, h, |x, _| {
+        let v = (x * 255 / w) as u8;
+        Rgba([v.saturating_add(brighten), v / 2 + 40, 255 - v, 255])
+    }))
+}
+
+#[test]
+fn verify_exit_codes_and_json_match_the_outcome() {
+    let root = std::env::temp_dir().join("sealed_test_cli_verify");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let path = |name: &str| root.join(name).to_string_lossy().to_string();
+
+    let key = SealedKeyPair::generate();
+    key.save_secret(&root.join("signer.key")).unwrap();
+    key.save_public(&root.join("signer.pub")).unwrap();
+    SealedKeyPair::generate().save_public(&root.join("other.pub")).unwrap();
+
+    let seal = |image: &str, dir: &str, extra: &[&str]| {
+        let output = run(&root, &[&["seal", &path(image), "--output", &path(dir), "--no-register"], extra].concat());
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    };
+    gradient(120, 90, 0).save(root.join("gradient.png")).unwrap();
+    seal("gradient.png", "sealed", &["--key", &path("signer.key")]);
+    gradient(120, 90, 6).save(root.join("brighter.png")).unwrap();
+    // Crop detection needs texture, so crops are checked against a pattern.
+    let pattern = patterned(256, 192);
+    pattern.save(root.join("pattern.png")).unwrap();
+    seal("pattern.png", "sealed-pattern", &[]);
+    pattern.crop_imm(64, 48, 128, 96).save(root.join("crop.png")).unwrap();
+
+    for (suspect, sealed, key, code, outcome) in [
+        ("gradient.png", "sealed", Some("signer.pub"), 0, "exact"),
+        ("brighter.png", "sealed", None, 3, "perceptual"),
+        ("crop.png", "sealed-pattern", None, 4, "crop"),
+        ("pattern.png", "sealed", None, 5, "no-match"),
+        ("gradient.png", "sealed", Some("other.pub"), 6, "signature-failure"),
+    ] {
+        let mut args = vec!["--format".to_string(), "json".to_string(), "verify".to_string(), path(suspect), path(sealed)];
+        if let Some(key) = key {
+            args.extend(["--public-key".to_string(), path(key)]);
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let output = run(&root, &args);
+        assert_eq!(output.status.code(), Some(code), "{} against {}: {}", suspect, sealed, String::from_utf8_lossy(&output.stderr));
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("stdout is one JSON document");
+        assert_eq!(json["outcome"], outcome, "{}", suspect);
+        assert_eq!(json["exit_code"], code, "{}", suspect);
+        assert!(json["verdict"]["kind"].is_string());
+    }
+
+    // Text output writes nothing next to the suspect unless asked to.
+    let output = run(&root, &["verify", &path("brighter.png"), &path("sealed")]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(!root.join("brighter_verification.json").exists());
+    let output = run(&root, &["verify", &path("brighter.png"), &path("sealed"), "--report", &path("report.json")]);
+    assert_eq!(output.status.code(), Some(3));
+    let report: serde_json::Value = serde_json::from_slice(&std::fs::read(root.join("report.json")).unwrap()).unwrap();
+    assert_eq!(report["outcome"], "perceptual");
+
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -0,0 +1,153 @@
+use std::path::Path;
+
+use sealed::config::{find_project_config, ConfigFile, CONFIG_FILE};
+use sealed::ipfs::IpfsProvider;
+use sealed::registry::PriorSealPolicy;
+
+const USER: &str = r#"
+default_profile = "studio"
+
+[defaults]
+edge_width = 30
+key = "/keys/user.key"
+timestamp = true
+
+[profiles.studio]
+output = "/archive/studio"
+sample_frames = 8
+"#;
+
+const PROJECT: &str = r#"
+[defaults]
+edge_width = 24
+output = "sealed-out"
+
+[profiles.studio]
+key = "keys/studio.key"
+on_prior_seal = "refuse-other-key"
+
+[profiles.web]
+ipfs = true
+tsa = "https://tsa.example/tsr"
+
+[profiles.web.ipfs_service]
+provider = "pinning-service"
+api_url = "https://pins.example/psa"
+token_env = "PINS_TOKEN"
+"#;
+
+#[test]
+fn project_config_overrides_user_config_per_field() {
+    let root = std::env::temp_dir().join("sealed_test_config");
+    let _ = std::fs::remove_dir_all(&root);
+    let project_dir = root.join("project");
+    std::fs::create_dir_all(project_dir.join("shoots/day1")).unwrap();
+    std::fs::write(root.join("user.toml"), USER).unwrap();
+    std::fs::write(project_dir.join(CONFIG_FILE), PROJECT).unwrap();
+
+    // Found from a subdirectory of the project.
+    let found = find_project_config(&project_dir.join("shoots/day1")).unwrap();
+    assert_eq!(found, project_dir.join(CONFIG_FILE));
+
+    let config = ConfigFile::load(&root.join("user.toml")).unwrap().overlay(ConfigFile::load(&found).unwrap());
+
+    // No profile named: the user file's default profile applies.
+    let studio = config.settings(None).unwrap();
+    assert_eq!(studio.edge_width, Some(24));
+    assert_eq!(studio.timestamp, Some(true));
+    assert_eq!(studio.sample_frames, Some(8));
+    assert_eq!(studio.on_prior_seal, Some(PriorSealPolicy::RefuseOtherKey));
+    // Relative paths are taken from the file that set them.
+    assert_eq!(studio.key.as_deref(), Some(project_dir.join("keys/studio.key").as_path()));
+    assert_eq!(studio.output.as_deref(), Some(Path::new("/archive/studio")));
+
+    let web = config.settings(Some("web")).unwrap();
+    assert_eq!(web.ipfs, Some(true));
+    assert_eq!(web.key.as_deref(), Some(Path::new("/keys/user.key")));
+    assert_eq!(web.output.as_deref(), Some(project_dir.join("sealed-out").as_path()));
+    let service = web.ipfs_service.unwrap();
+    assert_eq!(service.provider, IpfsProvider::PinningService);
+    assert_eq!(service.token_env.as_deref(), Some("PINS_TOKEN"));
+
+    let err = config.settings(Some("missing")).unwrap_err().to_string();
+    assert!(err.contains("studio, web"), "{}", err);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn unknown_settings_are_rejected() {
+    let root = std::env::temp_dir().join("sealed_test_config_typo");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let path = root.join(CONFIG_FILE);
+    std::fs::write(&path, "[defaults]\nedge_witdh = 10\n").unwrap();
+
+    let err = ConfigFile::load(&path).unwrap_err().to_string();
+    assert!(err.contains("edge_witdh"), "{}", err);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn config_flags_parse_with_subcommands() {
+    use clap::{CommandFactory, Parser};
+    use sealed::cli::Cli;
+
+    Cli::command().debug_assert();
+    let cli = Cli::try_parse_from(["sealed-ch", "--profile", "web", "seal", "a.png", "--no-config"]).unwrap();
+    assert_eq!(cli.profile.as_deref(), Some("web"));
+    assert!(cli.no_config);
+    // `ipfs` subcommands keep their own --profile for pinning profiles.
+    let cli = Cli::try_parse_from(["sealed-ch", "ipfs", "list", "--profile", "remote"]).unwrap();
+    assert!(cli.profile.is_none());
+}
+
+#[test]
+fn negative_flags_turn_config_switches_off() {
+    use clap::Parser;
+    use sealed::cli::{Cli, Commands};
+    use sealed::config::switch;
+
+    let parse = |args: &[&str]| match Cli::try_parse_from([&["sealed-ch", "seal", "a.png"], args].concat()).unwrap().command {
+        Commands::Seal { timestamp, no_timestamp, ipfs, no_ipfs, .. } => (timestamp, no_timestamp, ipfs, no_ipfs),
+        _ => panic!("expected seal"),
+    };
+    let (on, off, ..) = parse(&["--no-timestamp"]);
+    assert!(!switch(on, off, Some(true)));
+    let (on, off, ..) = parse(&[]);
+    assert!(switch(on, off, Some(true)));
+    assert!(!switch(on, off, None));
+    // The last of a pair wins.
+    let (.., on, off) = parse(&["--no-ipfs", "--ipfs"]);
+    assert!(switch(on, off, Some(false)));
+    let (.., on, off) = parse(&["--ipfs", "--no-ipfs"]);
+    assert!(!switch(on, off, Some(true)));
+    // ipfs-pin follows sealed.toml's cid_v1 as seal does.
+    let pin = |args: &[&str]| match Cli::try_parse_from([&["sealed-ch", "ipfs-pin", "sealed/a"], args].concat()).unwrap().command {
+        Commands::IpfsPin { cid_v1, no_cid_v1, .. } => (cid_v1, no_cid_v1),
+        _ => panic!("expected ipfs-pin"),
+    };
+    let (on, off) = pin(&[]);
+    assert!(switch(on, off, Some(true)));
+    let (on, off) = pin(&["--no-cid-v1"]);
+    assert!(!switch(on, off, Some(true)));
+}
+
+#[test]
+fn searched_project_files_cannot_pick_trust_anchors() {
+    let root = std::env::temp_dir().join("sealed_test_config_trust");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let path = root.join(CONFIG_FILE);
+    std::fs::write(&path, "[defaults]\nedge_width = 12\npublic_key = \"evil.pub\"\n\n[profiles.ci]\nrequire_signers = 1\nsigners = [\"evil\"]\n").unwrap();
+
+    let mut config = ConfigFile::load(&path).unwrap();
+    assert!(config.drop_trust_anchors());
+    let ci = config.settings(Some("ci")).unwrap();
+    assert!(ci.public_key.is_none() && ci.require_signers.is_none() && ci.signers.is_none());
+    assert_eq!(ci.edge_width, Some(12), "other settings are kept");
+    assert!(!config.drop_trust_anchors());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -3,7 +3,8 @@ use image::{DynamicImage, RgbaImage, Rgba};
 use sealed::image_processing::{seal_image, save_artifacts, SealConfig};
 use sealed::hashing::{compute_hash_record, compare_hashes, SimilarityConfidence};
 use sealed::signing::SealedKeyPair;
-use sealed::verification::{verify_image, SealedRecord};
+use sealed::tile_hashing::TileMatchResult;
+use sealed::verification::{verify_image, SealedRecord, SignatureStatus, Verdict};
 
 /// Helper: create a test image with a gradient pattern (more realistic than solid color).
 fn make_gradient_image(w: u32, h: u32) -> DynamicImage {
@@ -63,7 +64,9 @@ fn end_to_end_seal_and_verify_exact_match() {
     assert_eq!(result.vs_original.dhash_hamming, 0, "dHash distance should be 0");
     assert_eq!(result.vs_original.phash_hamming, 0, "pHash distance should be 0");
     assert_eq!(result.vs_original.confidence, SimilarityConfidence::Exact, "Confidence should be Exact");
-    assert!(result.verdict.contains("EXACT MATCH"), "Verdict should contain EXACT MATCH");
+    assert_eq!(result.verdict, Verdict::ExactOriginal { sha256: result.suspect_hashes.sha256.clone() });
+    assert_eq!(result.signature, SignatureStatus::Unsigned);
+    assert!(result.summary().contains("EXACT MATCH"), "Verdict should contain EXACT MATCH");
 
     // Cleanup
     let _ = std::fs::remove_dir_all(&tmp);
@@ -117,7 +120,12 @@ fn end_to_end_sign_and_verify_with_public_key() {
 
     assert!(result.signature_valid, "Signature should be valid");
     assert!(result.vs_original.exact_match, "Should be exact match");
-    assert!(result.verdict.contains("VALID"), "Verdict should say signature is VALID");
+    assert_eq!(result.signature, SignatureStatus::Trusted);
+    assert!(result.summary().contains("VALID"), "Verdict should say signature is VALID");
+
+    // Without the pinned key the signature still checks out, but only against itself.
+    let result = verify_image(&suspect_path, &tmp, None).expect("verify_image failed");
+    assert_eq!(result.signature, SignatureStatus::Valid);
 
     // Cleanup
     let _ = std::fs::remove_dir_all(&tmp);
@@ -163,7 +171,8 @@ fn end_to_end_different_image_no_match() {
     assert!(!result.vs_original.blake3_match, "BLAKE3 should NOT match");
     assert!(!result.vs_original.perceptually_similar,
         "Completely different patterned image should not be perceptually similar");
-    assert!(result.verdict.contains("NO MATCH"), "Verdict should say NO MATCH");
+    assert!(matches!(result.verdict, Verdict::NoMatch { .. }), "Verdict should be NoMatch");
+    assert!(result.summary().contains("NO MATCH"), "Verdict should say NO MATCH");
 
     // Cleanup
     let _ = std::fs::remove_dir_all(&tmp);
@@ -188,6 +197,43 @@ fn phash_deterministic() {
     assert_eq!(report.dhash_hamming, 0);
 }
 
+/// Verdicts serialize with a `kind` tag; crops outrank perceptual similarity.
+#[test]
+fn verdict_is_structured_and_rendered() {
+    let img = make_gradient_image(120, 90);
+    let hashes = compute_hash_record(&img).unwrap();
+    let other = compute_hash_record(&DynamicImage::ImageRgba8(RgbaImage::from_fn(120, 90, |x, y| {
+        Rgba([((x * 11 + 128) % 256) as u8, ((y * 13 + 64) % 256) as u8, ((x ^ y) % 256) as u8, 255])
+    })))
+    .unwrap();
+    let same = compare_hashes(&hashes, &hashes);
+    let differ = compare_hashes(&other, &hashes);
+
+    let verdict = Verdict::from_comparisons(&differ, &same, None, &hashes);
+    assert_eq!(verdict.kind(), "exact-share");
+    let json = serde_json::to_value(&verdict).unwrap();
+    assert_eq!(json["kind"], "exact-share");
+    assert_eq!(json["sha256"], hashes.sha256.as_str());
+
+    let tiles = TileMatchResult {
+        crop_detected: true,
+        consistent_votes: 12,
+        total_matches: 20,
+        estimated_offset: Some((16, 8)),
+        tiles_checked: 40,
+        refined_similarity: None,
+    };
+    let verdict = Verdict::from_comparisons(&differ, &differ, Some(&tiles), &other);
+    assert!(matches!(verdict, Verdict::CropDetected { offset: Some((16, 8)), consistent_votes: 12, .. }));
+    assert!(verdict.to_string().starts_with("SUB-REGION CROP DETECTED"));
+    let back: Verdict = serde_json::from_value(serde_json::to_value(&verdict).unwrap()).unwrap();
+    assert_eq!(back, verdict);
+
+    let verdict = Verdict::from_comparisons(&differ, &differ, None, &other);
+    assert!(!verdict.is_match());
+    assert_eq!(serde_json::to_value(&verdict).unwrap()["kind"], "no-match");
+}
+
 /// Verify encrypted key round-trip works.
 #[test]
 fn encrypted_key_roundtrip() {
@@ -0,0 +1,137 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use sealed::image_processing::{seal_image, save_artifacts, SealConfig};
+use sealed::integrity::{check_artifacts, ArtifactStatus};
+use sealed::signing::SealedKeyPair;
+use sealed::verification::{verify_image, verify_image_with, SealedRecord};
+
+fn make_patterned_image(w: u32, h: u32) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(w, h, |x, y| {
+        Rgba([((x * 7 + y * 3) % 256) as u8, ((x * 11 + y * 5) % 256) as u8, ((x * 13 + y * 7) % 256) as u8, 255])
+    }))
+}
+
+/// Helper: seal `img` into `dir` and sign the record.
+fn seal_signed(img: &DynamicImage, dir: &std::path::Path) -> SealedRecord {
+    std::fs::create_dir_all(dir).unwrap();
+    let artifacts = seal_image(img, &SealConfig::default()).unwrap();
+    save_artifacts(&artifacts, dir).unwrap();
+    let record = SealedRecord {
+        original: artifacts.original_hashes.clone(),
+        frame: artifacts.frame_hashes.clone(),
+        cropped: artifacts.cropped_hashes.clone(),
+        recombined: artifacts.recombined_hashes.clone(),
+        share: Some(artifacts.share_hashes.clone()),
+        tile_index: None,
+        sealed_at: chrono::Utc::now().to_rfc3339(),
+        sealed_version: "2.0.0".to_string(),
+    };
+    let json = serde_json::to_string_pretty(&record).unwrap();
+    std::fs::write(dir.join("hashes.json"), &json).unwrap();
+    let envelope = SealedKeyPair::generate().sign(&json);
+    std::fs::write(dir.join("signed_record.json"), serde_json::to_string(&envelope).unwrap()).unwrap();
+    record
+}
+
+fn status<'a>(report: &'a sealed::integrity::IntegrityReport, file: &str) -> &'a ArtifactStatus {
+    &report.artifacts.iter().find(|a| a.file == file).unwrap().status
+}
+
+#[test]
+fn freshly_sealed_directory_is_intact() {
+    let dir = std::env::temp_dir().join("sealed_test_integrity_ok");
+    let _ = std::fs::remove_dir_all(&dir);
+    seal_signed(&make_patterned_image(160, 120), &dir);
+
+    let report = check_artifacts(&dir).unwrap();
+    assert!(report.is_intact(), "{:?}", report.problems());
+    assert_eq!(report.checked_against, "signed_record.json");
+    assert_eq!(report.artifacts.len(), 5);
+    assert_eq!(report.recombines, Some(true));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn tampered_and_missing_artifacts_are_reported() {
+    let dir = std::env::temp_dir().join("sealed_test_integrity_bad");
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut record = seal_signed(&make_patterned_image(160, 120), &dir);
+
+    // Repaint one pixel in the middle of the cropped artifact and drop the share.
+    let mut cropped = image::open(dir.join("cropped.png")).unwrap().to_rgba8();
+    cropped.put_pixel(80, 60, Rgba([1, 2, 3, 255]));
+    cropped.save(dir.join("cropped.png")).unwrap();
+    std::fs::remove_file(dir.join("share.png")).unwrap();
+    std::fs::write(dir.join("frame.png"), b"not a png").unwrap();
+    // Edit hashes.json to cover for it; the signed payload still disagrees.
+    record.cropped.sha256 = "00".repeat(32);
+    std::fs::write(dir.join("hashes.json"), serde_json::to_string_pretty(&record).unwrap()).unwrap();
+
+    let report = check_artifacts(&dir).unwrap();
+    assert!(!report.is_intact());
+    assert_eq!(report.record_mismatches, ["cropped.sha256"]);
+    assert_eq!(status(&report, "original.png"), &ArtifactStatus::Intact);
+    assert!(matches!(status(&report, "frame.png"), ArtifactStatus::Unreadable { .. }));
+    assert_eq!(status(&report, "share.png"), &ArtifactStatus::Missing);
+    match status(&report, "cropped.png") {
+        ArtifactStatus::Tampered { fields } => assert!(fields.contains(&"sha256".to_string())),
+        other => panic!("expected tampered, got {:?}", other),
+    }
+    // Frame could not be decoded, so recombination was not attempted.
+    assert_eq!(report.recombines, None);
+    assert_eq!(report.problems().len(), 5);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn artifacts_that_hash_correctly_must_still_recombine() {
+    let dir = std::env::temp_dir().join("sealed_test_integrity_recombine");
+    let _ = std::fs::remove_dir_all(&dir);
+    seal_signed(&make_patterned_image(160, 120), &dir);
+
+    // An unsigned record rewritten to match a swapped-in frame: every hash
+    // agrees, but frame and cropped no longer make up the original.
+    std::fs::remove_file(dir.join("signed_record.json")).unwrap();
+    let other = seal_image(&DynamicImage::ImageRgba8(RgbaImage::from_pixel(160, 120, Rgba([9, 9, 9, 255]))), &SealConfig::default()).unwrap();
+    let swapped = dir.join("frame.png");
+    other.frame.save(&swapped).unwrap();
+    let mut record: SealedRecord = serde_json::from_str(&std::fs::read_to_string(dir.join("hashes.json")).unwrap()).unwrap();
+    record.frame = other.frame_hashes.clone();
+    std::fs::write(dir.join("hashes.json"), serde_json::to_string_pretty(&record).unwrap()).unwrap();
+
+    let report = check_artifacts(&dir).unwrap();
+    assert_eq!(report.checked_against, "hashes.json");
+    assert!(report.artifacts.iter().all(|a| a.status == ArtifactStatus::Intact));
+    assert_eq!(report.recombines, Some(false));
+    assert!(!report.is_intact());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn verifying_a_directory_with_every_artifact_deleted_reports_them() {
+    let dir = std::env::temp_dir().join("sealed_test_integrity_emptied");
+    let _ = std::fs::remove_dir_all(&dir);
+    let img = make_patterned_image(160, 120);
+    seal_signed(&img, &dir.join("sealed"));
+    for file in ["original.png", "frame.png", "cropped.png", "share.png", "recombined.png"] {
+        std::fs::remove_file(dir.join("sealed").join(file)).unwrap();
+    }
+    let suspect = dir.join("suspect.png");
+    img.save(&suspect).unwrap();
+
+    let result = verify_image(&suspect, &dir.join("sealed"), None).unwrap();
+    let report = result.integrity.expect("a local sealed directory is always checked");
+    assert!(!report.is_intact());
+    assert!(report.artifacts.iter().all(|a| a.status == ArtifactStatus::Missing));
+    // Five missing files, and nothing to recombine.
+    assert_eq!(report.problems().len(), 6);
+
+    // A record fetched by CID carries no artifacts, so it is not checked.
+    let result = verify_image_with(&suspect, &dir.join("sealed"), None, false).unwrap();
+    assert!(result.integrity.is_none());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,398 @@
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use tiny_http::{Response, Server};
+
+use sealed::car::{decode_car, encode_car};
+use sealed::cid::{cid_for_bytes, write_varint, Block, CidVersion};
+use sealed::ipfs::{
+    check_recorded_cid, export_car, import_sealed_dir, pin_directory, pin_to_ipfs, pin_to_local_ipfs,
+    IpfsConfig, IpfsProvider,
+};
+use sealed::pinning::{list_pins, pin_status, resolve_config, unpin, IpfsProfile, IpfsProfiles};
+use sealed::retrieve::{retrieve_sealed_record, FetchSource};
+
+/// Helper: a local `/api/v0/add` endpoint that always answers with `reply`.
+/// Each request's URL and body are sent back on the returned channel.
+fn spawn_mock_node(reply: String) -> (String, mpsc::Receiver<(String, Vec<u8>)>) {
+    let server = Server::http("127.0.0.1:0").expect("bind mock IPFS node");
+    let port = server.server_addr().to_ip().unwrap().port();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let mut body = Vec::new();
+            request.as_reader().read_to_end(&mut body).unwrap();
+            let _ = tx.send((request.url().to_string(), body));
+            request.respond(Response::from_string(reply.clone())).unwrap();
+        }
+    });
+    (format!("http://127.0.0.1:{}", port), rx)
+}
+
+fn spawn_mock_kubo(cid: String) -> String {
+    let reply = serde_json::json!({ "Name": "hashes.json", "Hash": cid, "Size": "1" });
+    spawn_mock_node(reply.to_string()).0
+}
+
+/// Helper: an in-memory Pinning Service API that accepts only `token`.
+/// New pins are `queued` and become `pinned` on their first status poll.
+fn spawn_mock_pinning_service(token: &'static str) -> String {
+    let server = Server::http("127.0.0.1:0").expect("bind mock pinning service");
+    let port = server.server_addr().to_ip().unwrap().port();
+    let pins: Arc<Mutex<Vec<serde_json::Value>>> = Arc::default();
+    thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let authorized = request.headers().iter().any(|h| {
+                h.field.equiv("Authorization") && h.value.as_str() == format!("Bearer {}", token)
+            });
+            if !authorized {
+                request.respond(Response::from_string("{}").with_status_code(401)).unwrap();
+                continue;
+            }
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let url = request.url().to_string();
+            let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+            let mut pins = pins.lock().unwrap();
+
+            let (code, reply) = match (request.method().as_str(), path) {
+                ("POST", "/pins") => {
+                    let pin: serde_json::Value = serde_json::from_str(&body).unwrap();
+                    let status = serde_json::json!({
+                        "requestid": format!("req-{}", pins.len() + 1),
+                        "status": "queued",
+                        "created": "2026-01-01T00:00:00Z",
+                        "pin": { "cid": pin["cid"], "name": pin["name"] },
+                        "delegates": [],
+                    });
+                    pins.push(status.clone());
+                    (202, status)
+                }
+                ("GET", "/pins") => {
+                    let results: Vec<_> = pins
+                        .iter()
+                        .filter(|p| query.split('&').all(|kv| match kv.split_once('=') {
+                            Some(("cid", cid)) => p["pin"]["cid"] == cid,
+                            _ => true,
+                        }))
+                        .cloned()
+                        .collect();
+                    (200, serde_json::json!({ "count": results.len(), "results": results }))
+                }
+                ("GET", id) => match pins.iter_mut().find(|p| format!("/pins/{}", p["requestid"].as_str().unwrap()) == id) {
+                    Some(pin) => {
+                        pin["status"] = "pinned".into();
+                        (200, pin.clone())
+                    }
+                    None => (404, serde_json::json!({})),
+                },
+                ("DELETE", id) => {
+                    let before = pins.len();
+                    pins.retain(|p| format!("/pins/{}", p["requestid"].as_str().unwrap()) != id);
+                    (if pins.len() < before { 202 } else { 404 }, serde_json::json!({}))
+                }
+                _ => (404, serde_json::json!({})),
+            };
+            request
+                .respond(Response::from_string(reply.to_string()).with_status_code(code))
+                .unwrap();
+        }
+    });
+    format!("http://127.0.0.1:{}", port)
+}
+
+/// Helper: a trustless gateway at `/ipfs` serving `blocks` as `?format=raw`
+/// blocks or as a CAR rooted at the requested CID. `tamper` flips a byte in
+/// every block it serves.
+fn spawn_mock_gateway(blocks: Vec<Block>, tamper: bool) -> String {
+    let server = Server::http("127.0.0.1:0").expect("bind mock gateway");
+    let port = server.server_addr().to_ip().unwrap().port();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let url = request.url().to_string();
+            let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+            let requested = path.trim_start_matches("/ipfs/");
+            let Some(root) = blocks.iter().find(|b| b.cid.to_string() == requested) else {
+                request.respond(Response::from_string("not found").with_status_code(404)).unwrap();
+                continue;
+            };
+            let served: Vec<Block> = blocks
+                .iter()
+                .map(|b| {
+                    let mut data = b.data.clone();
+                    if tamper && b.cid != root.cid {
+                        data[0] ^= 1;
+                    }
+                    Block { cid: b.cid, data }
+                })
+                .collect();
+            let body = if query.contains("format=car") {
+                encode_car(&root.cid, &served)
+            } else {
+                root.data.clone()
+            };
+            request.respond(Response::from_data(body)).unwrap();
+        }
+    });
+    format!("http://127.0.0.1:{}/ipfs", port)
+}
+
+fn setup(name: &str) -> std::path::PathBuf {
+    let tmp = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).unwrap();
+    std::fs::write(tmp.join("hashes.json"), b"{\"sha256\":\"abc\"}").unwrap();
+    tmp
+}
+
+#[test]
+fn pin_accepts_matching_cid_and_verify_rechecks_it() {
+    let tmp = setup("sealed_test_ipfs_cid_ok");
+    let content = std::fs::read(tmp.join("hashes.json")).unwrap();
+    let cid = cid_for_bytes(&content, CidVersion::V1).to_string();
+
+    let config = IpfsConfig {
+        api_url: spawn_mock_kubo(cid.clone()),
+        cid_version: CidVersion::V1,
+        ..IpfsConfig::default()
+    };
+    let record = pin_to_local_ipfs(&tmp.join("hashes.json"), &config).expect("pin failed");
+    assert_eq!(record.cid, cid);
+    assert_eq!(record.file, "hashes.json");
+    std::fs::write(tmp.join("ipfs_record.json"), serde_json::to_string(&record).unwrap()).unwrap();
+
+    assert!(check_recorded_cid(&tmp).unwrap().unwrap().matches);
+
+    std::fs::write(tmp.join("hashes.json"), b"{\"sha256\":\"tampered\"}").unwrap();
+    assert!(!check_recorded_cid(&tmp).unwrap().unwrap().matches);
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn pin_fails_when_remote_cid_disagrees() {
+    let tmp = setup("sealed_test_ipfs_cid_bad");
+    let wrong = cid_for_bytes(b"something else", CidVersion::V0).to_string();
+
+    let config = IpfsConfig {
+        api_url: spawn_mock_kubo(wrong),
+        ..IpfsConfig::default()
+    };
+    let err = pin_to_local_ipfs(&tmp.join("hashes.json"), &config).unwrap_err();
+    assert!(err.to_string().contains("hashes to"));
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+/// Helper: a small sealed directory with a nested item.
+fn setup_tree(name: &str) -> std::path::PathBuf {
+    let tmp = setup(name);
+    std::fs::create_dir_all(tmp.join("0")).unwrap();
+    std::fs::write(tmp.join("0/hashes.json"), b"{\"item\":0}").unwrap();
+    std::fs::write(tmp.join("timestamp.ots"), b"proof").unwrap();
+    std::fs::write(tmp.join(".DS_Store"), b"junk").unwrap();
+    std::fs::write(tmp.join("ipfs_record.json"), b"{}").unwrap();
+    tmp
+}
+
+#[test]
+fn empty_directory_matches_kubo() {
+    let tmp = std::env::temp_dir().join("sealed_test_ipfs_empty_dir");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).unwrap();
+
+    let import = import_sealed_dir(&tmp, CidVersion::V0).unwrap();
+    assert_eq!(import.root.to_string(), "QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn");
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn car_export_has_one_root_over_every_file() {
+    let tmp = setup_tree("sealed_test_ipfs_car");
+    let car_path = std::env::temp_dir().join("sealed_test_ipfs_car.car");
+
+    let record = export_car(&tmp, &car_path, CidVersion::V1).unwrap();
+    let paths: Vec<&str> = record.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, vec!["0/hashes.json", "hashes.json", "timestamp.ots"]);
+
+    let (root, blocks) = decode_car(&std::fs::read(&car_path).unwrap()).unwrap();
+    assert_eq!(root.to_string(), record.cid);
+    for file in &record.files {
+        assert!(blocks.iter().any(|b| b.cid.to_string() == file.cid), "{} missing", file.path);
+    }
+
+    // The recorded per-file CID still checks hashes.json.
+    std::fs::write(tmp.join("ipfs_record.json"), serde_json::to_string(&record).unwrap()).unwrap();
+    let check = check_recorded_cid(&tmp).unwrap().unwrap();
+    assert_eq!(check.file, "hashes.json");
+    assert!(check.matches);
+
+    // Editing a file changes the root.
+    std::fs::write(tmp.join("0/hashes.json"), b"{\"item\":1}").unwrap();
+    assert_ne!(import_sealed_dir(&tmp, CidVersion::V1).unwrap().root.to_string(), record.cid);
+
+    let mut corrupt = std::fs::read(&car_path).unwrap();
+    let last = corrupt.len() - 1;
+    corrupt[last] ^= 0xff;
+    assert!(decode_car(&corrupt).is_err());
+
+    // Lengths near usize::MAX are truncation errors, not overflows.
+    let mut huge = Vec::new();
+    write_varint(&mut huge, u64::MAX);
+    assert!(decode_car(&huge).is_err());
+    let mut huge_block = encode_car(&root, &[]);
+    write_varint(&mut huge_block, u64::MAX);
+    assert!(decode_car(&huge_block).is_err());
+
+    let _ = std::fs::remove_dir_all(&tmp);
+    let _ = std::fs::remove_file(&car_path);
+}
+
+#[test]
+fn directory_pin_wraps_and_checks_root() {
+    let tmp = setup_tree("sealed_test_ipfs_wrap");
+    let root = import_sealed_dir(&tmp, CidVersion::V0).unwrap().root.to_string();
+
+    let reply = format!(
+        "{}\n{}\n",
+        serde_json::json!({ "Name": "hashes.json", "Hash": "ignored" }),
+        serde_json::json!({ "Name": "", "Hash": root }),
+    );
+    let (url, requests) = spawn_mock_node(reply);
+    let config = IpfsConfig { api_url: url, ..IpfsConfig::default() };
+
+    let record = pin_directory(&tmp, &config).expect("directory pin failed");
+    assert_eq!(record.cid, root);
+    assert_eq!(record.files.len(), 3);
+
+    let (path, body) = requests.recv().unwrap();
+    assert!(path.contains("wrap-with-directory=true"));
+    let body = String::from_utf8_lossy(&body);
+    assert!(body.contains("filename=\"0%2Fhashes.json\""));
+    assert!(body.contains("application/x-directory"));
+    assert!(!body.contains(".DS_Store"));
+
+    // A node reporting another root fails the pin.
+    let (url, _requests) = spawn_mock_node(serde_json::json!({ "Name": "", "Hash": "QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn" }).to_string());
+    let config = IpfsConfig { api_url: url, ..IpfsConfig::default() };
+    assert!(pin_directory(&tmp, &config).is_err());
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn pinning_service_pin_polls_then_lists_and_unpins() {
+    let tmp = setup("sealed_test_ipfs_psa");
+    let expected = cid_for_bytes(&std::fs::read(tmp.join("hashes.json")).unwrap(), CidVersion::V0);
+
+    let config = IpfsConfig {
+        poll_timeout_secs: 10,
+        ..IpfsConfig::pinning_service(&spawn_mock_pinning_service("secret"), "secret")
+    };
+    let record = pin_to_ipfs(&tmp.join("hashes.json"), &config).expect("pin failed");
+    assert_eq!(record.cid, expected.to_string());
+    assert_eq!(record.service, "pinning-service");
+    let request_id = record.request_id.expect("request id recorded");
+
+    let status = pin_status(&config, &request_id).unwrap();
+    assert_eq!(status[0].status, "pinned");
+    let pins = list_pins(&config, Some(&record.cid)).unwrap();
+    assert_eq!(pins.len(), 1);
+    assert_eq!(pins[0].name.as_deref(), Some("hashes.json"));
+
+    assert_eq!(unpin(&config, &record.cid).unwrap(), 1);
+    assert!(list_pins(&config, None).unwrap().is_empty());
+
+    let wrong_token = IpfsConfig { api_key: Some("nope".to_string()), ..config };
+    assert!(list_pins(&wrong_token, None).unwrap_err().to_string().contains("401"));
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn profiles_replace_the_api_key_heuristic() {
+    let tmp = setup("sealed_test_ipfs_profiles");
+    let path = tmp.join("ipfs_profiles.json");
+
+    let mut profiles = IpfsProfiles::default();
+    profiles.profiles.insert("remote".to_string(), IpfsProfile {
+        provider: IpfsProvider::PinningService,
+        api_url: "https://pins.example/psa/".to_string(),
+        token: None,
+        token_env: Some("SEALED_TEST_PSA_TOKEN".to_string()),
+        gateway_url: None,
+        source_node: None,
+    });
+    profiles.default = Some("remote".to_string());
+    profiles.save(&path).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    // No profiles file: the local node, with no key guessing.
+    let local = resolve_config(&tmp.join("missing.json"), None, None, None).unwrap();
+    assert_eq!(local.provider, IpfsProvider::Kubo);
+
+    let err = resolve_config(&path, None, None, None).unwrap_err();
+    assert!(err.to_string().contains("SEALED_TEST_PSA_TOKEN"));
+
+    std::env::set_var("SEALED_TEST_PSA_TOKEN", "t0ken");
+    let remote = resolve_config(&path, None, None, None).unwrap();
+    assert_eq!(remote.provider, IpfsProvider::PinningService);
+    assert_eq!(remote.api_url, "https://pins.example/psa");
+    assert_eq!(remote.api_key.as_deref(), Some("t0ken"));
+
+    // An explicit URL still means a Kubo node, whatever the default profile is.
+    let explicit = resolve_config(&path, None, Some("http://10.0.0.2:5001"), None).unwrap();
+    assert_eq!(explicit.provider, IpfsProvider::Kubo);
+    assert!(resolve_config(&path, Some("absent"), None, None).is_err());
+    // A key says nothing about what a URL speaks, so the pair is refused.
+    assert!(resolve_config(&path, None, Some("https://api.pinata.cloud"), Some("k3y")).is_err());
+    assert_eq!(resolve_config(&path, None, None, Some("k3y")).unwrap().provider, IpfsProvider::Pinata);
+
+    assert!(IpfsConfig::for_provider(IpfsProvider::Kubo, "http://10.0.0.2:5001", Some("k3y")).is_err());
+    assert!(IpfsConfig::for_provider(IpfsProvider::Pinata, "https://api.pinata.cloud", None).is_err());
+    let psa = IpfsConfig::for_provider(IpfsProvider::PinningService, "https://pins.example/psa/", Some("k3y")).unwrap();
+    assert_eq!(psa.api_url, "https://pins.example/psa");
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn verify_by_cid_fetches_and_checks_a_directory_pin() {
+    let tmp = setup("sealed_test_ipfs_retrieve");
+    // Bigger than one chunk so the file spans several blocks.
+    let original: Vec<u8> = (0..600_000u32).map(|i| (i % 251) as u8).collect();
+    std::fs::write(tmp.join("original.png"), &original).unwrap();
+    std::fs::write(tmp.join("signed_record.json"), b"{\"payload\":\"x\"}").unwrap();
+    std::fs::write(tmp.join("notes.txt"), b"not fetched").unwrap();
+
+    let import = import_sealed_dir(&tmp, CidVersion::V1).unwrap();
+    let root = import.root.to_string();
+    let dest = tmp.join("fetched");
+
+    let gateway = FetchSource::Gateway(spawn_mock_gateway(import.blocks.clone(), false));
+    let retrieved = retrieve_sealed_record(&root, None, &gateway, &dest).expect("retrieve failed");
+    assert!(retrieved.directory);
+    assert_eq!(retrieved.files.len(), 3);
+    assert_eq!(std::fs::read(dest.join("original.png")).unwrap(), original);
+    assert_eq!(
+        std::fs::read(dest.join("hashes.json")).unwrap(),
+        std::fs::read(tmp.join("hashes.json")).unwrap()
+    );
+    assert!(!dest.join("notes.txt").exists());
+
+    // A single-file CID is treated as hashes.json.
+    let hashes_cid = &retrieved.files.iter().find(|f| f.path == "hashes.json").unwrap().cid;
+    let single = retrieve_sealed_record(hashes_cid, None, &gateway, &tmp.join("single")).unwrap();
+    assert!(!single.directory);
+
+    let tampered = FetchSource::Gateway(spawn_mock_gateway(import.blocks, true));
+    let err = retrieve_sealed_record(&root, None, &tampered, &tmp.join("bad")).unwrap_err();
+    assert!(err.to_string().contains("does not match its CID"), "{}", err);
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
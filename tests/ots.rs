@@ -0,0 +1,85 @@
+use sha2::{Digest, Sha256};
+
+use sealed::ots::{verify_proof, Attestation, BlockHeader, DetachedTimestampFile, Op, Timestamp};
+
+/// Helper: a proof with a pending branch and a Bitcoin branch, as an upgraded
+/// calendar response would look.
+fn make_proof(digest: &[u8]) -> DetachedTimestampFile {
+    let bitcoin_leaf = Timestamp {
+        attestations: vec![Attestation::Bitcoin { height: 800_000 }],
+        ops: vec![],
+    };
+    let pending_leaf = Timestamp {
+        attestations: vec![Attestation::Pending {
+            uri: "https://a.pool.opentimestamps.org".to_string(),
+        }],
+        ops: vec![],
+    };
+    let timestamp = Timestamp {
+        attestations: vec![],
+        ops: vec![
+            (Op::Append(vec![0xaa; 16]), Timestamp {
+                attestations: vec![],
+                ops: vec![(Op::Sha256, pending_leaf)],
+            }),
+            (Op::Prepend(vec![0xbb; 32]), Timestamp {
+                attestations: vec![],
+                ops: vec![(Op::Sha256, bitcoin_leaf)],
+            }),
+        ],
+    };
+    DetachedTimestampFile {
+        file_hash_op: Op::Sha256,
+        digest: digest.to_vec(),
+        timestamp,
+    }
+}
+
+#[test]
+fn ots_roundtrip_serialization() {
+    let digest = Sha256::digest(b"sealed").to_vec();
+    let proof = make_proof(&digest);
+
+    let bytes = proof.serialize();
+    let parsed = DetachedTimestampFile::parse(&bytes).expect("parse failed");
+
+    assert_eq!(parsed, proof);
+    assert_eq!(parsed.serialize(), bytes);
+    assert!(parsed.timestamp.is_complete());
+}
+
+#[test]
+fn ots_verify_against_block_header() {
+    let digest = Sha256::digest(b"sealed").to_vec();
+    let proof = make_proof(&digest);
+
+    // Commitment on the Bitcoin branch: sha256(0xbb*32 || digest).
+    let commitment = Sha256::digest([vec![0xbb; 32], digest.clone()].concat());
+    let mut raw = [0u8; 80];
+    raw[36..68].copy_from_slice(&commitment);
+    raw[68..72].copy_from_slice(&1_700_000_000u32.to_le_bytes());
+    let header = BlockHeader::from_bytes(&raw).unwrap();
+
+    let result = verify_proof(&proof, &hex::encode(&digest), Some(&header)).unwrap();
+    assert!(result.digest_matches);
+    assert!(result.is_verified());
+    assert_eq!(result.pending_calendars, vec!["https://a.pool.opentimestamps.org"]);
+    assert_eq!(result.bitcoin[0].height, 800_000);
+
+    let wrong_header = BlockHeader::from_bytes(&[0u8; 80]).unwrap();
+    let result = verify_proof(&proof, &hex::encode(&digest), Some(&wrong_header)).unwrap();
+    assert!(!result.is_verified());
+
+    let other = hex::encode(Sha256::digest(b"other"));
+    let result = verify_proof(&proof, &other, Some(&header)).unwrap();
+    assert!(!result.digest_matches);
+}
+
+#[test]
+fn ots_rejects_malformed_input() {
+    assert!(DetachedTimestampFile::parse(b"not a proof").is_err());
+
+    let digest = Sha256::digest(b"sealed").to_vec();
+    let bytes = make_proof(&digest).serialize();
+    assert!(DetachedTimestampFile::parse(&bytes[..bytes.len() - 3]).is_err());
+}
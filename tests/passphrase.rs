@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+use sealed::config::{ConfigFile, CONFIG_FILE};
+use sealed::passphrase::PassphraseSource;
+use sealed::signing::SealedKeyPair;
+
+#[test]
+fn sources_parse_and_print_alike() {
+    for (text, source) in [
+        ("prompt", PassphraseSource::Prompt),
+        ("stdin", PassphraseSource::Stdin),
+        ("env:SEALED_PASS", PassphraseSource::Env("SEALED_PASS".to_string())),
+        ("fd:3", PassphraseSource::Fd(3)),
+        ("file:/run/secrets/key pass", PassphraseSource::File(PathBuf::from("/run/secrets/key pass"))),
+    ] {
+        assert_eq!(text.parse::<PassphraseSource>().unwrap(), source);
+        assert_eq!(source.to_string(), text);
+    }
+    for bad in ["", "env:", "fd:-1", "fd:x", "file:", "pass:hunter2", "keychain"] {
+        assert!(bad.parse::<PassphraseSource>().is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn encrypted_keys_load_without_a_terminal() {
+    let dir = std::env::temp_dir().join("sealed_test_passphrase");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let keypair = SealedKeyPair::generate();
+    let key = dir.join("sealed.key");
+    keypair.save_secret_encrypted(&key, "correct horse").unwrap();
+
+    std::env::set_var("SEALED_TEST_PASSPHRASE", "correct horse");
+    let from_env = PassphraseSource::Env("SEALED_TEST_PASSPHRASE".to_string());
+    assert_eq!(SealedKeyPair::load_with_passphrase(&key, &from_env).unwrap().key_id(), keypair.key_id());
+    assert!(SealedKeyPair::load_with_passphrase(&key, &PassphraseSource::Env("SEALED_TEST_UNSET".to_string())).is_err());
+
+    // Only the first line counts, without its line ending.
+    let file = dir.join("passphrase");
+    std::fs::write(&file, "correct horse\r\nignored\n").unwrap();
+    let from_file = PassphraseSource::File(file.clone());
+    assert_eq!(from_file.read("").unwrap().as_str(), "correct horse");
+    assert_eq!(SealedKeyPair::load_with_passphrase(&key, &from_file).unwrap().key_id(), keypair.key_id());
+    std::fs::write(&file, "\n").unwrap();
+    assert!(from_file.read("").is_err(), "an empty passphrase is a misconfiguration");
+
+    // Unencrypted keys never read the source.
+    let plain = dir.join("plain.key");
+    keypair.save_secret(&plain).unwrap();
+    let missing = PassphraseSource::File(dir.join("missing"));
+    assert!(SealedKeyPair::load_with_passphrase(&plain, &missing).is_ok());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn config_passphrase_files_are_relative_to_the_config() {
+    let root = std::env::temp_dir().join("sealed_test_passphrase_config");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let path = root.join(CONFIG_FILE);
+    std::fs::write(&path, "[defaults]\nkey_passphrase = \"file:secrets/pass\"\n\n[profiles.ci]\nkey_passphrase = \"env:CI_KEY_PASS\"\n").unwrap();
+
+    let config = ConfigFile::load(&path).unwrap();
+    assert_eq!(
+        config.settings(None).unwrap().key_passphrase,
+        Some(PassphraseSource::File(root.join("secrets/pass")))
+    );
+    assert_eq!(
+        config.settings(Some("ci")).unwrap().key_passphrase,
+        Some(PassphraseSource::Env("CI_KEY_PASS".to_string()))
+    );
+    std::fs::write(&path, "[defaults]\nkey_passphrase = \"hunter2\"\n").unwrap();
+    assert!(ConfigFile::load(&path).is_err());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -0,0 +1,163 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use rand::Rng;
+
+use sealed::hashing::{compute_hash_record, SimilarityConfidence};
+use sealed::image_processing::{seal_image, save_artifacts, SealConfig};
+use sealed::registry::{collect_entries, enforce_prior_seal_policy, BkTree, PriorSealPolicy, Registry};
+use sealed::signing::SealedKeyPair;
+use sealed::verification::SealedRecord;
+
+/// Helper: a smooth pattern that differs clearly between `seed` values.
+fn make_image(w: u32, h: u32, seed: u32) -> DynamicImage {
+    let mut img = RgbaImage::new(w, h);
+    for y in 0..h {
+        for x in 0..w {
+            let v = match seed {
+                0 => (x * 255 / w) as u8,
+                1 => (y * 255 / h) as u8,
+                _ => if ((x / 25) + (y / 25)) % 2 == 0 { 230 } else { 20 },
+            };
+            img.put_pixel(x, y, Rgba([v, v / 2 + 40, 255 - v, 255]));
+        }
+    }
+    DynamicImage::ImageRgba8(img)
+}
+
+/// Helper: seal `img` into `dir` the way `cmd_seal` lays out a single image.
+fn seal_into(img: &DynamicImage, dir: &std::path::Path, key: Option<&SealedKeyPair>) {
+    std::fs::create_dir_all(dir).unwrap();
+    let artifacts = seal_image(img, &SealConfig::default()).unwrap();
+    save_artifacts(&artifacts, dir).unwrap();
+    let record = SealedRecord {
+        original: artifacts.original_hashes.clone(),
+        frame: artifacts.frame_hashes.clone(),
+        cropped: artifacts.cropped_hashes.clone(),
+        recombined: artifacts.recombined_hashes.clone(),
+        share: Some(artifacts.share_hashes.clone()),
+        tile_index: None,
+        sealed_at: chrono::Utc::now().to_rfc3339(),
+        sealed_version: "2.0.0".to_string(),
+    };
+    let json = serde_json::to_string_pretty(&record).unwrap();
+    std::fs::write(dir.join("hashes.json"), &json).unwrap();
+    if let Some(key) = key {
+        let envelope = key.sign(&json);
+        std::fs::write(dir.join("signed_record.json"), serde_json::to_string(&envelope).unwrap()).unwrap();
+    }
+}
+
+#[test]
+fn bk_tree_agrees_with_brute_force() {
+    let mut rng = rand::thread_rng();
+    let hashes: Vec<u64> = (0..2000).map(|_| rng.gen()).collect();
+    let mut tree = BkTree::default();
+    for (i, &h) in hashes.iter().enumerate() {
+        tree.insert(h, i);
+    }
+    // A few near-duplicates so small radii have something to find.
+    tree.insert(hashes[7] ^ 0b1011, 2000);
+    assert_eq!(tree.len(), 2001);
+
+    for radius in [0, 3, 12, 24] {
+        let query = hashes[7];
+        let mut found: Vec<usize> = tree.find(query, radius).into_iter().map(|(i, _)| i).collect();
+        found.sort();
+        let mut expected: Vec<usize> = hashes
+            .iter()
+            .enumerate()
+            .filter(|(_, &h)| (h ^ query).count_ones() <= radius)
+            .map(|(i, _)| i)
+            .collect();
+        if radius >= 3 {
+            expected.push(2000);
+        }
+        assert_eq!(found, expected, "radius {}", radius);
+    }
+}
+
+#[test]
+fn registry_finds_prior_seal_of_a_derivative() {
+    let root = std::env::temp_dir().join("sealed_test_registry");
+    let _ = std::fs::remove_dir_all(&root);
+    let key = SealedKeyPair::generate();
+    seal_into(&make_image(240, 180, 0), &root.join("gradient"), Some(&key));
+    seal_into(&make_image(240, 180, 2), &root.join("checker"), None);
+
+    let mut registry = Registry::default();
+    for entry in collect_entries(&root).unwrap() {
+        assert!(registry.add(entry));
+    }
+    assert_eq!(registry.entries.len(), 2);
+    // Re-adding the same directory updates in place.
+    let again = collect_entries(&root.join("checker")).unwrap().remove(0);
+    assert!(!registry.add(again));
+    assert_eq!(registry.entries.len(), 2);
+
+    let path = root.join("registry.json");
+    registry.save(&path).unwrap();
+    let registry = Registry::load(&path).unwrap();
+
+    // A slightly brightened copy is still found, and only the right work.
+    let DynamicImage::ImageRgba8(mut brighter) = make_image(240, 180, 0) else { unreachable!() };
+    for px in brighter.pixels_mut() {
+        px.0[0] = px.0[0].saturating_add(6);
+    }
+    let hashes = compute_hash_record(&DynamicImage::ImageRgba8(brighter)).unwrap();
+    let matches = registry.search(&hashes, SimilarityConfidence::Medium);
+    assert_eq!(matches.len(), 1);
+    assert!(matches[0].entry.sealed_dir.ends_with("gradient"));
+    assert_eq!(matches[0].entry.signer.as_deref(), Some(key.public_key_base64().as_str()));
+
+    let exact = compute_hash_record(&make_image(240, 180, 2)).unwrap();
+    let matches = registry.search(&exact, SimilarityConfidence::Exact);
+    assert_eq!(matches[0].report.confidence, SimilarityConfidence::Exact);
+    assert!(matches[0].entry.signer.is_none());
+
+    let unrelated = compute_hash_record(&make_image(240, 180, 1)).unwrap();
+    assert!(registry.search(&unrelated, SimilarityConfidence::High).is_empty());
+
+    let mut registry = registry;
+    assert_eq!(registry.remove(root.join("checker").to_str().unwrap()).unwrap(), 1);
+    let id = registry.entries[0].id.clone();
+    assert_eq!(registry.remove(&id[..6]).unwrap(), 1);
+    assert!(registry.entries.is_empty());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn prior_seal_policy_distinguishes_signers() {
+    let root = std::env::temp_dir().join("sealed_test_prior_seal");
+    let _ = std::fs::remove_dir_all(&root);
+    let owner = SealedKeyPair::generate();
+    let img = make_image(240, 180, 2);
+    seal_into(&img, &root.join("first"), Some(&owner));
+
+    let mut registry = Registry::default();
+    for entry in collect_entries(&root).unwrap() {
+        registry.add(entry);
+    }
+    let hashes = compute_hash_record(&img).unwrap();
+
+    let same = registry.prior_seals(&hashes, Some(&owner.public_key_base64()));
+    assert_eq!(same.len(), 1);
+    assert!(same[0].same_signer);
+    assert!(enforce_prior_seal_policy(PriorSealPolicy::RefuseOtherKey, &same).is_ok());
+    assert!(enforce_prior_seal_policy(PriorSealPolicy::Refuse, &same).is_err());
+
+    let other = SealedKeyPair::generate();
+    let foreign = registry.prior_seals(&hashes, Some(&other.public_key_base64()));
+    assert!(!foreign[0].same_signer);
+    let err = enforce_prior_seal_policy(PriorSealPolicy::RefuseOtherKey, &foreign).unwrap_err();
+    assert!(err.to_string().contains("EXACT"));
+    assert!(enforce_prior_seal_policy(PriorSealPolicy::Warn, &foreign).is_ok());
+
+    // Unsigned seals never count as the same signer.
+    let unsigned = registry.prior_seals(&hashes, None);
+    assert!(!unsigned[0].same_signer);
+
+    let unrelated = compute_hash_record(&make_image(240, 180, 1)).unwrap();
+    assert!(registry.prior_seals(&unrelated, None).is_empty());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -0,0 +1,171 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use sealed::hashing::SimilarityConfidence;
+use sealed::image_processing::{seal_image, save_artifacts, SealConfig};
+use sealed::registry::{collect_entries, Registry};
+use sealed::scan::{scan_suspects, ScanConfig};
+use sealed::signing::SealedKeyPair;
+use sealed::tile_hashing::generate_tile_index;
+use sealed::verification::{SealedRecord, SignatureStatus};
+
+fn make_patterned_image(w: u32, h: u32) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(w, h, |x, y| {
+        Rgba([
+            ((x * 7 + y * 3) % 256) as u8,
+            ((x * 11 + y * 5) % 256) as u8,
+            ((x * 13 + y * 7) % 256) as u8,
+            255,
+        ])
+    }))
+}
+
+fn make_gradient(w: u32, h: u32, brighten: u8) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(w, h, |x, _| {
+        let v = (x * 255 / w) as u8;
+        Rgba([v.saturating_add(brighten), v / 2 + 40, 255 - v, 255])
+    }))
+}
+
+/// Helper: seal `img` into `dir` with a tile index, as `cmd_seal` does.
+fn seal_into(img: &DynamicImage, dir: &std::path::Path, key: Option<&SealedKeyPair>) {
+    std::fs::create_dir_all(dir).unwrap();
+    let artifacts = seal_image(img, &SealConfig::default()).unwrap();
+    save_artifacts(&artifacts, dir).unwrap();
+    let record = SealedRecord {
+        original: artifacts.original_hashes.clone(),
+        frame: artifacts.frame_hashes.clone(),
+        cropped: artifacts.cropped_hashes.clone(),
+        recombined: artifacts.recombined_hashes.clone(),
+        share: Some(artifacts.share_hashes.clone()),
+        tile_index: Some(generate_tile_index(img)),
+        sealed_at: chrono::Utc::now().to_rfc3339(),
+        sealed_version: "2.0.0".to_string(),
+    };
+    let json = serde_json::to_string_pretty(&record).unwrap();
+    std::fs::write(dir.join("hashes.json"), &json).unwrap();
+    if let Some(key) = key {
+        let envelope = key.sign(&json);
+        std::fs::write(dir.join("signed_record.json"), serde_json::to_string(&envelope).unwrap()).unwrap();
+    }
+}
+
+#[test]
+fn scan_ranks_matches_across_a_folder() {
+    let root = std::env::temp_dir().join("sealed_test_scan");
+    let _ = std::fs::remove_dir_all(&root);
+    let key = SealedKeyPair::generate();
+    let pattern = make_patterned_image(400, 300);
+    seal_into(&pattern, &root.join("sealed/pattern"), Some(&key));
+    seal_into(&make_gradient(240, 180, 0), &root.join("sealed/gradient"), None);
+
+    let suspects_dir = root.join("suspects");
+    std::fs::create_dir_all(&suspects_dir).unwrap();
+    pattern.save(suspects_dir.join("copy.png")).unwrap();
+    make_gradient(240, 180, 6).save(suspects_dir.join("brighter.png")).unwrap();
+    pattern.crop_imm(100, 75, 200, 150).save(suspects_dir.join("crop.png")).unwrap();
+    std::fs::write(suspects_dir.join("broken.png"), b"not an image").unwrap();
+    let mut suspects: Vec<_> = std::fs::read_dir(&suspects_dir).unwrap().map(|e| e.unwrap().path()).collect();
+    suspects.sort();
+
+    let registry = Registry { entries: collect_entries(&root.join("sealed")).unwrap() };
+    let config = ScanConfig { jobs: 2, exhaustive_tiles: true, ..ScanConfig::default() };
+    let report = scan_suspects(&suspects, &registry, &config);
+
+    assert_eq!(report.suspects, 4);
+    assert_eq!(report.records, 2);
+    assert_eq!(report.failures.len(), 1);
+    assert!(report.failures[0].suspect.ends_with("broken.png"));
+
+    let best = &report.matches[0];
+    assert!(best.suspect.ends_with("copy.png"));
+    assert!(best.sealed_dir.ends_with("pattern"));
+    assert_eq!(best.confidence, SimilarityConfidence::Exact);
+    assert_eq!(best.signature, SignatureStatus::Valid);
+
+    let brighter = report.matches.iter().find(|m| m.suspect.ends_with("brighter.png")).unwrap();
+    assert!(brighter.sealed_dir.ends_with("gradient"));
+    assert_eq!(brighter.signature, SignatureStatus::Unsigned);
+
+    let crop = report.matches.iter().find(|m| m.suspect.ends_with("crop.png")).unwrap();
+    assert!(crop.sealed_dir.ends_with("pattern"));
+    assert!(crop.crop_detected);
+    assert!(crop.tile_offset.is_some());
+
+    // Ranked best first.
+    let ranks: Vec<u8> = report.matches.iter().map(|m| m.confidence.rank()).collect();
+    assert!(ranks.windows(2).all(|w| w[0] >= w[1]));
+
+    let csv = report.to_csv();
+    assert!(csv.starts_with("rank,suspect,sealed_dir,confidence"));
+    assert_eq!(csv.lines().count(), report.matches.len() + 1);
+
+    // Pinning another key marks the signed record's signature invalid.
+    let other = root.join("other.pub");
+    SealedKeyPair::generate().save_public(&other).unwrap();
+    let config = ScanConfig { public_key: Some(other), min_confidence: SimilarityConfidence::Exact, ..ScanConfig::default() };
+    let report = scan_suspects(&suspects, &registry, &config);
+    assert_eq!(report.matches.len(), 1);
+    assert_eq!(report.matches[0].signature, SignatureStatus::Invalid);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn batch_items_are_checked_against_keys_in_any_format() {
+    use sealed::batch::{BatchItem, BatchManifest, SIGNED_BATCH_FILE};
+    use sealed::key_formats::{export_public_key, KeyFormat};
+    use sealed::registry::RegistryEntry;
+
+    let root = std::env::temp_dir().join("sealed_test_scan_batch_key");
+    let _ = std::fs::remove_dir_all(&root);
+    let key = SealedKeyPair::generate();
+    let img = make_patterned_image(320, 240);
+    let batch = root.join("batch");
+    let item = batch.join("0");
+    seal_into(&img, &item, None);
+    // A batch item carries no signed_record.json; the batch signature covers
+    // it through the Merkle root.
+    let record: SealedRecord = serde_json::from_str(&std::fs::read_to_string(item.join("hashes.json")).unwrap()).unwrap();
+    let mut manifest = BatchManifest::new();
+    manifest.items.push(BatchItem::new("0", "copy.png", "", &record).unwrap());
+    manifest.compute_root().unwrap();
+    manifest.save(&batch).unwrap();
+    let envelope = key.sign_canonical(&manifest).unwrap();
+    std::fs::write(batch.join(SIGNED_BATCH_FILE), serde_json::to_string(&envelope).unwrap()).unwrap();
+
+    let entry = RegistryEntry::from_sealed_dir(&item).unwrap();
+    assert_eq!(entry.signer.as_deref(), Some(key.public_key_base64().as_str()));
+    let registry = Registry { entries: vec![entry] };
+
+    let suspect = root.join("copy.png");
+    img.save(&suspect).unwrap();
+    let status = |public_key: Option<&std::path::Path>| {
+        let config = ScanConfig { public_key: public_key.map(|p| p.to_path_buf()), ..ScanConfig::default() };
+        scan_suspects(std::slice::from_ref(&suspect), &registry, &config).matches[0].signature
+    };
+
+    let pem = root.join("signer.pem");
+    std::fs::write(&pem, export_public_key(&key.verifying_key(), KeyFormat::Pkcs8, "").unwrap()).unwrap();
+    assert_eq!(status(Some(&pem)), SignatureStatus::Trusted);
+
+    let other = root.join("other.pub");
+    std::fs::write(&other, export_public_key(&SealedKeyPair::generate().verifying_key(), KeyFormat::Openssh, "").unwrap()).unwrap();
+    assert_eq!(status(Some(&other)), SignatureStatus::Valid);
+
+    // The leaf covers the record, not its layout.
+    std::fs::write(item.join("hashes.json"), serde_json::to_string(&record).unwrap()).unwrap();
+    assert_eq!(status(Some(&pem)), SignatureStatus::Trusted);
+
+    // A hashes.json the batch did not sign is not attributed to its key.
+    seal_into(&img, &item, None);
+    assert_eq!(status(None), SignatureStatus::Invalid);
+    assert_eq!(status(Some(&pem)), SignatureStatus::Invalid);
+    assert_eq!(RegistryEntry::from_sealed_dir(&item).unwrap().signer, None);
+
+    // Nor is an item directory dropped in beside the signed ones.
+    let forged = batch.join("forged");
+    seal_into(&img, &forged, None);
+    assert_eq!(RegistryEntry::from_sealed_dir(&forged).unwrap().signer, None);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
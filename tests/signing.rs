@@ -23,3 +23,204 @@ fn wrong_key_fails() {
     envelope.public_key = kp2.public_key_base64();
     assert!(envelope.verify().is_err());
 }
+
+#[test]
+fn canonical_json_follows_rfc_8785() {
+    use sealed::canonical::canonicalize_str;
+
+    // Number and ordering vectors from RFC 8785 sections 3.2.2 and 3.2.3.
+    let json = r#"{
+        "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000001, 1e-7, -0, 100, -5],
+        "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+        "literals": [null, true, false],
+        "\u20ac": 1, "\r": 2, "1": 3, "\ud83d\ude00": 4, "\u0080": 5, "\u00f6": 6, "\ufb33": 7
+    }"#;
+    assert_eq!(
+        canonicalize_str(json).unwrap(),
+        "{\"\\r\":2,\"1\":3,\"literals\":[null,true,false],\
+         \"numbers\":[333333333.3333333,1e+30,4.5,0.002,0.000001,1e-7,0,100,-5],\
+         \"string\":\"€$\\u000f\\nA'B\\\"\\\\\\\\\\\"/\",\"\u{80}\":5,\"ö\":6,\"€\":1,\"😀\":4,\"\u{fb33}\":7}"
+    );
+
+    // Numbers are doubles, whatever their source spelling.
+    assert_eq!(
+        canonicalize_str("[9007199254740993, 18446744073709551615, -9223372036854775807, 1e21, 123456789012345680000]").unwrap(),
+        "[9007199254740992,18446744073709552000,-9223372036854776000,1e+21,123456789012345680000]"
+    );
+    // A repeated member name is ambiguous, at any depth.
+    assert!(canonicalize_str(r#"{"a":1,"a":2}"#).is_err());
+    assert!(canonicalize_str(r#"[{"b":{"c":1,"c":1}}]"#).is_err());
+    assert!(canonicalize_str(r#"{"a":{"c":1},"b":{"c":1}}"#).is_ok());
+}
+
+#[test]
+fn canonical_signature_survives_reformatting() {
+    let kp = SealedKeyPair::generate();
+    let record = serde_json::json!({ "b": [1, 2], "a": { "sha256": "ab" } });
+    let envelope = kp.sign_canonical(&record).unwrap();
+    assert_eq!(envelope.canonicalization.as_deref(), Some("jcs"));
+    assert_eq!(envelope.payload, r#"{"a":{"sha256":"ab"},"b":[1,2]}"#);
+
+    let mut reformatted = envelope.clone();
+    reformatted.payload = serde_json::to_string_pretty(&record).unwrap();
+    assert!(reformatted.verify().is_ok());
+
+    let mut tampered = envelope.clone();
+    tampered.payload = r#"{"a":{"sha256":"ac"},"b":[1,2]}"#.to_string();
+    assert!(tampered.verify().is_err());
+
+    // Without the marker, the reformatted payload is no longer what was signed.
+    reformatted.canonicalization = None;
+    assert!(reformatted.verify().is_err());
+    let mut unknown = envelope;
+    unknown.canonicalization = Some("c14n".to_string());
+    assert!(unknown.verify().is_err());
+}
+
+#[test]
+fn record_signature_compares_the_whole_record() {
+    use image::{DynamicImage, Rgba, RgbaImage};
+    use sealed::hashing::compute_hash_record;
+    use sealed::verification::{record_signature_status, SealedRecord, SignatureStatus};
+
+    let dir = std::env::temp_dir().join("sealed_test_signing_record");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let hashes = compute_hash_record(&DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 48, |x, y| {
+        Rgba([(x * 4) as u8, (y * 5) as u8, 90, 255])
+    })))
+    .unwrap();
+    let mut record = SealedRecord {
+        original: hashes.clone(),
+        frame: hashes.clone(),
+        cropped: hashes.clone(),
+        recombined: hashes.clone(),
+        share: Some(hashes),
+        tile_index: None,
+        sealed_at: "2026-01-01T00:00:00+00:00".to_string(),
+        sealed_version: "2.0.0".to_string(),
+    };
+    let kp = SealedKeyPair::generate();
+    let envelope = kp.sign_canonical(&record).unwrap();
+    std::fs::write(dir.join("signed_record.json"), serde_json::to_string_pretty(&envelope).unwrap()).unwrap();
+
+    // hashes.json in any layout is fine.
+    std::fs::write(dir.join("hashes.json"), serde_json::to_string(&record).unwrap()).unwrap();
+    assert_eq!(record_signature_status(&dir, &record, None).unwrap(), SignatureStatus::Valid);
+
+    // A change outside original.sha256/blake3 is caught too.
+    record.frame.width += 1;
+    assert_eq!(record_signature_status(&dir, &record, None).unwrap(), SignatureStatus::Invalid);
+    record.frame.width -= 1;
+
+    // A key pinned with --public-key may be a co-signer, but only alongside
+    // a primary signature that holds.
+    let agency = SealedKeyPair::generate();
+    let agency_pub = dir.join("agency.pub");
+    agency.save_public(&agency_pub).unwrap();
+    let mut cosigned = envelope.clone();
+    cosigned.cosign(&agency).unwrap();
+    std::fs::write(dir.join("signed_record.json"), serde_json::to_string(&cosigned).unwrap()).unwrap();
+    assert_eq!(record_signature_status(&dir, &record, Some(&agency_pub)).unwrap(), SignatureStatus::Trusted);
+    cosigned.signature = cosigned.cosignatures[0].signature.clone();
+    std::fs::write(dir.join("signed_record.json"), serde_json::to_string(&cosigned).unwrap()).unwrap();
+    assert!(cosigned.verify_cosignature_with_key(&agency_pub).is_ok());
+    assert_eq!(record_signature_status(&dir, &record, Some(&agency_pub)).unwrap(), SignatureStatus::Invalid);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn envelopes_carry_a_stable_key_id() {
+    let keypair = SealedKeyPair::generate();
+    let envelope = keypair.sign("payload");
+    let id = keypair.key_id();
+    assert_eq!(id.len(), 32);
+    assert_eq!(envelope.key_id.as_deref(), Some(id.as_str()));
+    assert_eq!(sealed::signing::key_id_from_base64(&keypair.public_key_base64()).unwrap(), id);
+    assert!(envelope.verify().is_ok());
+
+    // Envelopes from before key IDs still verify; a mismatched ID does not.
+    let mut legacy = envelope.clone();
+    legacy.key_id = None;
+    assert!(legacy.verify().is_ok());
+    let mut wrong = envelope;
+    wrong.key_id = Some(SealedKeyPair::generate().key_id());
+    assert!(wrong.verify().is_err());
+}
+
+#[test]
+fn cosignatures_are_checked_independently() {
+    let (photographer, agency, client) = (SealedKeyPair::generate(), SealedKeyPair::generate(), SealedKeyPair::generate());
+    let mut envelope = photographer.sign_canonical(&serde_json::json!({"work": "harbour at dusk"})).unwrap();
+    envelope.cosign(&agency).unwrap();
+    envelope.cosign(&client).unwrap();
+    assert!(envelope.cosign(&agency).is_err(), "a key signs once");
+    assert!(envelope.cosign(&photographer).is_err(), "the primary signer cannot co-sign");
+
+    // Co-signatures survive a JSON round trip and reformatting of the payload.
+    let mut envelope: sealed::signing::SignedEnvelope =
+        serde_json::from_str(&serde_json::to_string_pretty(&envelope).unwrap()).unwrap();
+    envelope.payload = "{ \"work\" : \"harbour at dusk\" }".to_string();
+    assert!(envelope.verify().is_ok());
+    assert!(envelope.verify_cosignatures().iter().all(|r| r.is_ok()));
+
+    // A broken co-signature fails on its own without touching the others.
+    envelope.cosignatures[0].signature = envelope.cosignatures[1].signature.clone();
+    let results = envelope.verify_cosignatures();
+    assert!(results[0].is_err() && results[1].is_ok());
+    assert!(envelope.verify().is_ok());
+
+    // A changed payload breaks every signature.
+    envelope.payload = "{\"work\":\"something else\"}".to_string();
+    assert!(envelope.verify().is_err());
+    assert!(envelope.verify_cosignatures().iter().all(|r| r.is_err()));
+}
+
+#[test]
+fn encrypted_keys_record_their_kdf_parameters() {
+    use aes_gcm::aead::Aead;
+    use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+    use sealed::signing::{encrypted_key_format, KdfAlgorithm, KdfParams, ENCRYPTED_KEY_MAGIC};
+
+    let dir = std::env::temp_dir().join("sealed_test_encrypted_keys");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let keypair = SealedKeyPair::generate();
+    let path = dir.join("sealed.key");
+
+    let params = KdfParams { algorithm: KdfAlgorithm::Argon2i, memory_kib: 1024, iterations: 3, ..KdfParams::default() };
+    keypair.save_secret_encrypted_with(&path, "hunter2", &params).unwrap();
+    let data = std::fs::read(&path).unwrap();
+    assert_eq!(encrypted_key_format(&data).unwrap(), (2, params));
+    assert_eq!(SealedKeyPair::load_encrypted(&path, "hunter2").unwrap().key_id(), keypair.key_id());
+    assert!(SealedKeyPair::load_encrypted(&path, "hunter3").is_err());
+
+    // The parameters are authenticated: lowering the stored iterations breaks the key.
+    let mut lowered = data.clone();
+    lowered[b"SEALED_ENC_V2".len() + 12] = 2;
+    std::fs::write(&path, &lowered).unwrap();
+    assert!(SealedKeyPair::load_encrypted(&path, "hunter2").is_err());
+
+    // Files without a known header, or of the wrong length, are refused outright.
+    for bad in [&data[b"SEALED_ENC_V2".len()..], &data[..data.len() - 1], &[b"SEALED_ENC_V9".as_slice(), &data[13..]].concat()] {
+        std::fs::write(&path, bad).unwrap();
+        assert!(SealedKeyPair::load_encrypted(&path, "hunter2").is_err());
+    }
+
+    // Keys written before the parameters were recorded still load.
+    let (salt, nonce) = ([7u8; 16], [9u8; 12]);
+    let mut derived = [0u8; 32];
+    argon2::Argon2::default().hash_password_into(b"hunter2", &salt, &mut derived).unwrap();
+    let ciphertext = Aes256Gcm::new_from_slice(&derived)
+        .unwrap()
+        .encrypt(Nonce::from_slice(&nonce), sealed::key_formats::export_secret_key(&keypair, sealed::key_formats::KeyFormat::Raw, "").unwrap().as_slice())
+        .unwrap();
+    std::fs::write(&path, [ENCRYPTED_KEY_MAGIC, &salt, &nonce, &ciphertext].concat()).unwrap();
+    assert_eq!(encrypted_key_format(&std::fs::read(&path).unwrap()).unwrap(), (1, KdfParams::default()));
+    assert_eq!(SealedKeyPair::load_encrypted(&path, "hunter2").unwrap().key_id(), keypair.key_id());
+
+    assert!(KdfParams { memory_kib: 1 << 30, ..KdfParams::default() }.validate().is_err());
+    assert!(KdfParams { iterations: 0, ..KdfParams::default() }.validate().is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,102 @@
+use sealed::signing::SealedKeyPair;
+use sealed::statements::{read_statement, revocation_statement, rotation_statement, verify_statement, KeyStatement};
+use sealed::trust::{KeyTrust, TrustStore, TrustedKey};
+
+#[test]
+fn rotation_hands_trust_over_to_the_new_key() {
+    let (old, new) = (SealedKeyPair::generate(), SealedKeyPair::generate());
+    let envelope = rotation_statement(&old, &new.public_key_base64(), "2026-05-01").unwrap();
+    let statement = verify_statement(&envelope).unwrap();
+    match &statement {
+        KeyStatement::Rotation { old_key_id, new_key_id, effective_at, .. } => {
+            assert_eq!(*old_key_id, old.key_id());
+            assert_eq!(*new_key_id, new.key_id());
+            assert_eq!(effective_at, "2026-05-01T00:00:00+00:00");
+        }
+        other => panic!("expected a rotation, got {:?}", other),
+    }
+
+    let mut store = TrustStore::default();
+    // The old key has to be trusted already.
+    assert!(store.apply_statement(&statement, None).is_err());
+    store.add(TrustedKey::new("studio", &old.public_key_base64())).unwrap();
+    let name = store.apply_statement(&statement, None).unwrap();
+    assert_eq!(name, format!("studio-{}", &new.key_id()[..8]));
+
+    let (before, after) = ("2026-04-01T00:00:00Z", "2026-06-01T00:00:00Z");
+    assert!(store.assess(&old.public_key_base64(), before).is_trusted());
+    assert_eq!(store.assess(&old.public_key_base64(), after), KeyTrust::OutsideValidity { name: "studio".to_string() });
+    assert_eq!(store.assess(&new.public_key_base64(), after), KeyTrust::Trusted { name: name.clone() });
+    assert!(!store.assess(&new.public_key_base64(), before).is_trusted());
+    assert_eq!(store.keys[0].replaced_by.as_deref(), Some(new.key_id().as_str()));
+    assert!(store.apply_statement(&statement, Some("again")).is_err(), "the new key is already trusted");
+}
+
+#[test]
+fn revocation_untrusts_seals_from_its_effective_date() {
+    let key = SealedKeyPair::generate();
+    let dir = std::env::temp_dir().join("sealed_test_statements");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("key_revocation.json");
+    let envelope = revocation_statement(&key, "2026-03-01T12:00:00Z", Some("laptop stolen")).unwrap();
+    std::fs::write(&path, serde_json::to_string_pretty(&envelope).unwrap()).unwrap();
+    let statement = read_statement(&path).unwrap();
+
+    let mut store = TrustStore::default();
+    store.add(TrustedKey::new("studio", &key.public_key_base64())).unwrap();
+    assert_eq!(store.apply_statement(&statement, None).unwrap(), "studio");
+    assert!(store.assess(&key.public_key_base64(), "2026-03-01T11:59:59Z").is_trusted());
+    assert_eq!(
+        store.assess(&key.public_key_base64(), "2026-03-01T12:00:00Z"),
+        KeyTrust::Revoked { name: "studio".to_string(), reason: Some("laptop stolen".to_string()) }
+    );
+
+    // A key the store has never seen is recorded as revoked outright: a
+    // self-signed revocation must not make earlier seals trusted.
+    let mut empty = TrustStore::default();
+    assert_eq!(empty.apply_statement(&statement, None).unwrap(), key.key_id());
+    for at in ["2020-01-01T00:00:00Z", "2026-04-01T00:00:00Z"] {
+        assert!(matches!(empty.assess(&key.public_key_base64(), at), KeyTrust::Revoked { .. }), "{}", at);
+        assert!(!empty.assess_seal(&key.public_key_base64(), at, Some(at)).is_trusted(), "{}", at);
+    }
+    assert_eq!(empty.keys[0].revoked_effective, None);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn forged_statements_are_rejected() {
+    let (key, other) = (SealedKeyPair::generate(), SealedKeyPair::generate());
+
+    // Someone else revoking our key.
+    let forged = other
+        .sign_canonical(&KeyStatement::Revocation {
+            key_id: key.key_id(),
+            public_key: key.public_key_base64(),
+            effective_at: "2026-01-01T00:00:00+00:00".to_string(),
+            issued_at: "2026-01-01T00:00:00+00:00".to_string(),
+            reason: None,
+        })
+        .unwrap();
+    assert!(verify_statement(&forged).is_err());
+
+    // A key ID that does not belong to the key.
+    let mislabelled = key
+        .sign_canonical(&KeyStatement::Revocation {
+            key_id: other.key_id(),
+            public_key: key.public_key_base64(),
+            effective_at: "2026-01-01T00:00:00+00:00".to_string(),
+            issued_at: "2026-01-01T00:00:00+00:00".to_string(),
+            reason: None,
+        })
+        .unwrap();
+    assert!(verify_statement(&mislabelled).is_err());
+
+    // A rotation whose payload was edited after signing.
+    let mut edited = rotation_statement(&key, &other.public_key_base64(), "2026-05-01").unwrap();
+    edited.payload = edited.payload.replace("2026-05-01", "2020-05-01");
+    assert!(verify_statement(&edited).is_err());
+
+    assert!(rotation_statement(&key, &key.public_key_base64(), "2026-05-01").is_err());
+}
@@ -0,0 +1,213 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use sha2::{Digest, Sha256};
+use tiny_http::{Method, Response, Server};
+
+use sealed::ots::{verify_proof, Attestation, BlockHeader, DetachedTimestampFile, Op, Timestamp};
+use sealed::timestamp::{timestamp_hash_with, try_upgrade_ots, CalendarConfig, TimestampRecord};
+use sealed::upgrade_queue::{enqueue, process_queue, QueueEntry, QueueLock, UpgradeQueue};
+
+/// Helper: a local calendar that answers `/digest` with a pending attestation
+/// and `/timestamp/<commitment>` with a Bitcoin attestation at `height`.
+fn spawn_mock_calendar(height: u64) -> String {
+    spawn_calendar(height, false)
+}
+
+/// Helper: like `spawn_mock_calendar`, but `garbled` upgrades are not a timestamp.
+fn spawn_calendar(height: u64, garbled: bool) -> String {
+    let server = Server::http("127.0.0.1:0").expect("bind mock calendar");
+    let port = server.server_addr().to_ip().unwrap().port();
+    let base = format!("http://127.0.0.1:{}", port);
+    let pending: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+
+    let uri = base.clone();
+    thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let url = request.url().to_string();
+            match (request.method(), url.as_str()) {
+                (Method::Post, "/digest") => {
+                    let mut body = Vec::new();
+                    request.as_reader().read_to_end(&mut body).unwrap();
+                    let prefix = height.to_le_bytes().to_vec();
+                    let commitment = Sha256::digest([prefix.clone(), body].concat());
+                    pending.lock().unwrap().push(hex::encode(commitment));
+
+                    let stamp = Timestamp {
+                        attestations: vec![],
+                        ops: vec![(Op::Prepend(prefix), Timestamp {
+                            attestations: vec![],
+                            ops: vec![(Op::Sha256, Timestamp {
+                                attestations: vec![Attestation::Pending { uri: uri.clone() }],
+                                ops: vec![],
+                            })],
+                        })],
+                    };
+                    let mut out = Vec::new();
+                    stamp.serialize(&mut out);
+                    request.respond(Response::from_data(out)).unwrap();
+                }
+                (Method::Get, path) if path.starts_with("/timestamp/") => {
+                    let commitment = path.trim_start_matches("/timestamp/");
+                    if !pending.lock().unwrap().iter().any(|c| c == commitment) {
+                        request.respond(Response::empty(404)).unwrap();
+                        continue;
+                    }
+                    if garbled {
+                        request.respond(Response::from_data(vec![0xff; 7])).unwrap();
+                        continue;
+                    }
+                    let stamp = Timestamp {
+                        attestations: vec![],
+                        ops: vec![(Op::Append(vec![0x42; 32]), Timestamp {
+                            attestations: vec![],
+                            ops: vec![(Op::Sha256, Timestamp {
+                                attestations: vec![Attestation::Bitcoin { height }],
+                                ops: vec![],
+                            })],
+                        })],
+                    };
+                    let mut out = Vec::new();
+                    stamp.serialize(&mut out);
+                    request.respond(Response::from_data(out)).unwrap();
+                }
+                _ => request.respond(Response::empty(404)).unwrap(),
+            }
+        }
+    });
+
+    base
+}
+
+fn test_config(calendars: Vec<String>, min_calendars: usize) -> CalendarConfig {
+    CalendarConfig {
+        calendars,
+        min_calendars,
+        timeout_secs: 5,
+    }
+}
+
+#[test]
+fn submit_merges_calendars_and_upgrade_completes_proof() {
+    let a = spawn_mock_calendar(800_000);
+    let b = spawn_mock_calendar(800_001);
+    let config = test_config(vec![a.clone(), b.clone()], 2);
+
+    let tmp = std::env::temp_dir().join("sealed_test_ots_mock");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).unwrap();
+
+    let sha256 = hex::encode(Sha256::digest(b"sealed image pixels"));
+    let record = timestamp_hash_with(&sha256, &tmp, &config).expect("submission failed");
+    assert_eq!(record.calendars, vec![a.clone(), b.clone()]);
+    assert_eq!(record.status, "pending");
+
+    let ots_path = tmp.join("timestamp.ots");
+    let proof = DetachedTimestampFile::load(&ots_path).unwrap();
+    // Nonce op first, so calendars never see the raw sealed hash.
+    assert!(matches!(proof.timestamp.ops[0].0, Op::Append(ref n) if n.len() == 16));
+
+    let pending = verify_proof(&proof, &sha256, None).unwrap();
+    assert!(pending.digest_matches);
+    assert_eq!(pending.pending_calendars, vec![a, b]);
+    assert!(pending.bitcoin.is_empty());
+
+    assert!(try_upgrade_ots(&ots_path, &config).expect("upgrade failed"));
+
+    let upgraded = DetachedTimestampFile::load(&ots_path).unwrap();
+    assert!(upgraded.timestamp.is_complete());
+    let result = verify_proof(&upgraded, &sha256, None).unwrap();
+    assert_eq!(result.bitcoin.len(), 2);
+
+    let mut raw = [0u8; 80];
+    raw[36..68].copy_from_slice(&hex::decode(&result.bitcoin[0].commitment).unwrap());
+    let header = BlockHeader::from_bytes(&raw).unwrap();
+    assert!(verify_proof(&upgraded, &sha256, Some(&header)).unwrap().is_verified());
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn a_garbled_calendar_does_not_block_the_others() {
+    let bad = spawn_calendar(800_000, true);
+    let good = spawn_mock_calendar(800_001);
+    let config = test_config(vec![bad, good], 2);
+
+    let tmp = std::env::temp_dir().join("sealed_test_ots_garbled");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).unwrap();
+
+    let sha256 = hex::encode(Sha256::digest(b"one bad calendar"));
+    timestamp_hash_with(&sha256, &tmp, &config).unwrap();
+    let ots_path = tmp.join("timestamp.ots");
+    assert!(try_upgrade_ots(&ots_path, &config).expect("upgrade failed"));
+    let upgraded = DetachedTimestampFile::load(&ots_path).unwrap();
+    assert_eq!(verify_proof(&upgraded, &sha256, None).unwrap().bitcoin.len(), 1);
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn submit_fails_below_minimum_calendars() {
+    let a = spawn_mock_calendar(800_000);
+    let config = test_config(vec![a, "http://127.0.0.1:9".to_string()], 2);
+
+    let tmp = std::env::temp_dir().join("sealed_test_ots_min");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).unwrap();
+
+    let sha256 = hex::encode(Sha256::digest(b"sealed"));
+    assert!(timestamp_hash_with(&sha256, &tmp, &config).is_err());
+    assert!(!tmp.join("timestamp.ots").exists());
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn upgrade_queue_survives_and_drains() {
+    let a = spawn_mock_calendar(800_000);
+    let config = test_config(vec![a.clone()], 1);
+
+    let tmp = std::env::temp_dir().join("sealed_test_ots_queue");
+    let _ = std::fs::remove_dir_all(&tmp);
+    let sealed_dir = tmp.join("sealed");
+    std::fs::create_dir_all(&sealed_dir).unwrap();
+    let queue_path = tmp.join("ots_queue.json");
+
+    let sha256 = hex::encode(Sha256::digest(b"queued seal"));
+    let record = timestamp_hash_with(&sha256, &sealed_dir, &config).unwrap();
+    enqueue(&queue_path, QueueEntry::new(&sha256, &sealed_dir, vec![], None)).unwrap();
+    enqueue(&queue_path, QueueEntry::new(&sha256, &tmp.join("gone"), vec![], None)).unwrap();
+    assert_eq!(UpgradeQueue::load(&queue_path).unwrap().entries.len(), 2);
+
+    // Calendar neither in the config nor in the entry: nothing can be upgraded,
+    // so the entry backs off.
+    let offline = test_config(vec![], 1);
+    let summary = process_queue(&queue_path, &offline, None, true).unwrap();
+    assert_eq!(summary.dropped.len(), 1);
+    assert_eq!(summary.still_pending, 1);
+    let queue = UpgradeQueue::load(&queue_path).unwrap();
+    assert_eq!(queue.entries[0].attempts, 1);
+    assert!(queue.entries[0].next_retry > chrono::Utc::now());
+
+    // Not due yet, so an unforced pass leaves it alone.
+    let summary = process_queue(&queue_path, &config, None, false).unwrap();
+    assert!(summary.confirmed.is_empty());
+    assert_eq!(summary.still_pending, 1);
+
+    // The calendars recorded in the entry are enough, whatever the worker's config.
+    enqueue(&queue_path, QueueEntry::new(&sha256, &sealed_dir, record.calendars, None)).unwrap();
+    let lock = QueueLock::acquire(&queue_path).unwrap().unwrap();
+    assert!(QueueLock::acquire(&queue_path).unwrap().is_none());
+    let summary = process_queue(&queue_path, &offline, Some(&lock), true).unwrap();
+    assert_eq!(summary.confirmed.len(), 1);
+    assert_eq!(summary.still_pending, 0);
+    drop(lock);
+    assert!(QueueLock::acquire(&queue_path).unwrap().is_some());
+
+    let json = std::fs::read_to_string(sealed_dir.join("timestamp_record.json")).unwrap();
+    let record: TimestampRecord = serde_json::from_str(&json).unwrap();
+    assert_eq!(record.status, "confirmed");
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
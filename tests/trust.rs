@@ -0,0 +1,245 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use sealed::image_processing::{seal_image, save_artifacts, SealConfig};
+use sealed::signing::SealedKeyPair;
+use sealed::trust::{normalize_time, read_public_key, KeyTrust, TrustStore, TrustedKey};
+use sealed::ots::{Attestation, BlockHeader, DetachedTimestampFile, Op, Timestamp};
+use sealed::verification::{proven_sealing_time, verify_image, SealedRecord, SignatureStatus, SignerPolicy, TimeAnchors};
+
+fn trusted(name: &str, key: &SealedKeyPair, not_before: Option<&str>, not_after: Option<&str>) -> TrustedKey {
+    let mut trusted = TrustedKey::new(name, &key.public_key_base64());
+    trusted.not_before = not_before.map(|t| normalize_time(t, false).unwrap());
+    trusted.not_after = not_after.map(|t| normalize_time(t, true).unwrap());
+    trusted
+}
+
+#[test]
+fn trust_store_judges_keys_by_period_and_revocation() {
+    let path = std::env::temp_dir().join("sealed_test_trust").join("trust.json");
+    let _ = std::fs::remove_file(&path);
+    let (alice, bob, mallory) = (SealedKeyPair::generate(), SealedKeyPair::generate(), SealedKeyPair::generate());
+
+    let mut store = TrustStore::load(&path).unwrap();
+    store.add(trusted("alice", &alice, None, None)).unwrap();
+    store.add(trusted("bob", &bob, Some("2025-01-01"), Some("2025-12-31"))).unwrap();
+    assert!(store.add(trusted("alice", &mallory, None, None)).is_err(), "names are unique");
+    assert!(store.add(trusted("alice2", &alice, None, None)).is_err(), "keys are unique");
+    store.save(&path).unwrap();
+
+    let store = TrustStore::load(&path).unwrap();
+    let at = "2025-06-01T12:00:00+00:00";
+    assert_eq!(store.assess(&alice.public_key_base64(), at), KeyTrust::Trusted { name: "alice".to_string() });
+    assert_eq!(store.assess(&bob.public_key_base64(), at), KeyTrust::Trusted { name: "bob".to_string() });
+    // The last day of the period is included.
+    assert!(store.assess(&bob.public_key_base64(), "2025-12-31T20:00:00Z").is_trusted());
+    assert_eq!(
+        store.assess(&bob.public_key_base64(), "2026-01-02T00:00:00Z"),
+        KeyTrust::OutsideValidity { name: "bob".to_string() }
+    );
+    assert_eq!(store.assess(&bob.public_key_base64(), "not a time"), KeyTrust::OutsideValidity { name: "bob".to_string() });
+    assert_eq!(store.assess(&mallory.public_key_base64(), at), KeyTrust::Unknown);
+
+    let mut store = store;
+    store.revoke("alice", Some("key leaked"), None).unwrap();
+    assert_eq!(
+        store.assess(&alice.public_key_base64(), at),
+        KeyTrust::Revoked { name: "alice".to_string(), reason: Some("key leaked".to_string()) }
+    );
+    assert!(store.revoke("nobody", None, None).is_err());
+
+    // A dated revocation leaves earlier seals trusted; revoking by key ID works too.
+    store.revoke(&bob.key_id(), None, Some("2025-07-01T00:00:00Z")).unwrap();
+    assert!(store.assess(&bob.public_key_base64(), at).is_trusted());
+    assert!(matches!(store.assess(&bob.public_key_base64(), "2025-08-01T00:00:00Z"), KeyTrust::Revoked { .. }));
+    // Revoking again without a date widens it to every seal.
+    store.revoke("bob", None, None).unwrap();
+    assert!(matches!(store.assess(&bob.public_key_base64(), at), KeyTrust::Revoked { .. }));
+
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn public_keys_are_read_from_files_or_base64() {
+    let dir = std::env::temp_dir().join("sealed_test_trust_keys");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let key = SealedKeyPair::generate();
+    key.save_public(&dir.join("sealed.pub")).unwrap();
+    std::fs::write(dir.join("key.b64"), format!("{}\n", key.public_key_base64())).unwrap();
+
+    let expected = key.public_key_base64();
+    assert_eq!(read_public_key(dir.join("sealed.pub").to_str().unwrap()).unwrap(), expected);
+    assert_eq!(read_public_key(dir.join("key.b64").to_str().unwrap()).unwrap(), expected);
+    assert_eq!(read_public_key(&expected).unwrap(), expected);
+    assert!(read_public_key("AAAA").is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// Helper: seal a small image into `dir`, signed by `key` with a fixed
+/// sealing time. Returns the suspect path (a copy of the original).
+fn seal_signed_by(dir: &std::path::Path, key: &SealedKeyPair) -> std::path::PathBuf {
+    let _ = std::fs::remove_dir_all(dir);
+    let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(120, 90, |x, y| Rgba([(x * 2) as u8, (y * 2) as u8, 60, 255])));
+    let artifacts = seal_image(&img, &SealConfig::default()).unwrap();
+    save_artifacts(&artifacts, dir).unwrap();
+    let record = SealedRecord {
+        original: artifacts.original_hashes.clone(),
+        frame: artifacts.frame_hashes.clone(),
+        cropped: artifacts.cropped_hashes.clone(),
+        recombined: artifacts.recombined_hashes.clone(),
+        share: Some(artifacts.share_hashes.clone()),
+        tile_index: None,
+        sealed_at: "2026-03-01T09:00:00+00:00".to_string(),
+        sealed_version: "2.0.0".to_string(),
+    };
+    std::fs::write(dir.join("hashes.json"), serde_json::to_string_pretty(&record).unwrap()).unwrap();
+    let envelope = key.sign_canonical(&record).unwrap();
+    std::fs::write(dir.join("signed_record.json"), serde_json::to_string(&envelope).unwrap()).unwrap();
+    let suspect = dir.join("suspect.png");
+    img.save(&suspect).unwrap();
+    suspect
+}
+
+#[test]
+fn verification_names_the_trusted_signer() {
+    let dir = std::env::temp_dir().join("sealed_test_trust_verify");
+    let key = SealedKeyPair::generate();
+    let suspect = seal_signed_by(&dir, &key);
+
+    let mut result = verify_image(&suspect, &dir, None).unwrap();
+    result.apply_trust(&TrustStore::default());
+    assert_eq!(result.signature, SignatureStatus::Valid);
+    assert!(result.summary().contains("Valid signature by unknown key"), "{}", result.summary());
+
+    let mut store = TrustStore::default();
+    store.add(trusted("newsroom", &key, Some("2026-01-01"), None)).unwrap();
+    result.apply_trust(&store);
+    assert_eq!(result.signature, SignatureStatus::Trusted);
+    assert!(result.summary().contains("Signed by newsroom (trusted)"), "{}", result.summary());
+    assert!(!result.signer_distrusted());
+
+    store.revoke("newsroom", None, None).unwrap();
+    result.apply_trust(&store);
+    assert_eq!(result.signature, SignatureStatus::Valid);
+    assert!(result.signer_distrusted());
+    assert!(result.summary().contains("REVOKED"), "{}", result.summary());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cosigners_are_reported_and_counted_against_a_policy() {
+    let dir = std::env::temp_dir().join("sealed_test_trust_cosign");
+    let (photographer, agency, client) = (SealedKeyPair::generate(), SealedKeyPair::generate(), SealedKeyPair::generate());
+    let suspect = seal_signed_by(&dir, &photographer);
+    let signed_path = dir.join("signed_record.json");
+    let mut envelope: sealed::signing::SignedEnvelope =
+        serde_json::from_str(&std::fs::read_to_string(&signed_path).unwrap()).unwrap();
+    envelope.cosign(&agency).unwrap();
+    envelope.cosign(&client).unwrap();
+    std::fs::write(&signed_path, serde_json::to_string_pretty(&envelope).unwrap()).unwrap();
+
+    let mut store = TrustStore::default();
+    store.add(trusted("photographer", &photographer, None, None)).unwrap();
+    store.add(trusted("agency", &agency, None, None)).unwrap();
+
+    let mut result = verify_image(&suspect, &dir, None).unwrap();
+    result.apply_trust(&store);
+    assert_eq!(result.cosigners.len(), 2);
+    assert_eq!(result.cosigners[0].trust, KeyTrust::Trusted { name: "agency".to_string() });
+    assert_eq!(result.cosigners[1].trust, KeyTrust::Unknown);
+    assert!(result.summary().contains("Co-signed by agency (trusted), unknown key"), "{}", result.summary());
+
+    result.apply_policy(&SignerPolicy::new(2, Vec::new()).unwrap());
+    assert!(result.policy.as_ref().unwrap().satisfied);
+    assert!(!result.cosigning_failed());
+    // The client's key is not trusted, so naming it cannot help.
+    result.apply_policy(&SignerPolicy::new(2, vec!["agency".to_string(), client.key_id()]).unwrap());
+    let outcome = result.policy.as_ref().unwrap();
+    assert_eq!(outcome.satisfied_by, ["agency"]);
+    assert!(!outcome.satisfied);
+    assert!(result.cosigning_failed());
+    assert!(SignerPolicy::new(3, vec!["agency".to_string()]).is_err());
+    assert!(SignerPolicy::new(0, Vec::new()).is_err());
+
+    // A revoked co-signer distrusts the whole result; a forged one is listed apart.
+    store.revoke("agency", None, None).unwrap();
+    result.apply_trust(&store);
+    assert_eq!(result.signature, SignatureStatus::Trusted);
+    assert!(result.signer_distrusted());
+    envelope.cosignatures[1].signature = envelope.cosignatures[0].signature.clone();
+    std::fs::write(&signed_path, serde_json::to_string_pretty(&envelope).unwrap()).unwrap();
+    let result = verify_image(&suspect, &dir, None).unwrap();
+    assert_eq!(result.cosigners.len(), 1);
+    assert_eq!(result.invalid_cosignatures, [client.key_id()]);
+    assert!(result.cosigning_failed());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// Helper: write a `timestamp.ots` in `dir` attesting `sha256_hex` in a
+/// Bitcoin block, and return that block's header with time `unix_time`.
+fn attest_in_block(dir: &std::path::Path, sha256_hex: &str, unix_time: u32) -> BlockHeader {
+    use sha2::{Digest, Sha256};
+    let digest = hex::decode(sha256_hex).unwrap();
+    let proof = DetachedTimestampFile {
+        file_hash_op: Op::Sha256,
+        digest: digest.clone(),
+        timestamp: Timestamp {
+            attestations: vec![],
+            ops: vec![(Op::Sha256, Timestamp { attestations: vec![Attestation::Bitcoin { height: 950_000 }], ops: vec![] })],
+        },
+    };
+    std::fs::write(dir.join("timestamp.ots"), proof.serialize()).unwrap();
+    let mut raw = [0u8; 80];
+    raw[36..68].copy_from_slice(&Sha256::digest(&digest));
+    raw[68..72].copy_from_slice(&unix_time.to_le_bytes());
+    BlockHeader::from_bytes(&raw).unwrap()
+}
+
+#[test]
+fn backdated_seals_do_not_escape_a_revocation() {
+    let dir = std::env::temp_dir().join("sealed_test_trust_backdated");
+    let key = SealedKeyPair::generate();
+    // The record claims 2026-03-01, but whoever holds the key can write any date there.
+    let suspect = seal_signed_by(&dir, &key);
+    let mut store = TrustStore::default();
+    store.add(trusted("studio", &key, None, None)).unwrap();
+    store.revoke("studio", Some("key stolen"), Some("2026-06-01T00:00:00Z")).unwrap();
+
+    let mut result = verify_image(&suspect, &dir, None).unwrap();
+    assert_eq!(result.sealed_record.sealed_at, "2026-03-01T09:00:00+00:00");
+    result.apply_trust(&store);
+    assert!(matches!(result.signer.as_ref().unwrap().trust, KeyTrust::Revoked { .. }));
+    assert!(result.signer_distrusted());
+    assert!(result.summary().contains("No verified timestamp"), "{}", result.summary());
+
+    // A block mined after the revocation shows the seal is no older than that.
+    let sha256 = result.sealed_record.original.sha256.clone();
+    let late = TimeAnchors { block_header: Some(attest_in_block(&dir, &sha256, 1_782_864_000)), ..TimeAnchors::default() };
+    result.proven_time = proven_sealing_time(&dir, &sha256, &late);
+    assert_eq!(result.proven_time.as_ref().unwrap().time, "2026-07-01T00:00:00+00:00");
+    result.apply_trust(&store);
+    assert!(matches!(result.signer.as_ref().unwrap().trust, KeyTrust::Revoked { .. }));
+
+    // One mined before it proves the seal predates the revocation.
+    let early = TimeAnchors { block_header: Some(attest_in_block(&dir, &sha256, 1_775_001_600)), ..TimeAnchors::default() };
+    result.proven_time = proven_sealing_time(&dir, &sha256, &early);
+    result.apply_trust(&store);
+    assert_eq!(result.signature, SignatureStatus::Trusted);
+    // A header that does not confirm the proof is no evidence.
+    let unrelated = TimeAnchors { block_header: Some(BlockHeader::from_bytes(&[0u8; 80]).unwrap()), ..TimeAnchors::default() };
+    assert!(proven_sealing_time(&dir, &sha256, &unrelated).is_none());
+    assert!(proven_sealing_time(&dir, &sha256, &TimeAnchors::default()).is_none());
+
+    // The end of a validity period needs the same evidence.
+    let mut store = TrustStore::default();
+    store.add(trusted("studio", &key, None, Some("2026-12-31"))).unwrap();
+    result.proven_time = None;
+    result.apply_trust(&store);
+    assert_eq!(result.signer.as_ref().unwrap().trust, KeyTrust::OutsideValidity { name: "studio".to_string() });
+    assert_eq!(store.assess_seal(&key.public_key_base64(), "2026-03-01T09:00:00Z", Some("2026-04-01T00:00:00Z")), KeyTrust::Trusted { name: "studio".to_string() });
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,207 @@
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use cms::builder::{SignedDataBuilder, SignerInfoBuilder};
+use cms::cert::{CertificateChoices, IssuerAndSerialNumber};
+use cms::signed_data::{EncapsulatedContentInfo, SignerIdentifier};
+use der::asn1::{Any, ObjectIdentifier, OctetString};
+use der::{Decode, Encode, Tag};
+use p256::ecdsa::{DerSignature, SigningKey};
+use sha2::{Digest, Sha256};
+use tiny_http::{Response, Server};
+use x509_cert::builder::{Builder, CertificateBuilder, Profile};
+use x509_cert::ext::pkix::ExtendedKeyUsage;
+use x509_cert::name::Name;
+use x509_cert::serial_number::SerialNumber;
+use x509_cert::spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned};
+use x509_cert::time::Validity;
+use x509_cert::Certificate;
+
+use sealed::timestamp::{timestamp_hash_tsa, TsaConfig, TSR_FILE};
+use sealed::tsa::{
+    load_trust_anchor, verify_token, PkiStatusInfo, TimeStampReq, TimeStampResp, TstInfo,
+};
+
+const ID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+const ID_CT_TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+const ID_KP_TIME_STAMPING: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.8");
+
+/// Helper: a self-signed root and a TSA certificate it issued.
+struct TestPki {
+    root: Certificate,
+    tsa_cert: Certificate,
+    tsa_key: SigningKey,
+}
+
+fn make_pki(root_cn: &str) -> TestPki {
+    let root_key = SigningKey::random(&mut rand::rngs::OsRng);
+    let root_name = Name::from_str(&format!("CN={}", root_cn)).unwrap();
+    let root = CertificateBuilder::new(
+        Profile::Root,
+        SerialNumber::from(1u32),
+        Validity::from_now(Duration::from_secs(3600)).unwrap(),
+        root_name.clone(),
+        SubjectPublicKeyInfoOwned::from_key(*root_key.verifying_key()).unwrap(),
+        &root_key,
+    )
+    .unwrap()
+    .build::<DerSignature>()
+    .unwrap();
+
+    let tsa_key = SigningKey::random(&mut rand::rngs::OsRng);
+    let mut builder = CertificateBuilder::new(
+        Profile::Leaf {
+            issuer: root_name,
+            enable_key_agreement: false,
+            enable_key_encipherment: false,
+        },
+        SerialNumber::from(2u32),
+        Validity::from_now(Duration::from_secs(3600)).unwrap(),
+        Name::from_str("CN=Sealed Test TSA").unwrap(),
+        SubjectPublicKeyInfoOwned::from_key(*tsa_key.verifying_key()).unwrap(),
+        &root_key,
+    )
+    .unwrap();
+    builder.add_extension(&ExtendedKeyUsage(vec![ID_KP_TIME_STAMPING])).unwrap();
+    let tsa_cert = builder.build::<DerSignature>().unwrap();
+
+    TestPki { root, tsa_cert, tsa_key }
+}
+
+/// Helper: sign a TSTInfo answering `request` and wrap it in a granted response.
+fn issue_token(pki: &TestPki, request: &TimeStampReq) -> Vec<u8> {
+    let gen_time = chrono::Utc::now().format("%Y%m%d%H%M%S%.3fZ").to_string();
+    let tst = TstInfo {
+        version: 1,
+        policy: ObjectIdentifier::new_unwrap("1.2.3.4.1"),
+        message_imprint: request.message_imprint.clone(),
+        serial_number: der::asn1::Uint::new(&[0x01, 0x02, 0x03]).unwrap(),
+        gen_time: Any::new(Tag::GeneralizedTime, gen_time.into_bytes()).unwrap(),
+        accuracy: None,
+        ordering: false,
+        nonce: request.nonce.clone(),
+        tsa: None,
+        extensions: None,
+    };
+    let econtent = OctetString::new(tst.to_der().unwrap()).unwrap();
+    let eci = EncapsulatedContentInfo {
+        econtent_type: ID_CT_TST_INFO,
+        econtent: Some(Any::encode_from(&econtent).unwrap()),
+    };
+
+    let digest_alg = AlgorithmIdentifierOwned { oid: ID_SHA256, parameters: None };
+    let sid = SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+        issuer: pki.tsa_cert.tbs_certificate.issuer.clone(),
+        serial_number: pki.tsa_cert.tbs_certificate.serial_number.clone(),
+    });
+    let signer = SignerInfoBuilder::new(&pki.tsa_key, sid, digest_alg.clone(), &eci, None).unwrap();
+    let token = SignedDataBuilder::new(&eci)
+        .add_digest_algorithm(digest_alg)
+        .unwrap()
+        .add_certificate(CertificateChoices::Certificate(pki.tsa_cert.clone()))
+        .unwrap()
+        .add_signer_info::<SigningKey, DerSignature>(signer)
+        .unwrap()
+        .build()
+        .unwrap();
+
+    TimeStampResp {
+        status: PkiStatusInfo { status: 0, status_string: None, fail_info: None },
+        time_stamp_token: Some(token),
+    }
+    .to_der()
+    .unwrap()
+}
+
+/// Helper: a local TSA that grants every request (or rejects all if `reject`).
+fn spawn_mock_tsa(pki: TestPki, reject: bool) -> String {
+    let server = Server::http("127.0.0.1:0").expect("bind mock TSA");
+    let port = server.server_addr().to_ip().unwrap().port();
+
+    thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let mut body = Vec::new();
+            request.as_reader().read_to_end(&mut body).unwrap();
+            let reply = if reject {
+                TimeStampResp {
+                    status: PkiStatusInfo {
+                        status: 2,
+                        status_string: Some(vec!["unsupported algorithm".to_string()]),
+                        fail_info: None,
+                    },
+                    time_stamp_token: None,
+                }
+                .to_der()
+                .unwrap()
+            } else {
+                issue_token(&pki, &TimeStampReq::from_der(&body).unwrap())
+            };
+            request.respond(Response::from_data(reply)).unwrap();
+        }
+    });
+
+    format!("http://127.0.0.1:{}/tsa", port)
+}
+
+#[test]
+fn tsa_token_saved_and_verified_offline() {
+    let pki = make_pki("Sealed Test Root");
+    let root = pki.root.clone();
+    let url = spawn_mock_tsa(pki, false);
+
+    let tmp = std::env::temp_dir().join("sealed_test_tsa_mock");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).unwrap();
+
+    let sha256 = hex::encode(Sha256::digest(b"sealed image pixels"));
+    let record = timestamp_hash_tsa(&sha256, &tmp, &TsaConfig::new(&url)).expect("TSA request failed");
+    let tsa = record.tsa.expect("record should carry the TSA token");
+    assert_eq!(tsa.tsr_file, TSR_FILE);
+    assert_eq!(tsa.serial_number, "010203");
+    assert_eq!(record.status, "none");
+
+    let anchor_path = tmp.join("root.pem");
+    std::fs::write(&anchor_path, der::EncodePem::to_pem(&root, der::pem::LineEnding::LF).unwrap()).unwrap();
+    let anchor = load_trust_anchor(&anchor_path).unwrap();
+
+    let tsr = std::fs::read(tmp.join(TSR_FILE)).unwrap();
+    let result = verify_token(&tsr, &sha256, &anchor).unwrap();
+    assert!(result.is_verified(), "{:?}", result);
+    assert_eq!(result.policy, "1.2.3.4.1");
+
+    let other = hex::encode(Sha256::digest(b"other"));
+    let result = verify_token(&tsr, &other, &anchor).unwrap();
+    assert!(!result.imprint_matches);
+    assert!(!result.is_verified());
+
+    let stranger = make_pki("Unrelated Root").root;
+    let result = verify_token(&tsr, &sha256, &stranger).unwrap();
+    assert!(result.signature_valid);
+    assert!(!result.chain_trusted);
+
+    // Flipping a byte inside the signed TSTInfo breaks the CMS signature.
+    let mut tampered = tsr.clone();
+    let pos = tampered.windows(3).position(|w| w == [0x01, 0x02, 0x03]).unwrap();
+    tampered[pos + 2] ^= 0xff;
+    let result = verify_token(&tampered, &sha256, &anchor).unwrap();
+    assert!(!result.signature_valid);
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn tsa_rejection_is_an_error() {
+    let url = spawn_mock_tsa(make_pki("Sealed Test Root"), true);
+
+    let tmp = std::env::temp_dir().join("sealed_test_tsa_reject");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).unwrap();
+
+    let sha256 = hex::encode(Sha256::digest(b"sealed"));
+    let err = timestamp_hash_tsa(&sha256, &tmp, &TsaConfig::new(&url)).unwrap_err();
+    assert!(err.to_string().contains("unsupported algorithm"));
+    assert!(!tmp.join(TSR_FILE).exists());
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
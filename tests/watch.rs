@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use sealed::watch::{FolderWatcher, WatchState, WATCH_STATE_FILE};
+
+fn is_png(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "png")
+}
+
+fn set_mtime(path: &Path, secs_ago: u64) {
+    let file = std::fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() - Duration::from_secs(secs_ago)).unwrap();
+}
+
+fn names(ready: &[sealed::watch::ReadyFile]) -> Vec<&str> {
+    ready.iter().map(|f| f.name.as_str()).collect()
+}
+
+#[test]
+fn watcher_waits_for_stable_files_and_remembers_them() {
+    let dir = std::env::temp_dir().join("sealed_test_watch");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let state = dir.join(WATCH_STATE_FILE);
+
+    std::fs::write(dir.join("a.png"), b"first").unwrap();
+    std::fs::write(dir.join("notes.txt"), b"ignored").unwrap();
+    std::fs::write(dir.join(".hidden.png"), b"ignored").unwrap();
+
+    let mut watcher = FolderWatcher::new(&dir, &state, Duration::ZERO, is_png).unwrap();
+    // First sight only starts the settle clock.
+    assert!(watcher.poll().unwrap().is_empty());
+    assert!(watcher.is_settling());
+    let ready = watcher.poll().unwrap();
+    assert_eq!(names(&ready), ["a.png"]);
+    watcher.record(&ready[0], Ok(PathBuf::from("sealed/a"))).unwrap();
+    assert!(watcher.poll().unwrap().is_empty());
+    assert!(!watcher.is_settling());
+
+    // A file still being written is not ready until its size holds.
+    std::fs::write(dir.join("b.png"), b"par").unwrap();
+    assert!(watcher.poll().unwrap().is_empty());
+    std::fs::write(dir.join("b.png"), b"partial then complete").unwrap();
+    assert!(watcher.poll().unwrap().is_empty());
+    let ready = watcher.poll().unwrap();
+    assert_eq!(names(&ready), ["b.png"]);
+    watcher.record(&ready[0], Err("refused".to_string())).unwrap();
+
+    // A restart picks up the state and reseals nothing, failures included.
+    let mut watcher = FolderWatcher::new(&dir, &state, Duration::ZERO, is_png).unwrap();
+    assert!(watcher.poll().unwrap().is_empty());
+    assert!(watcher.poll().unwrap().is_empty());
+    let saved = WatchState::load(&state).unwrap();
+    assert_eq!(saved.files["a.png"].sealed_dir.as_deref(), Some(Path::new("sealed/a")));
+    assert_eq!(saved.files["b.png"].error.as_deref(), Some("refused"));
+
+    // Touched but identical: not resealed.
+    set_mtime(&dir.join("a.png"), 60);
+    watcher.poll().unwrap();
+    assert!(watcher.poll().unwrap().is_empty());
+    assert!(!watcher.is_settling());
+
+    // Changed content is sealed again.
+    std::fs::write(dir.join("a.png"), b"second").unwrap();
+    set_mtime(&dir.join("a.png"), 30);
+    assert!(watcher.poll().unwrap().is_empty());
+    assert_eq!(names(&watcher.poll().unwrap()), ["a.png"]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn watcher_honours_the_settle_time() {
+    let dir = std::env::temp_dir().join("sealed_test_watch_settle");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("slow.png"), b"data").unwrap();
+
+    let mut watcher = FolderWatcher::new(&dir, &dir.join(WATCH_STATE_FILE), Duration::from_millis(300), is_png).unwrap();
+    assert!(watcher.poll().unwrap().is_empty());
+    assert!(watcher.poll().unwrap().is_empty());
+    std::thread::sleep(Duration::from_millis(350));
+    assert_eq!(names(&watcher.poll().unwrap()), ["slow.png"]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn state_files_in_missing_directories_are_created() {
+    let dir = std::env::temp_dir().join("sealed_test_watch_state_dir");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.png"), b"data").unwrap();
+    let state = dir.join("state/nested").join(WATCH_STATE_FILE);
+
+    let mut watcher = FolderWatcher::new(&dir, &state, Duration::ZERO, is_png).unwrap();
+    watcher.poll().unwrap();
+    let ready = watcher.poll().unwrap();
+    watcher.record(&ready[0], Ok(PathBuf::from("sealed/a"))).unwrap();
+    assert!(WatchState::load(&state).unwrap().files.contains_key("a.png"));
+    assert!(!state.with_file_name(format!("{}.tmp", WATCH_STATE_FILE)).exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}